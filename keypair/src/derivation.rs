@@ -0,0 +1,374 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hierarchical deterministic derivation of secret keys from a seed, as defined in [SLIP-0010].
+//!
+//! For secp256k1 it's the same as [BIP32], Ed25519 supports only hardened indexes.
+//!
+//! [SLIP-0010]: https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+//! [BIP32]: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+use crate::error::DerivationError;
+use crate::key_pair::KeyFormat;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+/// Indexes starting from this one are hardened.
+pub const HARDENED_OFFSET: u32 = 1 << 31;
+
+const MIN_SEED_LEN: usize = 16;
+const MAX_SEED_LEN: usize = 64;
+
+/// Derivation path such as `m/44'/0'/0'/0/1`, hardened indexes are marked with `'` or `h`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Build a path from raw indexes, hardened ones include `HARDENED_OFFSET`.
+    pub fn new(indexes: Vec<u32>) -> Self {
+        Self(indexes)
+    }
+
+    pub fn indexes(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// Path to the child with the index, e.g. to derive a family of node keys.
+    pub fn child(&self, index: u32) -> Self {
+        let mut indexes = self.0.clone();
+        indexes.push(index);
+        Self(indexes)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = DerivationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DerivationError::InvalidPath(s.to_string());
+
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid());
+        }
+
+        parts
+            .map(|part| {
+                let (index, hardened) = match part.strip_suffix(['\'', 'h']) {
+                    Some(index) => (index, true),
+                    None => (part, false),
+                };
+                // leading signs are accepted by u32::from_str, but not by the path syntax
+                if !index.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid());
+                }
+
+                let index: u32 = index.parse().map_err(|_| invalid())?;
+                if index >= HARDENED_OFFSET {
+                    return Err(invalid());
+                }
+
+                Ok(if hardened {
+                    index + HARDENED_OFFSET
+                } else {
+                    index
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(DerivationPath)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if *index >= HARDENED_OFFSET {
+                write!(f, "/{}'", index - HARDENED_OFFSET)?;
+            } else {
+                write!(f, "/{index}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Secret key with its chain code
+struct ExtendedKey {
+    key: Zeroizing<[u8; 32]>,
+    chain_code: Zeroizing<[u8; 32]>,
+}
+
+impl ExtendedKey {
+    fn from_hmac(i: &[u8; 64]) -> Self {
+        let mut key = Zeroizing::new([0u8; 32]);
+        let mut chain_code = Zeroizing::new([0u8; 32]);
+        key.copy_from_slice(&i[..32]);
+        chain_code.copy_from_slice(&i[32..]);
+
+        Self { key, chain_code }
+    }
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for chunk in data {
+        mac.update(chunk);
+    }
+
+    Zeroizing::new(mac.finalize().into_bytes().into())
+}
+
+fn is_valid_secp256k1_key(key: &[u8]) -> bool {
+    libsecp256k1::SecretKey::parse_slice(key).is_ok()
+}
+
+fn master_key(seed: &[u8], format: KeyFormat) -> Result<ExtendedKey, DerivationError> {
+    let curve_key: &[u8] = match format {
+        KeyFormat::Ed25519 => b"ed25519 seed",
+        KeyFormat::Secp256k1 => b"Bitcoin seed",
+        format => return Err(DerivationError::UnsupportedKeyFormat(format.into())),
+    };
+
+    let mut i = hmac_sha512(curve_key, &[seed]);
+    // every 32 bytes are a valid Ed25519 key, but not a secp256k1 one
+    while format == KeyFormat::Secp256k1 && !is_valid_secp256k1_key(&i[..32]) {
+        i = hmac_sha512(curve_key, &[i.as_ref()]);
+    }
+
+    Ok(ExtendedKey::from_hmac(&i))
+}
+
+fn ed25519_child(parent: &ExtendedKey, index: u32) -> Result<ExtendedKey, DerivationError> {
+    if index < HARDENED_OFFSET {
+        return Err(DerivationError::NonHardenedIndex(index));
+    }
+
+    let i = hmac_sha512(
+        parent.chain_code.as_ref(),
+        &[&[0], parent.key.as_ref(), &index.to_be_bytes()],
+    );
+
+    Ok(ExtendedKey::from_hmac(&i))
+}
+
+fn secp256k1_child(parent: &ExtendedKey, index: u32) -> ExtendedKey {
+    let parent_key =
+        libsecp256k1::SecretKey::parse(&parent.key).expect("parent key was validated when derived");
+
+    let mut i = if index >= HARDENED_OFFSET {
+        hmac_sha512(
+            parent.chain_code.as_ref(),
+            &[&[0], parent.key.as_ref(), &index.to_be_bytes()],
+        )
+    } else {
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&parent_key);
+        hmac_sha512(
+            parent.chain_code.as_ref(),
+            &[&public_key.serialize_compressed(), &index.to_be_bytes()],
+        )
+    };
+
+    loop {
+        if let Ok(tweak) = libsecp256k1::SecretKey::parse_slice(&i[..32]) {
+            let mut key = parent_key;
+            if key.tweak_add_assign(&tweak).is_ok() {
+                let mut child = ExtendedKey::from_hmac(&i);
+                child.key = Zeroizing::new(key.serialize());
+                return child;
+            }
+        }
+
+        // the key is invalid, so derivation is repeated with the right half as proposed by SLIP-0010
+        i = hmac_sha512(
+            parent.chain_code.as_ref(),
+            &[&[1], &i[32..], &index.to_be_bytes()],
+        );
+    }
+}
+
+/// Derive a raw secret key of `format` from the seed along the path
+pub(crate) fn derive_secret_key(
+    seed: &[u8],
+    path: &DerivationPath,
+    format: KeyFormat,
+) -> Result<Zeroizing<[u8; 32]>, DerivationError> {
+    if !(MIN_SEED_LEN..=MAX_SEED_LEN).contains(&seed.len()) {
+        return Err(DerivationError::InvalidSeedLength(seed.len()));
+    }
+
+    let mut key = master_key(seed, format)?;
+    for index in path.indexes() {
+        key = match format {
+            KeyFormat::Ed25519 => ed25519_child(&key, *index)?,
+            _ => secp256k1_child(&key, *index),
+        };
+    }
+
+    Ok(key.key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn check_vectors(format: KeyFormat, seed: &str, vectors: &[(&str, &str)]) {
+        let seed = from_hex(seed);
+        for (path, key) in vectors {
+            let path = DerivationPath::from_str(path).unwrap();
+            let derived = derive_secret_key(&seed, &path, format).unwrap();
+            assert_eq!(derived.to_vec(), from_hex(key), "path {path}");
+        }
+    }
+
+    // SLIP-0010, test vector 1 for ed25519
+    #[test]
+    fn slip10_ed25519_vectors() {
+        check_vectors(
+            KeyFormat::Ed25519,
+            "000102030405060708090a0b0c0d0e0f",
+            &[
+                (
+                    "m",
+                    "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                ),
+                (
+                    "m/0'",
+                    "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+                ),
+                (
+                    "m/0'/1'",
+                    "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+                ),
+                (
+                    "m/0'/1'/2'/2'/1000000000'",
+                    "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+                ),
+            ],
+        );
+    }
+
+    // BIP32, test vector 1
+    #[test]
+    fn bip32_secp256k1_vectors() {
+        check_vectors(
+            KeyFormat::Secp256k1,
+            "000102030405060708090a0b0c0d0e0f",
+            &[
+                (
+                    "m",
+                    "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+                ),
+                (
+                    "m/0h",
+                    "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+                ),
+                (
+                    "m/0h/1",
+                    "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+                ),
+                (
+                    "m/0h/1/2h/2/1000000000",
+                    "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn ed25519_requires_hardened() {
+        let seed = [7u8; 32];
+        let path = DerivationPath::from_str("m/0'/1").unwrap();
+        assert!(matches!(
+            derive_secret_key(&seed, &path, KeyFormat::Ed25519),
+            Err(DerivationError::NonHardenedIndex(1))
+        ));
+        assert!(derive_secret_key(&seed, &path, KeyFormat::Secp256k1).is_ok());
+        assert!(matches!(
+            derive_secret_key(&seed, &path, KeyFormat::P256),
+            Err(DerivationError::UnsupportedKeyFormat(_))
+        ));
+        assert!(matches!(
+            derive_secret_key(&seed[..8], &path, KeyFormat::Secp256k1),
+            Err(DerivationError::InvalidSeedLength(8))
+        ));
+    }
+
+    #[test]
+    fn derivation_path_parse() {
+        let path = DerivationPath::from_str("m/44'/0h/7").unwrap();
+        assert_eq!(
+            path.indexes(),
+            &[44 + HARDENED_OFFSET, HARDENED_OFFSET, 7][..]
+        );
+        assert_eq!(path.to_string(), "m/44'/0'/7");
+        assert_eq!(path.child(3).to_string(), "m/44'/0'/7/3");
+        assert_eq!(DerivationPath::from_str("m").unwrap().indexes(), &[][..]);
+
+        for invalid in [
+            "",
+            "44'/0'",
+            "m/",
+            "m//1",
+            "m/-1",
+            "m/+1",
+            "m/1''",
+            "m/2147483648",
+        ] {
+            assert!(DerivationPath::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn keypair_family_from_mnemonic() {
+        use crate::mnemonic::Mnemonic;
+        use crate::KeyPair;
+
+        let mnemonic = Mnemonic::generate(24).unwrap();
+        let restored = Mnemonic::parse(&mnemonic.phrase()).unwrap();
+        let root = DerivationPath::from_str("m/44'/461'/0'").unwrap();
+
+        for format in [KeyFormat::Ed25519, KeyFormat::Secp256k1] {
+            let keys: Vec<_> = (0..3)
+                .map(|i| {
+                    let path = root.child(i + HARDENED_OFFSET);
+                    let kp = KeyPair::from_mnemonic(&mnemonic, "", &path, format).unwrap();
+                    let restored_kp = KeyPair::from_mnemonic(&restored, "", &path, format).unwrap();
+                    assert_eq!(kp.public(), restored_kp.public());
+                    kp.public()
+                })
+                .collect();
+            assert_ne!(keys[0], keys[1]);
+            assert_ne!(keys[1], keys[2]);
+
+            // passphrase changes the whole family
+            let path = root.child(HARDENED_OFFSET);
+            let kp = KeyPair::from_mnemonic(&mnemonic, "passphrase", &path, format).unwrap();
+            assert_ne!(kp.public(), keys[0]);
+        }
+    }
+}
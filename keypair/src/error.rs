@@ -43,6 +43,8 @@ pub enum DecodingError {
     Rsa,
     #[error("Failed to decode with secp256k1")]
     Secp256k1,
+    #[error("Failed to decode with P-256")]
+    P256,
     #[error("RSA keypair decoding is not supported yet")]
     KeypairDecodingIsNotSupported,
     #[error("Invalid type prefix")]
@@ -53,6 +55,29 @@ pub enum DecodingError {
     RawSignatureUnsupportedType(String),
     #[error("public key is not inlined in peer id: {0}")]
     PublicKeyNotInlined(String),
+    #[error("Failed to decode DER: {0}")]
+    Der(
+        #[from]
+        #[source]
+        der::Error,
+    ),
+    #[error("Key algorithm {0} is not supported")]
+    UnsupportedAlgorithm(String),
+    #[error("{0} public key can't be converted to libp2p key")]
+    Libp2pConversion(String),
+}
+
+/// An error during encoding of key material.
+#[derive(ThisError, Debug)]
+pub enum EncodingError {
+    #[error("Secret key of {0} keypair is not available for encoding")]
+    SecretNotAvailable(String),
+    #[error("Failed to encode DER: {0}")]
+    Der(
+        #[from]
+        #[source]
+        der::Error,
+    ),
 }
 
 /// An error during signing of a message.
@@ -72,6 +97,10 @@ pub enum SigningError {
         #[source]
         libsecp256k1::Error,
     ),
+    #[error("Failed to sign with P-256: {0}")]
+    P256(#[source] p256::ecdsa::Error),
+    #[error("EIP-191 signatures require a secp256k1 keypair, not {0}")]
+    Eip191UnsupportedKeyFormat(String),
 }
 
 /// An error during verification of a message.
@@ -84,6 +113,66 @@ pub enum VerificationError {
     #[error("Failed to verify signature {1} with {2} RSA public key: {0}")]
     Rsa(#[source] ring::error::Unspecified, String, String),
 
+    #[cfg(target_arch = "wasm32")]
+    #[error("Failed to verify signature {1} with {2} RSA public key: {0}")]
+    Rsa(#[source] rsa::Error, String, String),
+
     #[error("Failed to verify signature {1} with {2} secp256k1 public key: {0}")]
     Secp256k1(#[source] libsecp256k1::Error, String, String),
+
+    #[error("Failed to verify signature {1} with {2} P-256 public key: {0}")]
+    P256(#[source] p256::ecdsa::Error, String, String),
+
+    #[error("Public key can't be recovered from {0} signature")]
+    RecoveryIsNotSupported(String),
+}
+
+/// An error during encryption or decryption of a keystore.
+#[derive(ThisError, Debug)]
+pub enum KeystoreError {
+    #[error("Keystore is malformed")]
+    Malformed,
+    #[error("Keystore version {0} is not supported")]
+    UnsupportedVersion(u8),
+    #[error("Invalid key derivation parameters")]
+    InvalidParams,
+    #[error("Failed to encrypt the secret key")]
+    Encryption,
+    #[error("Failed to decrypt the secret key: incorrect password or corrupted keystore")]
+    Decryption,
+    #[error("Secret key of {0} keypair is not available for encryption")]
+    SecretNotAvailable(String),
+    #[error("{0}")]
+    Decoding(
+        #[from]
+        #[source]
+        DecodingError,
+    ),
+}
+
+/// An error during derivation of a keypair from a mnemonic or a seed.
+#[derive(ThisError, Debug)]
+pub enum DerivationError {
+    #[error("Invalid derivation path {0}")]
+    InvalidPath(String),
+    #[error("Index {0} is not hardened, only hardened derivation is supported for ed25519")]
+    NonHardenedIndex(u32),
+    #[error("Key derivation is not supported for {0} keys")]
+    UnsupportedKeyFormat(String),
+    #[error("Seed must be from 16 to 64 bytes long, got {0}")]
+    InvalidSeedLength(usize),
+    #[error("Mnemonic must have 12, 15, 18, 21 or 24 words, got {0}")]
+    InvalidWordCount(usize),
+    #[error("Invalid mnemonic: {0}")]
+    Mnemonic(
+        #[from]
+        #[source]
+        bip39::Error,
+    ),
+    #[error("{0}")]
+    Decoding(
+        #[from]
+        #[source]
+        DecodingError,
+    ),
 }
@@ -19,8 +19,16 @@
 // DEALINGS IN THE SOFTWARE.
 
 //! A node's network identity keys.
+use crate::derivation::{self, DerivationPath};
 use crate::ed25519;
-use crate::error::{DecodingError, Error, SigningError, VerificationError};
+use crate::error::{
+    DecodingError, DerivationError, EncodingError, Error, KeystoreError, SigningError,
+    VerificationError,
+};
+use crate::key_info;
+use crate::keystore::{self, KeystoreParams};
+use crate::mnemonic::Mnemonic;
+use crate::p256;
 use crate::public_key::PublicKey;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::rsa;
@@ -29,6 +37,7 @@ use crate::signature::Signature;
 use libp2p_identity::{KeyType, Keypair, PeerId};
 use std::convert::TryFrom;
 use std::str::FromStr;
+use zeroize::Zeroizing;
 
 /// Identity keypair of a node.
 ///
@@ -50,9 +59,9 @@ use std::str::FromStr;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeyFormat {
     Ed25519,
-    #[cfg(not(target_arch = "wasm32"))]
     Rsa,
     Secp256k1,
+    P256,
 }
 
 impl FromStr for KeyFormat {
@@ -63,7 +72,7 @@ impl FromStr for KeyFormat {
         match s {
             "ed25519" => Ok(KeyFormat::Ed25519),
             "secp256k1" => Ok(KeyFormat::Secp256k1),
-            #[cfg(not(target_arch = "wasm32"))]
+            "p256" => Ok(KeyFormat::P256),
             "rsa" => Ok(KeyFormat::Rsa),
             _ => Err(Error::InvalidKeyFormat(s.to_string())),
         }
@@ -76,9 +85,9 @@ impl TryFrom<u8> for KeyFormat {
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             0 => Ok(KeyFormat::Ed25519),
-            #[cfg(not(target_arch = "wasm32"))]
             1 => Ok(KeyFormat::Rsa),
             2 => Ok(KeyFormat::Secp256k1),
+            3 => Ok(KeyFormat::P256),
             _ => Err(DecodingError::InvalidTypeByte),
         }
     }
@@ -88,9 +97,9 @@ impl From<KeyFormat> for u8 {
     fn from(kf: KeyFormat) -> Self {
         match kf {
             KeyFormat::Ed25519 => 0,
-            #[cfg(not(target_arch = "wasm32"))]
             KeyFormat::Rsa => 1,
             KeyFormat::Secp256k1 => 2,
+            KeyFormat::P256 => 3,
         }
     }
 }
@@ -99,9 +108,9 @@ impl From<KeyFormat> for String {
     fn from(kf: KeyFormat) -> Self {
         match kf {
             KeyFormat::Ed25519 => "ed25519".to_string(),
-            #[cfg(not(target_arch = "wasm32"))]
             KeyFormat::Rsa => "rsa".to_string(),
             KeyFormat::Secp256k1 => "secp256k1".to_string(),
+            KeyFormat::P256 => "p256".to_string(),
         }
     }
 }
@@ -115,6 +124,8 @@ pub enum KeyPair {
     Rsa(rsa::Keypair),
     /// A Secp256k1 keypair.
     Secp256k1(secp256k1::Keypair),
+    /// A P-256 keypair.
+    P256(p256::Keypair),
 }
 
 impl KeyPair {
@@ -122,7 +133,7 @@ impl KeyPair {
         match format {
             KeyFormat::Ed25519 => KeyPair::generate_ed25519(),
             KeyFormat::Secp256k1 => KeyPair::generate_secp256k1(),
-            #[cfg(not(target_arch = "wasm32"))]
+            KeyFormat::P256 => KeyPair::generate_p256(),
             KeyFormat::Rsa => todo!("rsa generation is not supported yet!"),
         }
     }
@@ -137,6 +148,32 @@ impl KeyPair {
         KeyPair::Secp256k1(secp256k1::Keypair::generate())
     }
 
+    /// Generate a new P-256 keypair.
+    pub fn generate_p256() -> KeyPair {
+        KeyPair::P256(p256::Keypair::generate())
+    }
+
+    /// Derive an Ed25519 or Secp256k1 keypair from a seed along the path, see `derivation`.
+    pub fn from_seed(
+        seed: &[u8],
+        path: &DerivationPath,
+        format: KeyFormat,
+    ) -> Result<KeyPair, DerivationError> {
+        let secret = derivation::derive_secret_key(seed, path, format)?;
+        Ok(KeyPair::from_secret_key(secret.to_vec(), format)?)
+    }
+
+    /// Derive a keypair from the seed of a BIP39 mnemonic, so a family of keys
+    /// can be restored from a single phrase.
+    pub fn from_mnemonic(
+        mnemonic: &Mnemonic,
+        passphrase: &str,
+        path: &DerivationPath,
+        format: KeyFormat,
+    ) -> Result<KeyPair, DerivationError> {
+        KeyPair::from_seed(mnemonic.to_seed(passphrase).as_ref(), path, format)
+    }
+
     /// Decode an keypair from a DER-encoded secret key in PKCS#8 PrivateKeyInfo
     /// format (i.e. unencrypted) as defined in [RFC5208].
     ///
@@ -155,6 +192,38 @@ impl KeyPair {
             .map(|sk| KeyPair::Secp256k1(secp256k1::Keypair::from(sk)))
     }
 
+    /// Decode a keypair from a DER-encoded P-256 secret key in an ECPrivateKey
+    /// structure as defined in [RFC5915].
+    ///
+    /// [RFC5915]: https://tools.ietf.org/html/rfc5915
+    pub fn p256_from_der(der: &mut [u8]) -> Result<KeyPair, DecodingError> {
+        p256::SecretKey::from_der(der).map(|sk| KeyPair::P256(p256::Keypair::from(sk)))
+    }
+
+    /// Decode a keypair of any format from a DER-encoded PKCS#8 PrivateKeyInfo
+    /// as defined in [RFC5208], i.e. unencrypted.
+    ///
+    /// [RFC5208]: https://tools.ietf.org/html/rfc5208#section-5
+    pub fn from_pkcs8_der(der: &[u8]) -> Result<KeyPair, DecodingError> {
+        key_info::decode_private_key_info(der)
+    }
+
+    /// Encode the keypair as a DER PKCS#8 PrivateKeyInfo. RSA keypairs
+    /// can be encoded only if they were decoded from PKCS#8.
+    pub fn to_pkcs8_der(&self) -> Result<Zeroizing<Vec<u8>>, EncodingError> {
+        key_info::encode_private_key_info(self)
+    }
+
+    /// Decode a keypair from a PEM PKCS#8 PrivateKeyInfo ("PRIVATE KEY" label).
+    pub fn from_pkcs8_pem(pem: &str) -> Result<KeyPair, DecodingError> {
+        key_info::decode_private_key_pem(pem)
+    }
+
+    /// Encode the keypair as a PEM PKCS#8 PrivateKeyInfo, see `to_pkcs8_der`.
+    pub fn to_pkcs8_pem(&self) -> Result<Zeroizing<String>, EncodingError> {
+        key_info::encode_private_key_pem(self)
+    }
+
     /// Sign a message using the private key of this keypair, producing
     /// a signature that can be verified using the corresponding public key.
     pub fn sign(&self, msg: &[u8]) -> Result<Signature, SigningError> {
@@ -166,6 +235,22 @@ impl KeyPair {
             Secp256k1(ref pair) => Ok(Signature::Secp256k1(secp256k1::Signature(
                 pair.secret().sign(msg)?,
             ))),
+            P256(ref pair) => Ok(Signature::P256(p256::Signature(pair.secret().sign(msg)?))),
+        }
+    }
+
+    /// Sign a message like Ethereum `personal_sign`, see [EIP-191]. Such signatures
+    /// are verified by `PublicKey::verify` like any other. Only secp256k1 keypairs are supported.
+    ///
+    /// [EIP-191]: https://eips.ethereum.org/EIPS/eip-191
+    pub fn sign_eip191(&self, msg: &[u8]) -> Result<Signature, SigningError> {
+        match self {
+            KeyPair::Secp256k1(pair) => Ok(Signature::Eip191(secp256k1::Signature(
+                pair.secret().sign_eip191(msg),
+            ))),
+            _ => Err(SigningError::Eip191UnsupportedKeyFormat(
+                self.key_format().into(),
+            )),
         }
     }
 
@@ -178,6 +263,7 @@ impl KeyPair {
             #[cfg(not(target_arch = "wasm32"))]
             Rsa(_) => KeyFormat::Rsa,
             Secp256k1(_) => KeyFormat::Secp256k1,
+            P256(_) => KeyFormat::P256,
         }
     }
 
@@ -189,6 +275,7 @@ impl KeyPair {
             #[cfg(not(target_arch = "wasm32"))]
             Rsa(pair) => PublicKey::Rsa(pair.public()),
             Secp256k1(pair) => PublicKey::Secp256k1(pair.public().clone()),
+            P256(pair) => PublicKey::P256(pair.public().clone()),
         }
     }
 
@@ -199,6 +286,7 @@ impl KeyPair {
             #[cfg(not(target_arch = "wasm32"))]
             Rsa(_) => Err(eyre::eyre!("secret key is not available for RSA")),
             Secp256k1(pair) => Ok(pair.secret().to_bytes().to_vec()),
+            P256(pair) => Ok(pair.secret().to_bytes().to_vec()),
         }
     }
 
@@ -218,6 +306,7 @@ impl KeyPair {
             #[cfg(not(target_arch = "wasm32"))]
             Rsa(_) => todo!("rsa encoding is not supported yet!"),
             Secp256k1(kp) => kp.secret().to_bytes().to_vec(),
+            P256(kp) => kp.secret().to_bytes().to_vec(),
         }
     }
 
@@ -227,7 +316,7 @@ impl KeyPair {
         match format {
             KeyFormat::Ed25519 => Ok(Ed25519(ed25519::Keypair::decode(&mut bytes)?)),
             KeyFormat::Secp256k1 => Ok(Secp256k1(secp256k1::SecretKey::from_bytes(bytes)?.into())),
-            #[cfg(not(target_arch = "wasm32"))]
+            KeyFormat::P256 => Ok(P256(p256::SecretKey::from_bytes(bytes)?.into())),
             KeyFormat::Rsa => Err(DecodingError::KeypairDecodingIsNotSupported),
         }
     }
@@ -238,11 +327,51 @@ impl KeyPair {
         match format {
             KeyFormat::Ed25519 => Ok(Ed25519(ed25519::SecretKey::from_bytes(bytes)?.into())),
             KeyFormat::Secp256k1 => Ok(Secp256k1(secp256k1::SecretKey::from_bytes(bytes)?.into())),
-            #[cfg(not(target_arch = "wasm32"))]
+            KeyFormat::P256 => Ok(P256(p256::SecretKey::from_bytes(bytes)?.into())),
             KeyFormat::Rsa => Err(DecodingError::KeypairDecodingIsNotSupported),
         }
     }
 
+    /// Encrypt the secret key with a password, see `keystore` for the format.
+    pub fn to_encrypted(&self, password: &[u8]) -> Result<Vec<u8>, KeystoreError> {
+        self.to_encrypted_with(password, KeystoreParams::default())
+    }
+
+    /// Encrypt the secret key with a password and custom key derivation parameters.
+    pub fn to_encrypted_with(
+        &self,
+        password: &[u8],
+        params: KeystoreParams,
+    ) -> Result<Vec<u8>, KeystoreError> {
+        use KeyPair::*;
+
+        let secret = Zeroizing::new(match self {
+            Ed25519(pair) => pair.secret().0.to_vec(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Rsa(pair) => pair
+                .to_pkcs8()
+                .ok_or_else(|| KeystoreError::SecretNotAvailable(KeyFormat::Rsa.into()))?
+                .to_vec(),
+            Secp256k1(pair) => pair.secret().to_bytes().to_vec(),
+            P256(pair) => pair.secret().to_bytes().to_vec(),
+        });
+
+        keystore::encrypt(self.key_format(), &secret, password, params)
+    }
+
+    /// Decrypt a keypair encrypted by `to_encrypted`.
+    pub fn from_encrypted(bytes: &[u8], password: &[u8]) -> Result<Self, KeystoreError> {
+        let (format, secret) = keystore::decrypt(bytes, password)?;
+
+        match format {
+            #[cfg(not(target_arch = "wasm32"))]
+            KeyFormat::Rsa => Ok(KeyPair::rsa_from_pkcs8(&mut secret.to_vec())?),
+            #[cfg(target_arch = "wasm32")]
+            KeyFormat::Rsa => Err(DecodingError::KeypairDecodingIsNotSupported.into()),
+            _ => Ok(KeyPair::from_secret_key(secret.to_vec(), format)?),
+        }
+    }
+
     pub fn get_peer_id(&self) -> PeerId {
         self.public().to_peer_id()
     }
@@ -260,16 +389,19 @@ impl From<libp2p_identity::Keypair> for KeyPair {
                 #[cfg(not(target_arch = "wasm32"))]
                 KeyType::RSA => {
                     let kp = key.try_into_rsa()?;
-                    let raw_kp = unsafe {
-                        std::mem::transmute::<libp2p_identity::rsa::Keypair, rsa::Keypair>(kp)
-                    };
-                    Ok(KeyPair::Rsa(raw_kp))
+                    Ok(KeyPair::Rsa(rsa::Keypair::from(kp)))
                 }
                 KeyType::Secp256k1 => {
                     let kp = key.try_into_secp256k1()?;
                     let raw_kp = secp256k1::SecretKey::from_bytes(kp.secret().to_bytes())?;
                     Ok(KeyPair::Secp256k1(secp256k1::Keypair::from(raw_kp)))
                 }
+                KeyType::Ecdsa => {
+                    let kp = key.try_into_ecdsa()?;
+                    let raw_kp = p256::SecretKey::from_bytes(kp.secret().to_bytes())?;
+                    Ok(KeyPair::P256(p256::Keypair::from(raw_kp)))
+                }
+                #[cfg(target_arch = "wasm32")]
                 _ => unreachable!(),
             }
         }
@@ -289,11 +421,8 @@ impl From<KeyPair> for libp2p_identity::Keypair {
                     Ok(kp)
                 }
                 #[cfg(not(target_arch = "wasm32"))]
-                // safety: these Keypair structures are identical
                 KeyPair::Rsa(kp) => {
-                    let kp = unsafe {
-                        std::mem::transmute::<rsa::Keypair, libp2p_identity::rsa::Keypair>(kp)
-                    };
+                    let kp = libp2p_identity::rsa::Keypair::from(kp);
                     let kp = Keypair::from(kp);
                     Ok(kp)
                 }
@@ -305,6 +434,13 @@ impl From<KeyPair> for libp2p_identity::Keypair {
                     let kp = Keypair::from(kp);
                     Ok(kp)
                 }
+                KeyPair::P256(kp) => {
+                    let sk =
+                        libp2p_identity::ecdsa::SecretKey::try_from_bytes(kp.secret().to_bytes())?;
+                    let kp = libp2p_identity::ecdsa::Keypair::from(sk);
+                    let kp = Keypair::from(kp);
+                    Ok(kp)
+                }
             }
         }
         convert_keypair(key).expect("Could not convert key pair")
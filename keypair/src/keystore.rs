@@ -0,0 +1,245 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Password-encrypted keystore for keypairs.
+//!
+//! The secret key is encrypted with ChaCha20-Poly1305 under a key derived from
+//! the password by scrypt. Layout of the encoded keystore:
+//!
+//! ```text
+//! version (1) | key format (1) | log_n (1) | r (4, le) | p (4, le) | salt (16) | nonce (12) | ciphertext
+//! ```
+//!
+//! Everything before the ciphertext is authenticated, so the header can't be altered.
+//! RSA keypairs are stored as PKCS#8, keypairs of other formats as raw secret keys.
+use crate::error::KeystoreError;
+use crate::key_pair::KeyFormat;
+
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use rand::RngCore;
+use std::convert::TryFrom;
+use zeroize::Zeroizing;
+
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = 3 + 4 + 4 + SALT_LEN + NONCE_LEN;
+/// keystores asking for more than 1 GiB of scrypt work are refused,
+/// it is 2^20 with r = 8 and p = 1
+const MAX_COST: u64 = 1 << 30;
+
+/// Parameters of the scrypt key derivation, memory cost is `128 * r * 2^log_n` bytes
+/// and it's repeated `p` times. Their product is capped at 1 GiB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeystoreParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KeystoreParams {
+    fn default() -> Self {
+        Self {
+            log_n: scrypt::Params::RECOMMENDED_LOG_N,
+            r: scrypt::Params::RECOMMENDED_R,
+            p: scrypt::Params::RECOMMENDED_P,
+        }
+    }
+}
+
+fn derive_key(
+    password: &[u8],
+    salt: &[u8],
+    params: KeystoreParams,
+) -> Result<Zeroizing<[u8; 32]>, KeystoreError> {
+    // header isn't authenticated before the key is derived, so the cost is checked first
+    let cost = 1u64
+        .checked_shl(params.log_n.into())
+        .and_then(|n| n.checked_mul(128))
+        .and_then(|c| c.checked_mul(params.r.into()))
+        .and_then(|c| c.checked_mul(params.p.into()));
+    if !matches!(cost, Some(cost) if cost <= MAX_COST) {
+        return Err(KeystoreError::InvalidParams);
+    }
+
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|_| KeystoreError::InvalidParams)?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(password, salt, &scrypt_params, key.as_mut())
+        .map_err(|_| KeystoreError::InvalidParams)?;
+
+    Ok(key)
+}
+
+/// Encrypt the secret of a keypair in `format` with the password
+pub(crate) fn encrypt(
+    format: KeyFormat,
+    secret: &[u8],
+    password: &[u8],
+    params: KeystoreParams,
+) -> Result<Vec<u8>, KeystoreError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    let mut rng = rand::thread_rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let key = derive_key(password, &salt, params)?;
+
+    let mut result = Vec::with_capacity(HEADER_LEN + secret.len() + 16);
+    result.push(VERSION);
+    result.push(format.into());
+    result.push(params.log_n);
+    result.extend_from_slice(&params.r.to_le_bytes());
+    result.extend_from_slice(&params.p.to_le_bytes());
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&nonce);
+
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: secret,
+                aad: &result,
+            },
+        )
+        .map_err(|_| KeystoreError::Encryption)?;
+    result.extend(ciphertext);
+
+    Ok(result)
+}
+
+/// Decrypt the keystore with the password, returns the key format and the secret
+pub(crate) fn decrypt(
+    bytes: &[u8],
+    password: &[u8],
+) -> Result<(KeyFormat, Zeroizing<Vec<u8>>), KeystoreError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(KeystoreError::Malformed);
+    }
+    let (header, ciphertext) = bytes.split_at(HEADER_LEN);
+    if header[0] != VERSION {
+        return Err(KeystoreError::UnsupportedVersion(header[0]));
+    }
+
+    let format = KeyFormat::try_from(header[1])?;
+    let params = KeystoreParams {
+        log_n: header[2],
+        r: u32::from_le_bytes(header[3..7].try_into().expect("slice is 4 bytes")),
+        p: u32::from_le_bytes(header[7..11].try_into().expect("slice is 4 bytes")),
+    };
+    let salt = &header[11..11 + SALT_LEN];
+    let nonce = &header[11 + SALT_LEN..];
+
+    let key = derive_key(password, salt, params)?;
+    let secret = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| KeystoreError::Decryption)?;
+
+    Ok((format, Zeroizing::new(secret)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyPair;
+
+    /// cheap parameters, the default ones take seconds in debug builds
+    const PARAMS: KeystoreParams = KeystoreParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    fn round_trip(kp: KeyPair) {
+        let password = b"correct horse battery staple";
+        let keystore = kp.to_encrypted_with(password, PARAMS).unwrap();
+
+        let decrypted = KeyPair::from_encrypted(&keystore, password).unwrap();
+        assert_eq!(decrypted.public(), kp.public());
+        assert_eq!(decrypted.key_format(), kp.key_format());
+
+        assert!(matches!(
+            KeyPair::from_encrypted(&keystore, b"wrong password"),
+            Err(KeystoreError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn keystore_round_trip() {
+        round_trip(KeyPair::generate_ed25519());
+        round_trip(KeyPair::generate_secp256k1());
+        round_trip(KeyPair::generate_p256());
+        #[cfg(not(target_arch = "wasm32"))]
+        round_trip(
+            KeyPair::rsa_from_pkcs8(&mut include_bytes!("test/rsa-2048.pk8").to_vec()).unwrap(),
+        );
+    }
+
+    #[test]
+    fn keystore_tampered() {
+        let kp = KeyPair::generate_ed25519();
+        let keystore = kp.to_encrypted_with(b"password", PARAMS).unwrap();
+
+        // header is authenticated
+        let mut tampered = keystore.clone();
+        tampered[11] ^= 1;
+        assert!(matches!(
+            KeyPair::from_encrypted(&tampered, b"password"),
+            Err(KeystoreError::Decryption)
+        ));
+
+        let mut tampered = keystore.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(KeyPair::from_encrypted(&tampered, b"password").is_err());
+
+        let mut tampered = keystore.clone();
+        tampered[2] = 21;
+        assert!(matches!(
+            KeyPair::from_encrypted(&tampered, b"password"),
+            Err(KeystoreError::InvalidParams)
+        ));
+
+        // r and p are 4 bytes each, huge values are refused before scrypt runs
+        for offset in [3, 7] {
+            let mut tampered = keystore.clone();
+            tampered[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(matches!(
+                KeyPair::from_encrypted(&tampered, b"password"),
+                Err(KeystoreError::InvalidParams)
+            ));
+        }
+
+        let mut tampered = keystore.clone();
+        tampered[0] = VERSION + 1;
+        assert!(matches!(
+            KeyPair::from_encrypted(&tampered, b"password"),
+            Err(KeystoreError::UnsupportedVersion(_))
+        ));
+
+        assert!(matches!(
+            KeyPair::from_encrypted(&keystore[..HEADER_LEN - 1], b"password"),
+            Err(KeystoreError::Malformed)
+        ));
+    }
+}
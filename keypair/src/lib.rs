@@ -26,11 +26,15 @@
     unreachable_patterns
 )]
 
+pub mod derivation;
 mod ed25519;
 pub mod error;
+mod key_info;
 pub mod key_pair;
+pub mod keystore;
+pub mod mnemonic;
+mod p256;
 pub mod public_key;
-#[cfg(not(target_arch = "wasm32"))]
 mod rsa;
 mod secp256k1;
 pub mod signature;
@@ -0,0 +1,118 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! [BIP39] mnemonic phrases in English, used as a backup of a seed for key derivation.
+//!
+//! [BIP39]: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+use crate::error::DerivationError;
+
+use core::fmt;
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+/// A BIP39 mnemonic phrase.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic(bip39::Mnemonic);
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mnemonic")
+    }
+}
+
+impl Mnemonic {
+    /// Generate a random mnemonic of 12, 15, 18, 21 or 24 words.
+    pub fn generate(word_count: usize) -> Result<Self, DerivationError> {
+        if ![12, 15, 18, 21, 24].contains(&word_count) {
+            return Err(DerivationError::InvalidWordCount(word_count));
+        }
+
+        // every 3 words encode 32 bits of entropy
+        let mut entropy = Zeroizing::new([0u8; 32]);
+        let entropy = &mut entropy[..word_count / 3 * 4];
+        rand::thread_rng().fill_bytes(entropy);
+
+        Ok(Self(bip39::Mnemonic::from_entropy(entropy)?))
+    }
+
+    /// Parse a mnemonic phrase, checking the words and the checksum.
+    /// The phrase is NFKD normalized first.
+    pub fn parse(phrase: &str) -> Result<Self, DerivationError> {
+        Ok(Self(bip39::Mnemonic::parse(phrase)?))
+    }
+
+    /// Words of the mnemonic separated by spaces.
+    pub fn phrase(&self) -> Zeroizing<String> {
+        Zeroizing::new(self.0.to_string())
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.0.word_count()
+    }
+
+    /// Seed for key derivation, the passphrase may be empty and is NFKD normalized.
+    pub fn to_seed(&self, passphrase: &str) -> Zeroizing<[u8; 64]> {
+        Zeroizing::new(self.0.to_seed(passphrase))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mnemonic_known_vector() {
+        // the first vector of the reference implementation
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::parse(phrase).unwrap();
+        assert_eq!(mnemonic.phrase().as_str(), phrase);
+
+        let seed = mnemonic.to_seed("TREZOR");
+        let expected = "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04";
+        let seed_hex: String = seed.iter().map(|b| format!("{b:02x}")).collect();
+        assert_eq!(seed_hex, expected);
+    }
+
+    #[test]
+    fn mnemonic_non_ascii_passphrase() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::parse(phrase).unwrap();
+
+        // the same passphrase composed, decomposed and with a compatibility character
+        let expected = "054af481e35ce5781494200f8a9e6677b5f9b8e17293bd26fc9ff01948f362ee66387c26ae827813d126b3a897b5ec298495720ba5f3eb2d17583bd740ea4b2c";
+        for passphrase in ["\u{e9}t\u{e9} \u{2460}", "e\u{301}te\u{301} 1"] {
+            let seed = mnemonic.to_seed(passphrase);
+            let seed_hex: String = seed.iter().map(|b| format!("{b:02x}")).collect();
+            assert_eq!(seed_hex, expected);
+        }
+    }
+
+    #[test]
+    fn mnemonic_generate_parse() {
+        for word_count in [12, 15, 18, 21, 24] {
+            let mnemonic = Mnemonic::generate(word_count).unwrap();
+            assert_eq!(mnemonic.word_count(), word_count);
+            assert_eq!(Mnemonic::parse(&mnemonic.phrase()).unwrap(), mnemonic);
+        }
+
+        assert!(matches!(
+            Mnemonic::generate(13),
+            Err(DerivationError::InvalidWordCount(13))
+        ));
+        // wrong checksum
+        assert!(Mnemonic::parse("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! NIST P-256 keys.
+use crate::error::{DecodingError, SigningError, VerificationError};
+
+use ::p256::ecdsa::signature::{Signer, Verifier};
+use ::p256::ecdsa::{DerSignature, SigningKey, VerifyingKey};
+use core::fmt;
+use serde::de::Error as SerdeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_bytes::{ByteBuf as SerdeByteBuf, Bytes as SerdeBytes};
+use zeroize::Zeroize;
+
+/// A P-256 keypair.
+#[derive(Clone)]
+pub struct Keypair {
+    secret: SecretKey,
+    public: PublicKey,
+}
+
+impl Keypair {
+    /// Generate a new P-256 `Keypair`.
+    pub fn generate() -> Self {
+        Keypair::from(SecretKey::generate())
+    }
+
+    /// Get the public key of this keypair.
+    pub fn public(&self) -> &PublicKey {
+        &self.public
+    }
+
+    /// Get the secret key of this keypair.
+    pub fn secret(&self) -> &SecretKey {
+        &self.secret
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair")
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+/// Promote a P-256 secret key into a keypair.
+impl From<SecretKey> for Keypair {
+    fn from(secret: SecretKey) -> Self {
+        let public = PublicKey(*secret.0.verifying_key());
+        Keypair { secret, public }
+    }
+}
+
+/// Demote a P-256 keypair into a secret key.
+impl From<Keypair> for SecretKey {
+    fn from(kp: Keypair) -> Self {
+        kp.secret
+    }
+}
+
+/// A P-256 secret key.
+#[derive(Clone)]
+pub struct SecretKey(SigningKey);
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey")
+    }
+}
+
+impl SecretKey {
+    /// Generate a new P-256 secret key.
+    pub fn generate() -> Self {
+        SecretKey(SigningKey::random(&mut rand::thread_rng()))
+    }
+
+    /// Create a secret key from a byte slice, zeroing the slice on success.
+    /// If the bytes do not constitute a valid P-256 secret key, an
+    /// error is returned.
+    pub fn from_bytes(mut sk: impl AsMut<[u8]>) -> Result<Self, DecodingError> {
+        let sk_bytes = sk.as_mut();
+        let secret = SigningKey::from_slice(sk_bytes).map_err(|_| DecodingError::P256)?;
+        sk_bytes.zeroize();
+        Ok(SecretKey(secret))
+    }
+
+    /// Decode a DER-encoded P-256 secret key in an ECPrivateKey
+    /// structure as defined in [RFC5915].
+    ///
+    /// [RFC5915]: https://tools.ietf.org/html/rfc5915
+    pub fn from_der(mut der: impl AsMut<[u8]>) -> Result<SecretKey, DecodingError> {
+        let der_obj = der.as_mut();
+        let secret = ::p256::SecretKey::from_sec1_der(der_obj).map_err(|_| DecodingError::P256)?;
+        der_obj.zeroize();
+        Ok(SecretKey(secret.into()))
+    }
+
+    /// Sign a message with this secret key, producing a DER-encoded
+    /// ECDSA signature over its SHA-256 digest, as defined in [RFC3279].
+    ///
+    /// [RFC3279]: https://tools.ietf.org/html/rfc3279#section-2.2.3
+    pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        let signature: DerSignature = self.0.try_sign(msg).map_err(SigningError::P256)?;
+        Ok(signature.as_bytes().to_vec())
+    }
+
+    /// Returns the raw bytes of the secret key.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes().into()
+    }
+}
+
+/// A P-256 public key.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PublicKey(VerifyingKey);
+
+impl PublicKey {
+    /// Verify the P-256 DER-encoded signature on a message using the public key.
+    pub fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerificationError> {
+        ::p256::ecdsa::Signature::from_der(sig)
+            .and_then(|s| self.0.verify(msg, &s))
+            .map_err(|e| {
+                VerificationError::P256(
+                    e,
+                    bs58::encode(sig).into_string(),
+                    bs58::encode(self.encode()).into_string(),
+                )
+            })
+    }
+
+    /// Encode the public key in compressed form, i.e. with one coordinate
+    /// represented by a single bit.
+    pub fn encode(&self) -> [u8; 33] {
+        let mut bytes = [0u8; 33];
+        bytes.copy_from_slice(self.0.to_encoded_point(true).as_bytes());
+        bytes
+    }
+
+    /// Encode the public key in uncompressed form.
+    pub fn encode_uncompressed(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes.copy_from_slice(self.0.to_encoded_point(false).as_bytes());
+        bytes
+    }
+
+    /// Decode a public key from a byte slice in the SEC1 format,
+    /// either compressed as produced by `encode` or uncompressed.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodingError> {
+        VerifyingKey::from_sec1_bytes(bytes)
+            .map_err(|_| DecodingError::P256)
+            .map(PublicKey)
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SerdeBytes::new(self.encode().as_slice()).serialize(serializer)
+    }
+}
+
+impl<'d> Deserialize<'d> for PublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'d>,
+    {
+        let bytes = <SerdeByteBuf>::deserialize(deserializer)?;
+        PublicKey::decode(bytes.as_slice()).map_err(SerdeError::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signature(pub Vec<u8>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{key_pair, KeyFormat};
+    use quickcheck::QuickCheck;
+
+    fn eq_keypairs(kp1: key_pair::KeyPair, kp2: key_pair::KeyPair) -> bool {
+        kp1.public() == kp2.public() && kp1.secret().unwrap() == kp2.secret().unwrap()
+    }
+
+    #[test]
+    fn p256_secret_from_bytes() {
+        let sk1 = SecretKey::generate();
+        let mut sk_bytes = sk1.to_bytes();
+        let sk2 = SecretKey::from_bytes(&mut sk_bytes).unwrap();
+        assert_eq!(sk1.to_bytes(), sk2.to_bytes());
+        assert_eq!(sk_bytes, [0; 32]);
+    }
+
+    #[test]
+    fn p256_sign_verify() {
+        let kp = Keypair::generate();
+        let msg = b"hello world";
+        let sig = kp.secret().sign(msg).unwrap();
+        assert!(kp.public().verify(msg, &sig).is_ok());
+        assert!(kp.public().verify(b"another message", &sig).is_err());
+
+        let uncompressed = PublicKey::decode(&kp.public().encode_uncompressed()).unwrap();
+        assert_eq!(&uncompressed, kp.public());
+    }
+
+    #[test]
+    fn p256_keypair_encode_decode() {
+        fn prop() -> bool {
+            let kp1 = key_pair::KeyPair::generate(KeyFormat::P256);
+            let kp1_enc = libp2p_identity::Keypair::from(kp1.clone());
+            let kp2 = key_pair::KeyPair::from(kp1_enc);
+            eq_keypairs(kp1, kp2)
+        }
+        QuickCheck::new().tests(10).quickcheck(prop as fn() -> _);
+    }
+}
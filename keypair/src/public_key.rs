@@ -15,12 +15,13 @@
  */
 use crate::ed25519;
 use crate::error::{DecodingError, VerificationError};
-#[cfg(not(target_arch = "wasm32"))]
+use crate::key_info;
 use crate::rsa;
 use crate::secp256k1;
 use crate::signature::Signature;
 
 use crate::key_pair::KeyFormat;
+use crate::p256;
 use libp2p_identity::{KeyType, PeerId};
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
@@ -30,11 +31,12 @@ use std::convert::TryFrom;
 pub enum PublicKey {
     /// A public Ed25519 key.
     Ed25519(ed25519::PublicKey),
-    #[cfg(not(target_arch = "wasm32"))]
     /// A public RSA key.
     Rsa(rsa::PublicKey),
     /// A public Secp256k1 key.
     Secp256k1(secp256k1::PublicKey),
+    /// A public P-256 key.
+    P256(p256::PublicKey),
 }
 
 impl PublicKey {
@@ -45,11 +47,16 @@ impl PublicKey {
     // TODO: add VerificationError
     pub fn verify(&self, msg: &[u8], sig: &Signature) -> Result<(), VerificationError> {
         use PublicKey::*;
+
+        if let (Secp256k1(pk), Signature::Eip191(sig)) = (self, sig) {
+            return pk.verify_eip191(msg, &sig.0);
+        }
+
         match self {
             Ed25519(pk) => pk.verify(msg, sig.to_vec()),
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(pk) => pk.verify(msg, sig.to_vec()),
             Secp256k1(pk) => pk.verify(msg, sig.to_vec()),
+            P256(pk) => pk.verify(msg, sig.to_vec()),
         }
     }
 
@@ -59,9 +66,9 @@ impl PublicKey {
 
         match self {
             Ed25519(pk) => result.extend(pk.encode().to_vec()),
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(pk) => result.extend(pk.to_pkcs1()),
             Secp256k1(pk) => result.extend(pk.encode().to_vec()),
+            P256(pk) => result.extend(pk.encode().to_vec()),
         };
 
         result
@@ -70,13 +77,56 @@ impl PublicKey {
     pub fn decode(bytes: &[u8]) -> Result<PublicKey, DecodingError> {
         match KeyFormat::try_from(bytes[0])? {
             KeyFormat::Ed25519 => Ok(PublicKey::Ed25519(ed25519::PublicKey::decode(&bytes[1..])?)),
-            #[cfg(not(target_arch = "wasm32"))]
             KeyFormat::Rsa => Ok(PublicKey::Rsa(rsa::PublicKey::from_pkcs1(
                 bytes[1..].to_owned(),
             )?)),
             KeyFormat::Secp256k1 => Ok(PublicKey::Secp256k1(secp256k1::PublicKey::decode(
                 &bytes[1..],
             )?)),
+            KeyFormat::P256 => Ok(PublicKey::P256(p256::PublicKey::decode(&bytes[1..])?)),
+        }
+    }
+
+    /// Encode the public key in DER as a X.509 SubjectPublicKeyInfo structure,
+    /// as defined in [RFC5280].
+    ///
+    /// [RFC5280]: https://tools.ietf.org/html/rfc5280#section-4.1
+    pub fn to_spki_der(&self) -> Vec<u8> {
+        key_info::encode_public_key_info(self)
+    }
+
+    /// Decode a public key of any format from a DER-encoded SubjectPublicKeyInfo.
+    pub fn from_spki_der(der: &[u8]) -> Result<PublicKey, DecodingError> {
+        key_info::decode_public_key_info(der)
+    }
+
+    /// Encode the public key as a PEM SubjectPublicKeyInfo ("PUBLIC KEY" label).
+    pub fn to_spki_pem(&self) -> String {
+        key_info::encode_public_key_pem(self)
+    }
+
+    /// Decode a public key from a PEM SubjectPublicKeyInfo.
+    pub fn from_spki_pem(pem: &str) -> Result<PublicKey, DecodingError> {
+        key_info::decode_public_key_pem(pem)
+    }
+
+    /// Recover the secp256k1 public key that produced an EIP-191 signature.
+    pub fn recover_eip191(msg: &[u8], sig: &Signature) -> Result<PublicKey, VerificationError> {
+        match sig {
+            Signature::Eip191(sig) => {
+                secp256k1::PublicKey::recover_eip191(msg, &sig.0).map(PublicKey::Secp256k1)
+            }
+            _ => Err(VerificationError::RecoveryIsNotSupported(
+                sig.get_type_name(),
+            )),
+        }
+    }
+
+    /// EIP-55 checksummed Ethereum address of a secp256k1 public key, e.g. `0x5aAe...`
+    pub fn to_eth_address(&self) -> Option<String> {
+        match self {
+            PublicKey::Secp256k1(pk) => Some(secp256k1::to_checksum_address(&pk.to_eth_address())),
+            _ => None,
         }
     }
 
@@ -84,9 +134,9 @@ impl PublicKey {
         use PublicKey::*;
         match self {
             Ed25519(_) => KeyFormat::Ed25519.into(),
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(_) => KeyFormat::Rsa.into(),
             Secp256k1(_) => KeyFormat::Secp256k1.into(),
+            P256(_) => KeyFormat::P256.into(),
         }
     }
 
@@ -102,14 +152,22 @@ impl PublicKey {
 
         match self {
             Ed25519(pk) => pk.encode().to_vec(),
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(pk) => pk.to_pkcs1().to_vec(),
             Secp256k1(pk) => pk.encode().to_vec(),
+            P256(pk) => pk.encode().to_vec(),
         }
     }
 
     pub fn to_peer_id(&self) -> PeerId {
-        PeerId::from_public_key(&self.clone().into())
+        // libp2p supports RSA keys only on native targets
+        #[cfg(target_arch = "wasm32")]
+        if let PublicKey::Rsa(pk) = self {
+            return pk.to_peer_id();
+        }
+
+        let pk = libp2p_identity::PublicKey::try_from(self.clone())
+            .expect("key was decoded, so it converts to libp2p key");
+        PeerId::from_public_key(&pk)
     }
 
     pub fn get_key_format(&self) -> KeyFormat {
@@ -117,9 +175,9 @@ impl PublicKey {
 
         match self {
             Ed25519(_) => KeyFormat::Ed25519,
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(_) => KeyFormat::Rsa,
             Secp256k1(_) => KeyFormat::Secp256k1,
+            P256(_) => KeyFormat::P256,
         }
     }
 }
@@ -144,6 +202,12 @@ impl From<libp2p_identity::PublicKey> for PublicKey {
                     let raw_pk = secp256k1::PublicKey::decode(&pk.to_bytes())?;
                     Ok(PublicKey::Secp256k1(raw_pk))
                 }
+                KeyType::Ecdsa => {
+                    let pk = key.try_into_ecdsa()?;
+                    let raw_pk = p256::PublicKey::decode(&pk.to_bytes())?;
+                    Ok(PublicKey::P256(raw_pk))
+                }
+                #[cfg(target_arch = "wasm32")]
                 _ => unreachable!(),
             }
         }
@@ -152,32 +216,39 @@ impl From<libp2p_identity::PublicKey> for PublicKey {
     }
 }
 
-impl From<PublicKey> for libp2p_identity::PublicKey {
-    fn from(key: PublicKey) -> Self {
-        fn convert_key(key: PublicKey) -> eyre::Result<libp2p_identity::PublicKey> {
-            match key {
-                PublicKey::Ed25519(key) => {
-                    let raw_pk =
-                        libp2p_identity::ed25519::PublicKey::try_from_bytes(&key.encode())?;
-                    let pk = libp2p_identity::PublicKey::from(raw_pk);
-                    Ok(pk)
-                }
-                #[cfg(not(target_arch = "wasm32"))]
-                PublicKey::Rsa(key) => {
-                    let raw_pk =
-                        libp2p_identity::rsa::PublicKey::try_decode_x509(&key.encode_x509())?;
-                    let pk = libp2p_identity::PublicKey::from(raw_pk);
-                    Ok(pk)
-                }
-                PublicKey::Secp256k1(key) => {
-                    let raw_pk =
-                        libp2p_identity::secp256k1::PublicKey::try_from_bytes(&key.encode())?;
-                    let pk = libp2p_identity::PublicKey::from(raw_pk);
-                    Ok(pk)
-                }
+impl TryFrom<PublicKey> for libp2p_identity::PublicKey {
+    type Error = DecodingError;
+
+    fn try_from(key: PublicKey) -> Result<Self, Self::Error> {
+        let key_format: String = key.get_key_format().into();
+        let conversion_error = |_| DecodingError::Libp2pConversion(key_format.clone());
+
+        match key {
+            PublicKey::Ed25519(key) => {
+                let raw_pk = libp2p_identity::ed25519::PublicKey::try_from_bytes(&key.encode())
+                    .map_err(conversion_error)?;
+                Ok(libp2p_identity::PublicKey::from(raw_pk))
+            }
+            // libp2p supports RSA keys only on native targets
+            #[cfg(not(target_arch = "wasm32"))]
+            PublicKey::Rsa(key) => {
+                let raw_pk = libp2p_identity::rsa::PublicKey::try_decode_x509(&key.encode_x509())
+                    .map_err(conversion_error)?;
+                Ok(libp2p_identity::PublicKey::from(raw_pk))
+            }
+            #[cfg(target_arch = "wasm32")]
+            PublicKey::Rsa(_) => Err(DecodingError::Libp2pConversion(key_format)),
+            PublicKey::Secp256k1(key) => {
+                let raw_pk = libp2p_identity::secp256k1::PublicKey::try_from_bytes(&key.encode())
+                    .map_err(conversion_error)?;
+                Ok(libp2p_identity::PublicKey::from(raw_pk))
+            }
+            PublicKey::P256(key) => {
+                let raw_pk = libp2p_identity::ecdsa::PublicKey::try_from_bytes(&key.encode())
+                    .map_err(conversion_error)?;
+                Ok(libp2p_identity::PublicKey::from(raw_pk))
             }
         }
-        convert_key(key).expect("Could not convert key")
     }
 }
 
@@ -224,14 +295,59 @@ mod tests {
         assert_eq!(pk, PublicKey::decode(&encoded_pk).unwrap());
     }
 
+    #[test]
+    fn public_key_encode_decode_p256() {
+        let kp = KeyPair::generate_p256();
+        let pk = kp.public();
+        let encoded_pk = pk.encode();
+        assert_eq!(pk, PublicKey::decode(&encoded_pk).unwrap());
+    }
+
+    #[test]
+    fn public_key_peer_id_p256() {
+        let kp = KeyPair::generate_p256();
+        let fluence_pk = kp.public();
+        let libp2p_pk = libp2p_identity::PublicKey::try_from(fluence_pk.clone()).unwrap();
+        assert_eq!(PublicKey::from(libp2p_pk.clone()), fluence_pk);
+        assert_eq!(fluence_pk.to_peer_id(), PeerId::from_public_key(&libp2p_pk));
+    }
+
+    #[test]
+    fn public_key_peer_id_rsa() {
+        let kp =
+            KeyPair::rsa_from_pkcs8(&mut include_bytes!("test/rsa-2048.pk8").to_vec()).unwrap();
+        let fluence_pk = kp.public();
+        let libp2p_pk = libp2p_identity::PublicKey::try_from(fluence_pk.clone()).unwrap();
+        assert_eq!(PublicKey::from(libp2p_pk.clone()), fluence_pk);
+        assert_eq!(fluence_pk.to_peer_id(), PeerId::from_public_key(&libp2p_pk));
+    }
+
     #[test]
     fn public_key_peer_id_conversions() {
         let kp = KeyPair::generate_secp256k1();
         let fluence_pk = kp.public();
-        let libp2p_pk: libp2p_identity::PublicKey = fluence_pk.clone().into();
+        let libp2p_pk = libp2p_identity::PublicKey::try_from(fluence_pk.clone()).unwrap();
         let peer_id = PeerId::from_public_key(&libp2p_pk);
         let fluence_pk_converted = PublicKey::try_from(peer_id).unwrap();
 
         assert_eq!(fluence_pk, fluence_pk_converted);
     }
+
+    #[test]
+    fn public_key_eip191() {
+        let kp = KeyPair::generate_secp256k1();
+        let signature = kp.sign_eip191(b"message").unwrap();
+        assert!(kp.public().verify(b"message", &signature).is_ok());
+        assert_eq!(
+            PublicKey::recover_eip191(b"message", &signature).unwrap(),
+            kp.public()
+        );
+        assert_eq!(kp.public().to_eth_address().unwrap().len(), 42);
+
+        let kp = KeyPair::generate_ed25519();
+        assert!(kp.sign_eip191(b"message").is_err());
+        assert!(kp.public().to_eth_address().is_none());
+        let signature = kp.sign(b"message").unwrap();
+        assert!(PublicKey::recover_eip191(b"message", &signature).is_err());
+    }
 }
@@ -19,24 +19,46 @@
 // DEALINGS IN THE SOFTWARE.
 
 //! RSA keys.
-use crate::error::{DecodingError, SigningError, VerificationError};
+//!
+//! Keypairs are backed by `ring` and available only on native targets.
+//! Public keys are available everywhere, on wasm32 signatures are verified
+//! by the pure-Rust `rsa` crate.
+use crate::error::{DecodingError, VerificationError};
 
 use asn1_der::{Asn1Der, Asn1DerError, DerObject, DerTag, DerValue, FromDerObject, IntoDerObject};
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::SigningError;
+#[cfg(not(target_arch = "wasm32"))]
 use ring::rand::SystemRandom;
+#[cfg(not(target_arch = "wasm32"))]
 use ring::signature::KeyPair;
+#[cfg(not(target_arch = "wasm32"))]
 use ring::signature::{self, RsaKeyPair, RSA_PKCS1_2048_8192_SHA256, RSA_PKCS1_SHA256};
-use serde::{Deserialize, Serialize};
-use std::{
-    fmt::{self, Write},
-    sync::Arc,
-};
-use zeroize::Zeroize;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use zeroize::{Zeroize, Zeroizing};
+
+/// Modulus sizes accepted for verification, the same as ring's `RSA_PKCS1_2048_8192_SHA256`
+#[cfg(any(target_arch = "wasm32", test))]
+const MIN_MODULUS_BITS: usize = 2048;
+#[cfg(any(target_arch = "wasm32", test))]
+const MAX_MODULUS_BITS: usize = 8192;
 
 /// An RSA keypair.
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Clone)]
-pub struct Keypair(Arc<RsaKeyPair>);
+pub struct Keypair {
+    inner: Arc<RsaKeyPair>,
+    /// the encoding the keypair was decoded from, ring can't encode keypairs
+    pkcs8: Option<Arc<Zeroizing<Vec<u8>>>>,
+}
 
+#[cfg(not(target_arch = "wasm32"))]
 impl Keypair {
     /// Decode an RSA keypair from a DER-encoded private key in PKCS#8 PrivateKeyInfo
     /// format (i.e. unencrypted) as defined in [RFC5208].
@@ -44,32 +66,63 @@ impl Keypair {
     /// [RFC5208]: https://tools.ietf.org/html/rfc5208#section-5
     pub fn from_pkcs8(der: &mut [u8]) -> Result<Self, DecodingError> {
         let kp = RsaKeyPair::from_pkcs8(der).map_err(|_| DecodingError::Rsa)?;
+        let pkcs8 = Zeroizing::new(der.to_vec());
         der.zeroize();
-        Ok(Keypair(Arc::new(kp)))
+        Ok(Keypair {
+            inner: Arc::new(kp),
+            pkcs8: Some(Arc::new(pkcs8)),
+        })
+    }
+
+    /// PKCS#8 encoding of the keypair, if it was decoded from one.
+    pub fn to_pkcs8(&self) -> Option<&[u8]> {
+        self.pkcs8.as_ref().map(|der| der.as_slice())
     }
 
     /// Get the public key from the keypair.
     pub fn public(&self) -> PublicKey {
-        PublicKey(self.0.public_key().as_ref().to_vec())
+        PublicKey(self.inner.public_key().as_ref().to_vec())
     }
 
     /// Sign a message with this keypair.
     pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, SigningError> {
-        let mut signature = vec![0; self.0.public_modulus_len()];
+        let mut signature = vec![0; self.inner.public_modulus_len()];
         let rng = SystemRandom::new();
-        match self.0.sign(&RSA_PKCS1_SHA256, &rng, data, &mut signature) {
+        match self
+            .inner
+            .sign(&RSA_PKCS1_SHA256, &rng, data, &mut signature)
+        {
             Ok(()) => Ok(signature),
             Err(_) => Err(SigningError::Rsa),
         }
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl From<libp2p_identity::rsa::Keypair> for Keypair {
+    fn from(kp: libp2p_identity::rsa::Keypair) -> Self {
+        // safety: libp2p's Keypair is a newtype over the same Arc<RsaKeyPair>
+        let inner =
+            unsafe { std::mem::transmute::<libp2p_identity::rsa::Keypair, Arc<RsaKeyPair>>(kp) };
+        Keypair { inner, pkcs8: None }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<Keypair> for libp2p_identity::rsa::Keypair {
+    fn from(kp: Keypair) -> Self {
+        // safety: libp2p's Keypair is a newtype over the same Arc<RsaKeyPair>
+        unsafe { std::mem::transmute::<Arc<RsaKeyPair>, libp2p_identity::rsa::Keypair>(kp.inner) }
+    }
+}
+
 /// An RSA public key.
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PublicKey(Vec<u8>);
 
 impl PublicKey {
     /// Verify an RSA signature on a message using the public key.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerificationError> {
         let key = signature::UnparsedPublicKey::new(&RSA_PKCS1_2048_8192_SHA256, &self.0);
         key.verify(msg, sig).map_err(|e| {
@@ -81,6 +134,61 @@ impl PublicKey {
         })
     }
 
+    /// Verify an RSA signature on a message using the public key.
+    #[cfg(target_arch = "wasm32")]
+    pub fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerificationError> {
+        self.verify_pkcs1v15(msg, sig).map_err(|e| {
+            VerificationError::Rsa(
+                e,
+                bs58::encode(sig).into_string(),
+                bs58::encode(&self.0).into_string(),
+            )
+        })
+    }
+
+    /// Verify a PKCS#1 v1.5 signature over the SHA-256 digest of a message
+    /// with the pure-Rust implementation, which compiles for wasm32.
+    #[cfg(any(target_arch = "wasm32", test))]
+    fn verify_pkcs1v15(&self, msg: &[u8], sig: &[u8]) -> Result<(), ::rsa::Error> {
+        use ::rsa::{BigUint, Pkcs1v15Sign, RsaPublicKey};
+        use sha2::{Digest, Sha256};
+
+        let pkcs1 =
+            ::rsa::pkcs1::RsaPublicKey::try_from(self.0.as_slice()).map_err(::rsa::Error::Pkcs1)?;
+        let n = BigUint::from_bytes_be(pkcs1.modulus.as_bytes());
+        if n.bits() < MIN_MODULUS_BITS {
+            return Err(::rsa::Error::InvalidModulus);
+        }
+        let e = BigUint::from_bytes_be(pkcs1.public_exponent.as_bytes());
+        let key = RsaPublicKey::new_with_max_size(n, e, MAX_MODULUS_BITS)?;
+
+        key.verify(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(msg), sig)
+    }
+
+    /// PeerId of the key computed without libp2p, which supports RSA only on native targets.
+    /// RSA keys are too long to be inlined, so it's the SHA-256 multihash of the key
+    /// encoded as libp2p protobuf `PublicKey`.
+    #[cfg(any(target_arch = "wasm32", test))]
+    pub fn to_peer_id(&self) -> libp2p_identity::PeerId {
+        use sha2::{Digest, Sha256};
+
+        let x509 = self.encode_x509();
+        // field 1 is the key type, 0 is RSA; field 2 is the key bytes
+        let mut protobuf = vec![0x08, 0x00, 0x12];
+        let mut len = x509.len();
+        while len >= 0x80 {
+            protobuf.push((len as u8 & 0x7f) | 0x80);
+            len >>= 7;
+        }
+        protobuf.push(len as u8);
+        protobuf.extend(x509);
+
+        // sha2-256 multihash code and digest length
+        let mut multihash = vec![0x12, 0x20];
+        multihash.extend(Sha256::digest(&protobuf));
+        libp2p_identity::PeerId::from_bytes(&multihash).expect("multihash is valid")
+    }
+
     /// Encode the RSA public key in DER as a PKCS#1 RSAPublicKey structure,
     /// as defined in [RFC3447].
     ///
@@ -274,6 +382,30 @@ mod tests {
         QuickCheck::new().tests(10).quickcheck(prop as fn(_) -> _);
     }
 
+    #[test]
+    fn rsa_pure_rust_verify() {
+        fn prop(SomeKeypair(kp): SomeKeypair, msg: Vec<u8>) -> Result<bool, SigningError> {
+            let pk = kp.public();
+            let mut sig = kp.sign(&msg)?;
+            let valid = pk.verify_pkcs1v15(&msg, &sig).is_ok();
+            sig[0] ^= 1;
+            Ok(valid && pk.verify_pkcs1v15(&msg, &sig).is_err())
+        }
+        QuickCheck::new()
+            .tests(10)
+            .quickcheck(prop as fn(_, _) -> _);
+    }
+
+    #[test]
+    fn rsa_peer_id() {
+        let kp = Keypair::from_pkcs8(&mut KEY1.to_vec()).unwrap();
+        let pk = kp.public();
+        let libp2p_pk = libp2p_identity::PublicKey::from(
+            libp2p_identity::rsa::PublicKey::try_decode_x509(&pk.encode_x509()).unwrap(),
+        );
+        assert_eq!(pk.to_peer_id(), libp2p_pk.to_peer_id());
+    }
+
     #[test]
     fn rsa_sign_verify() {
         fn prop(SomeKeypair(kp): SomeKeypair, msg: Vec<u8>) -> Result<bool, SigningError> {
@@ -29,6 +29,7 @@ use serde::de::Error as SerdeError;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_bytes::{ByteBuf as SerdeByteBuf, Bytes as SerdeBytes};
 use sha2::{Digest as ShaDigestTrait, Sha256};
+use sha3::Keccak256;
 use zeroize::Zeroize;
 
 /// A Secp256k1 keypair.
@@ -78,6 +79,40 @@ impl From<Keypair> for SecretKey {
     }
 }
 
+/// Length of an Ethereum recoverable signature, `r || s || v`.
+pub const EIP191_SIGNATURE_LEN: usize = 65;
+
+/// Hash of a message signed by Ethereum `personal_sign`, as defined in [EIP-191].
+///
+/// [EIP-191]: https://eips.ethereum.org/EIPS/eip-191
+pub fn eip191_hash(msg: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"\x19Ethereum Signed Message:\n");
+    hasher.update(msg.len().to_string().as_bytes());
+    hasher.update(msg);
+    hasher.finalize().into()
+}
+
+/// Hex encoding of an Ethereum address with the mixed-case checksum of [EIP-55].
+///
+/// [EIP-55]: https://eips.ethereum.org/EIPS/eip-55
+pub fn to_checksum_address(address: &[u8; 20]) -> String {
+    let hex: String = address.iter().map(|b| format!("{b:02x}")).collect();
+    let hash = Keccak256::digest(hex.as_bytes());
+
+    let mut result = String::with_capacity(42);
+    result.push_str("0x");
+    for (i, c) in hex.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+        if nibble >= 8 {
+            result.push(c.to_ascii_uppercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
 /// A Secp256k1 secret key.
 #[derive(Clone)]
 pub struct SecretKey(libsecp256k1::SecretKey);
@@ -155,6 +190,35 @@ impl SecretKey {
             .as_ref()
             .into())
     }
+
+    /// Sign a message like Ethereum `personal_sign`, producing a recoverable
+    /// signature `r || s || v` with `v` in the RPC form, i.e. 27 or 28.
+    pub fn sign_eip191(&self, msg: &[u8]) -> Vec<u8> {
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&Message::parse(&eip191_hash(msg)), &self.0);
+
+        let mut result = signature.serialize().to_vec();
+        result.push(27 + recovery_id.serialize());
+        result
+    }
+}
+
+fn recover_eip191(msg: &[u8], sig: &[u8]) -> Result<libsecp256k1::PublicKey, libsecp256k1::Error> {
+    if sig.len() != EIP191_SIGNATURE_LEN {
+        return Err(libsecp256k1::Error::InvalidInputLength);
+    }
+
+    let signature = libsecp256k1::Signature::parse_standard_slice(&sig[..64])?;
+    if signature.s.is_high() {
+        return Err(libsecp256k1::Error::InvalidSignature);
+    }
+    let recovery_id = match sig[64] {
+        v @ (0 | 1) => libsecp256k1::RecoveryId::parse(v)?,
+        v @ (27 | 28) => libsecp256k1::RecoveryId::parse_rpc(v)?,
+        _ => return Err(libsecp256k1::Error::InvalidRecoveryId),
+    };
+
+    libsecp256k1::recover(&Message::parse(&eip191_hash(msg)), &signature, &recovery_id)
 }
 
 /// A Secp256k1 public key.
@@ -184,6 +248,42 @@ impl PublicKey {
             .map(|_| ())
     }
 
+    /// Recover the public key from an EIP-191 signature produced by `sign_eip191`
+    /// or an Ethereum wallet. Signatures with high `s` are rejected as malleable.
+    pub fn recover_eip191(msg: &[u8], sig: &[u8]) -> Result<Self, VerificationError> {
+        recover_eip191(msg, sig).map(PublicKey).map_err(|e| {
+            VerificationError::Secp256k1(e, bs58::encode(sig).into_string(), String::new())
+        })
+    }
+
+    /// Verify an EIP-191 signature on a message using the public key.
+    pub fn verify_eip191(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerificationError> {
+        recover_eip191(msg, sig)
+            .and_then(|pk| {
+                if pk == self.0 {
+                    Ok(())
+                } else {
+                    Err(libsecp256k1::Error::InvalidSignature)
+                }
+            })
+            .map_err(|e| {
+                VerificationError::Secp256k1(
+                    e,
+                    bs58::encode(sig).into_string(),
+                    bs58::encode(self.0.serialize_compressed()).into_string(),
+                )
+            })
+    }
+
+    /// Ethereum address of the public key: the last 20 bytes of the keccak256
+    /// hash of the uncompressed point without its tag.
+    pub fn to_eth_address(&self) -> [u8; 20] {
+        let hash = Keccak256::digest(&self.0.serialize()[1..]);
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+        address
+    }
+
     /// Encode the public key in compressed form, i.e. with one coordinate
     /// represented by a single bit.
     pub fn encode(&self) -> [u8; 33] {
@@ -202,6 +302,14 @@ impl PublicKey {
             .map_err(|_| DecodingError::Secp256k1)
             .map(PublicKey)
     }
+
+    /// Decode a public key from a SEC1 encoded point, either compressed
+    /// or uncompressed.
+    pub fn decode_sec1(bytes: &[u8]) -> Result<Self, DecodingError> {
+        libsecp256k1::PublicKey::parse_slice(bytes, None)
+            .map_err(|_| DecodingError::Secp256k1)
+            .map(PublicKey)
+    }
 }
 
 impl Serialize for PublicKey {
@@ -246,6 +354,84 @@ mod tests {
         assert_eq!(sk_bytes, [0; 32]);
     }
 
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn eip191_known_vector() {
+        // example key and signature of the eth-account documentation
+        let sk = SecretKey::from_bytes(from_hex(
+            "b25c7db31feed9122727bf0939dc769a96564b2de4c4726d035b36ecf1e5b364",
+        ))
+        .unwrap();
+        let pk = Keypair::from(sk.clone()).public().clone();
+        assert_eq!(
+            to_checksum_address(&pk.to_eth_address()),
+            "0x5ce9454909639D2D17A3F753ce7d93fa0b9aB12E"
+        );
+
+        let msg = "I♥SF".as_bytes();
+        let sig = sk.sign_eip191(msg);
+        assert_eq!(
+            sig,
+            from_hex(concat!(
+                "e6ca9bba58c88611fad66a6ce8f996908195593807c4b38bd528d2cff09d4eb3",
+                "3e5bfbbf4d3e39b1a2fd816a7680c19ebebaf3a141b239934ad43cb33fcec8ce1c"
+            ))
+        );
+        assert_eq!(PublicKey::recover_eip191(msg, &sig).unwrap(), pk);
+        pk.verify_eip191(msg, &sig).unwrap();
+        assert!(pk.verify_eip191(b"another message", &sig).is_err());
+        assert!(pk.verify(msg, &sig).is_err());
+    }
+
+    #[test]
+    fn eip191_recovery_id_forms() {
+        let keypair = Keypair::generate();
+        let mut sig = keypair.secret().sign_eip191(b"message");
+        keypair.public().verify_eip191(b"message", &sig).unwrap();
+
+        sig[64] -= 27;
+        keypair.public().verify_eip191(b"message", &sig).unwrap();
+
+        sig[64] = 2;
+        assert!(keypair.public().verify_eip191(b"message", &sig).is_err());
+        assert!(PublicKey::recover_eip191(b"message", &sig[..64]).is_err());
+    }
+
+    #[test]
+    fn eip191_rejects_high_s() {
+        let keypair = Keypair::generate();
+        let sig = keypair.secret().sign_eip191(b"message");
+
+        let mut signature = libsecp256k1::Signature::parse_standard_slice(&sig[..64]).unwrap();
+        signature.s = -signature.s;
+        let mut malleated = signature.serialize().to_vec();
+        malleated.push(27 + (1 - (sig[64] - 27)));
+
+        assert!(keypair
+            .public()
+            .verify_eip191(b"message", &malleated)
+            .is_err());
+    }
+
+    #[test]
+    fn eip55_checksum() {
+        for address in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let bytes: [u8; 20] = from_hex(&address[2..].to_lowercase()).try_into().unwrap();
+            assert_eq!(to_checksum_address(&bytes), address);
+        }
+    }
+
     #[test]
     fn secp256k1_keypair_encode_decode() {
         fn prop() -> bool {
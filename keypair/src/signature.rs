@@ -14,20 +14,27 @@
  * limitations under the License.
  */
 use crate::ed25519;
-use crate::error::DecodingError;
+use crate::error::{DecodingError, Error};
 use crate::key_pair::KeyFormat;
-#[cfg(not(target_arch = "wasm32"))]
+use crate::p256;
 use crate::rsa;
 use crate::secp256k1;
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// Type byte of EIP-191 signatures, the secp256k1 key format with the high bit set
+const EIP191_PREFIX: u8 = 0x82;
+const EIP191_TYPE_NAME: &str = "eip191";
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Signature {
     Ed25519(ed25519::Signature),
-    #[cfg(not(target_arch = "wasm32"))]
     Rsa(rsa::Signature),
     Secp256k1(secp256k1::Signature),
+    P256(p256::Signature),
+    /// Recoverable secp256k1 signature of Ethereum `personal_sign`
+    Eip191(secp256k1::Signature),
 }
 
 pub struct RawSignature {
@@ -40,9 +47,10 @@ impl Signature {
         use Signature::*;
         match self {
             Ed25519(_) => KeyFormat::Ed25519.into(),
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(_) => KeyFormat::Rsa.into(),
             Secp256k1(_) => KeyFormat::Secp256k1.into(),
+            P256(_) => KeyFormat::P256.into(),
+            Eip191(_) => EIP191_PREFIX,
         }
     }
 
@@ -54,9 +62,10 @@ impl Signature {
 
         match self {
             Ed25519(sig) => result.extend(sig.0.clone()),
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(sig) => result.extend(sig.0.clone()),
             Secp256k1(sig) => result.extend(sig.0.clone()),
+            P256(sig) => result.extend(sig.0.clone()),
+            Eip191(sig) => result.extend(sig.0.clone()),
         }
 
         result
@@ -64,13 +73,17 @@ impl Signature {
 
     /// decode with first byte set as keypair type
     pub fn decode(bytes: Vec<u8>) -> Result<Self, DecodingError> {
+        if bytes[0] == EIP191_PREFIX {
+            return Ok(Signature::Eip191(secp256k1::Signature(bytes[1..].to_vec())));
+        }
+
         match KeyFormat::try_from(bytes[0])? {
             KeyFormat::Ed25519 => Ok(Signature::Ed25519(ed25519::Signature(bytes[1..].to_vec()))),
-            #[cfg(not(target_arch = "wasm32"))]
             KeyFormat::Rsa => Ok(Signature::Rsa(rsa::Signature(bytes[1..].to_vec()))),
             KeyFormat::Secp256k1 => Ok(Signature::Secp256k1(secp256k1::Signature(
                 bytes[1..].to_vec(),
             ))),
+            KeyFormat::P256 => Ok(Signature::P256(p256::Signature(bytes[1..].to_vec()))),
         }
     }
 
@@ -79,21 +92,40 @@ impl Signature {
 
         match self {
             Ed25519(sig) => &sig.0,
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(sig) => &sig.0,
             Secp256k1(sig) => &sig.0,
+            P256(sig) => &sig.0,
+            Eip191(sig) => &sig.0,
         }
     }
 
+    /// Key format of the signer
     pub fn get_signature_type(&self) -> KeyFormat {
         use Signature::*;
 
         match self {
             Ed25519(_) => KeyFormat::Ed25519,
-            #[cfg(not(target_arch = "wasm32"))]
             Rsa(_) => KeyFormat::Rsa,
-            Secp256k1(_) => KeyFormat::Secp256k1,
+            Secp256k1(_) | Eip191(_) => KeyFormat::Secp256k1,
+            P256(_) => KeyFormat::P256,
+        }
+    }
+
+    /// Name of the signature type: `eip191` for EIP-191 signatures, the key format otherwise
+    pub fn get_type_name(&self) -> String {
+        match self {
+            Signature::Eip191(_) => EIP191_TYPE_NAME.to_string(),
+            _ => self.get_signature_type().into(),
+        }
+    }
+
+    /// Build a signature from its type name, see `get_type_name`
+    pub fn from_type_name(sig_type: &str, bytes: Vec<u8>) -> Result<Self, Error> {
+        if sig_type == EIP191_TYPE_NAME {
+            return Ok(Signature::Eip191(secp256k1::Signature(bytes)));
         }
+
+        Ok(Self::from_bytes(KeyFormat::from_str(sig_type)?, bytes))
     }
 
     pub fn get_raw_signature(&self) -> RawSignature {
@@ -106,9 +138,9 @@ impl Signature {
     pub fn from_bytes(key_format: KeyFormat, bytes: Vec<u8>) -> Self {
         match key_format {
             KeyFormat::Ed25519 => Signature::Ed25519(ed25519::Signature(bytes)),
-            #[cfg(not(target_arch = "wasm32"))]
             KeyFormat::Rsa => Signature::Rsa(rsa::Signature(bytes)),
             KeyFormat::Secp256k1 => Signature::Secp256k1(secp256k1::Signature(bytes)),
+            KeyFormat::P256 => Signature::P256(p256::Signature(bytes)),
         }
     }
 }
@@ -122,8 +154,9 @@ mod tests {
         let bytes: Vec<u8> = (0..10).collect();
         let ed25519_sig = Signature::Ed25519(crate::ed25519::Signature(bytes.clone()));
         let secp256k1_sig = Signature::Secp256k1(crate::secp256k1::Signature(bytes.clone()));
-        #[cfg(not(target_arch = "wasm32"))]
+        let p256_sig = Signature::P256(crate::p256::Signature(bytes.clone()));
         let rsa_sig = Signature::Rsa(crate::rsa::Signature(bytes.clone()));
+        let eip191_sig = Signature::Eip191(crate::secp256k1::Signature(bytes.clone()));
 
         assert_eq!(
             Signature::decode(ed25519_sig.encode()).unwrap(),
@@ -133,7 +166,24 @@ mod tests {
             Signature::decode(secp256k1_sig.encode()).unwrap(),
             secp256k1_sig
         );
-        #[cfg(not(target_arch = "wasm32"))]
+        assert_eq!(Signature::decode(p256_sig.encode()).unwrap(), p256_sig);
         assert_eq!(Signature::decode(rsa_sig.encode()).unwrap(), rsa_sig);
+        assert_eq!(Signature::decode(eip191_sig.encode()).unwrap(), eip191_sig);
+    }
+
+    #[test]
+    fn signature_type_name() {
+        let bytes: Vec<u8> = (0..10).collect();
+        for sig in [
+            Signature::Ed25519(crate::ed25519::Signature(bytes.clone())),
+            Signature::Secp256k1(crate::secp256k1::Signature(bytes.clone())),
+            Signature::Eip191(crate::secp256k1::Signature(bytes.clone())),
+        ] {
+            assert_eq!(
+                Signature::from_type_name(&sig.get_type_name(), bytes.clone()).unwrap(),
+                sig
+            );
+        }
+        assert!(Signature::from_type_name("eip712", bytes).is_err());
     }
 }
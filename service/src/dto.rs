@@ -1,12 +1,13 @@
 use crate::dto::DtoConversionError::PeerIdDecodeError;
 use fluence_keypair::error::DecodingError;
-use fluence_keypair::{KeyFormat, PublicKey, Signature};
+use fluence_keypair::{PublicKey, Signature};
 use libp2p_identity::PeerId;
 use marine_rs_sdk::marine;
 use std::convert::TryFrom;
 use std::str::FromStr;
 use std::time::Duration;
 use thiserror::Error as ThisError;
+use trust_graph::{SigningVersion, ThresholdError};
 
 #[derive(ThisError, Debug)]
 pub enum DtoConversionError {
@@ -24,23 +25,68 @@ pub enum DtoConversionError {
     ),
     #[error("Cannot decode peer id from string: {0}")]
     PeerIdDecodeError(String),
+    #[error("Public key doesn't match peer id {0}")]
+    PublicKeyMismatch(String),
     #[error("{0}")]
     InvalidKeyFormat(
         #[from]
         #[source]
         fluence_keypair::error::Error,
     ),
+    #[error("Unknown signing version: {0}")]
+    UnknownSigningVersion(u32),
+    #[error("{0}")]
+    InvalidThresholdAnchor(
+        #[from]
+        #[source]
+        ThresholdError,
+    ),
+}
+
+/// Peer ids of RSA and P-256 keys are hashes, such keys are accepted base58 encoded instead
+pub(crate) fn public_key_from_peer_id(peer_id: &str) -> Result<PublicKey, DtoConversionError> {
+    PeerId::from_str(peer_id)
+        .map_err(|e| PeerIdDecodeError(format!("{e:?}")))
+        .and_then(|p| {
+            PublicKey::try_from(p).map_err(|e| DtoConversionError::PeerIdDecodeError(e.to_string()))
+        })
+        .or_else(|e| decode_public_key(peer_id).map_err(|_| e))
+}
+
+pub(crate) fn decode_public_key(encoded: &str) -> Result<PublicKey, DtoConversionError> {
+    Ok(PublicKey::decode(&bs58::decode(encoded).into_vec()?)?)
+}
+
+/// Base58 encoded key if it can't be extracted from its peer id, empty otherwise
+fn encode_hashed_public_key(pk: &PublicKey) -> String {
+    match PublicKey::try_from(pk.to_peer_id()) {
+        Ok(_) => String::new(),
+        Err(_) => bs58::encode(pk.encode()).into_string(),
+    }
+}
+
+fn signing_version_from_u32(version: u32) -> Result<SigningVersion, DtoConversionError> {
+    u8::try_from(version)
+        .ok()
+        .and_then(SigningVersion::from_u8)
+        .ok_or(DtoConversionError::UnknownSigningVersion(version))
 }
 
 #[marine]
 pub struct Certificate {
     pub chain: Vec<Trust>,
+    /// Threshold trust for the root of the chain, empty if the root is trusted directly
+    pub anchor_trust: Vec<ThresholdTrust>,
 }
 
 impl From<trust_graph::Certificate> for Certificate {
     fn from(c: trust_graph::Certificate) -> Self {
         let chain: Vec<Trust> = c.chain.into_iter().map(|t| t.into()).collect();
-        Certificate { chain }
+        let anchor_trust = c.anchor_trust.into_iter().map(|t| t.into()).collect();
+        Certificate {
+            chain,
+            anchor_trust,
+        }
     }
 }
 
@@ -54,7 +100,16 @@ impl TryFrom<Certificate> for trust_graph::Certificate {
             .map(trust_graph::Trust::try_from)
             .collect();
         let chain = chain?;
-        Ok(trust_graph::Certificate { chain })
+        let anchor_trust = c
+            .anchor_trust
+            .into_iter()
+            .next()
+            .map(trust_graph::ThresholdTrust::try_from)
+            .transpose()?;
+        Ok(trust_graph::Certificate {
+            chain,
+            anchor_trust,
+        })
     }
 }
 
@@ -71,18 +126,30 @@ pub struct Trust {
     pub sig_type: String,
     /// Creation time of a trust, in secs
     pub issued_at: u64,
+    /// Format of the signed payload: 1 for legacy, 2 for domain-separated, 3 for signed confidence
+    pub signing_version: u32,
+    /// How much the issuer trusts the key, 255 is full trust
+    pub confidence: u8,
+    /// Base58 encoded public key of `issued_for`, needed only for
+    /// RSA and P-256 keys whose peer ids are hashes, empty otherwise
+    pub issued_for_pk: String,
 }
 
 impl TryFrom<Trust> for trust_graph::Trust {
     type Error = DtoConversionError;
 
     fn try_from(t: Trust) -> Result<Self, Self::Error> {
-        let issued_for = PublicKey::try_from(
-            PeerId::from_str(&t.issued_for).map_err(|e| PeerIdDecodeError(format!("{e:?}")))?,
-        )
-        .map_err(|e| DtoConversionError::PeerIdDecodeError(e.to_string()))?;
+        let issued_for = if t.issued_for_pk.is_empty() {
+            public_key_from_peer_id(&t.issued_for)?
+        } else {
+            let pk = decode_public_key(&t.issued_for_pk)?;
+            if pk.to_peer_id().to_base58() != t.issued_for {
+                return Err(DtoConversionError::PublicKeyMismatch(t.issued_for));
+            }
+            pk
+        };
         let signature = bs58::decode(&t.signature).into_vec()?;
-        let signature = Signature::from_bytes(KeyFormat::from_str(&t.sig_type)?, signature);
+        let signature = Signature::from_type_name(&t.sig_type, signature)?;
         let expires_at = Duration::from_secs(t.expires_at);
         let issued_at = Duration::from_secs(t.issued_at);
         Ok(trust_graph::Trust {
@@ -90,6 +157,8 @@ impl TryFrom<Trust> for trust_graph::Trust {
             expires_at,
             signature,
             issued_at,
+            version: signing_version_from_u32(t.signing_version)?,
+            confidence: t.confidence,
         })
     }
 }
@@ -97,16 +166,20 @@ impl TryFrom<Trust> for trust_graph::Trust {
 impl From<trust_graph::Trust> for Trust {
     fn from(t: trust_graph::Trust) -> Self {
         let issued_for = t.issued_for.to_peer_id().to_base58();
+        let issued_for_pk = encode_hashed_public_key(&t.issued_for);
         let raw_signature = t.signature.get_raw_signature();
         let signature = bs58::encode(raw_signature.bytes).into_string();
         let expires_at = t.expires_at.as_secs();
         let issued_at = t.issued_at.as_secs();
         Trust {
             issued_for,
+            issued_for_pk,
             expires_at,
             signature,
-            sig_type: raw_signature.sig_type.into(),
+            sig_type: t.signature.get_type_name(),
             issued_at,
+            signing_version: t.version.to_u8().into(),
+            confidence: t.confidence,
         }
     }
 }
@@ -124,29 +197,25 @@ pub struct Revocation {
     pub sig_type: String,
     /// the issuer of this revocation, base58 peer id
     pub revoked_by: String,
+    /// Format of the signed payload: 1 for legacy, 2 for domain-separated
+    pub signing_version: u32,
 }
 
 impl TryFrom<Revocation> for trust_graph::Revocation {
     type Error = DtoConversionError;
 
     fn try_from(r: Revocation) -> Result<Self, Self::Error> {
-        let revoked_pk = PublicKey::try_from(
-            PeerId::from_str(&r.revoked_peer_id)
-                .map_err(|e| PeerIdDecodeError(format!("{e:?}")))?,
-        )
-        .map_err(|e| DtoConversionError::PeerIdDecodeError(e.to_string()))?;
-        let revoked_by_pk = PublicKey::try_from(
-            PeerId::from_str(&r.revoked_by).map_err(|e| PeerIdDecodeError(format!("{e:?}")))?,
-        )
-        .map_err(|e| DtoConversionError::PeerIdDecodeError(e.to_string()))?;
+        let revoked_pk = public_key_from_peer_id(&r.revoked_peer_id)?;
+        let revoked_by_pk = public_key_from_peer_id(&r.revoked_by)?;
         let signature = bs58::decode(&r.signature).into_vec()?;
-        let signature = Signature::from_bytes(KeyFormat::from_str(&r.sig_type)?, signature);
+        let signature = Signature::from_type_name(&r.sig_type, signature)?;
         let revoked_at = Duration::from_secs(r.revoked_at);
         Ok(trust_graph::Revocation {
             pk: revoked_pk,
             revoked_at,
             revoked_by: revoked_by_pk,
             signature,
+            version: signing_version_from_u32(r.signing_version)?,
         })
     }
 }
@@ -162,8 +231,396 @@ impl From<trust_graph::Revocation> for Revocation {
             revoked_peer_id,
             revoked_at,
             signature,
-            sig_type: raw_signature.sig_type.into(),
+            sig_type: r.signature.get_type_name(),
             revoked_by,
+            signing_version: r.version.to_u8().into(),
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct Distrust {
+    /// who is distrusted
+    pub distrusted_peer_id: String,
+    /// date when distrust was created
+    pub distrusted_at: u64,
+    /// base58
+    pub signature: String,
+    pub sig_type: String,
+    /// the issuer of this distrust, base58 peer id
+    pub distrusted_by: String,
+}
+
+impl TryFrom<Distrust> for trust_graph::Distrust {
+    type Error = DtoConversionError;
+
+    fn try_from(d: Distrust) -> Result<Self, Self::Error> {
+        let distrusted_pk = public_key_from_peer_id(&d.distrusted_peer_id)?;
+        let distrusted_by_pk = public_key_from_peer_id(&d.distrusted_by)?;
+        let signature = bs58::decode(&d.signature).into_vec()?;
+        let signature = Signature::from_type_name(&d.sig_type, signature)?;
+        Ok(trust_graph::Distrust::new(
+            distrusted_by_pk,
+            distrusted_pk,
+            Duration::from_secs(d.distrusted_at),
+            signature,
+        ))
+    }
+}
+
+impl From<trust_graph::Distrust> for Distrust {
+    fn from(d: trust_graph::Distrust) -> Self {
+        let raw_signature = d.signature.get_raw_signature();
+        Distrust {
+            distrusted_peer_id: d.pk.to_peer_id().to_base58(),
+            distrusted_at: d.distrusted_at.as_secs(),
+            signature: bs58::encode(raw_signature.bytes).into_string(),
+            sig_type: d.signature.get_type_name(),
+            distrusted_by: d.distrusted_by.to_peer_id().to_base58(),
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct WeightAttestation {
+    /// the host that computed the weight, base58 peer id
+    pub host: String,
+    pub peer_id: String,
+    pub weight: u32,
+    /// when the weight was computed, in secs
+    pub timestamp: u64,
+    /// base58
+    pub signature: String,
+    pub sig_type: String,
+}
+
+impl TryFrom<WeightAttestation> for trust_graph::WeightAttestation {
+    type Error = DtoConversionError;
+
+    fn try_from(a: WeightAttestation) -> Result<Self, Self::Error> {
+        let host_pk = public_key_from_peer_id(&a.host)?;
+        let pk = public_key_from_peer_id(&a.peer_id)?;
+        let signature = bs58::decode(&a.signature).into_vec()?;
+        let signature = Signature::from_type_name(&a.sig_type, signature)?;
+        Ok(trust_graph::WeightAttestation::new(
+            host_pk,
+            pk,
+            a.weight,
+            Duration::from_secs(a.timestamp),
+            signature,
+        ))
+    }
+}
+
+impl From<trust_graph::WeightAttestation> for WeightAttestation {
+    fn from(a: trust_graph::WeightAttestation) -> Self {
+        let raw_signature = a.signature.get_raw_signature();
+        WeightAttestation {
+            host: a.host.to_peer_id().to_base58(),
+            peer_id: a.pk.to_peer_id().to_base58(),
+            weight: a.weight,
+            timestamp: a.timestamp.as_secs(),
+            signature: bs58::encode(raw_signature.bytes).into_string(),
+            sig_type: a.signature.get_type_name(),
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct SignedTreeHead {
+    /// the transparency log owner, base58 peer id
+    pub log_peer_id: String,
+    pub size: u64,
+    pub root: Vec<u8>,
+    /// in secs
+    pub timestamp: u64,
+    /// base58
+    pub signature: String,
+    pub sig_type: String,
+}
+
+impl From<trust_graph::SignedTreeHead> for SignedTreeHead {
+    fn from(h: trust_graph::SignedTreeHead) -> Self {
+        let raw_signature = h.signature.get_raw_signature();
+        SignedTreeHead {
+            log_peer_id: h.log_key.to_peer_id().to_base58(),
+            size: h.size,
+            root: h.root.to_vec(),
+            timestamp: h.timestamp.as_secs(),
+            signature: bs58::encode(raw_signature.bytes).into_string(),
+            sig_type: h.signature.get_type_name(),
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct RevocationList {
+    /// the issuer of the list and of every revocation in it, base58 peer id
+    pub issued_by: String,
+    /// lists with a lower or equal sequence number are replaced by this one
+    pub sequence: u64,
+    /// start of the validity window, in secs
+    pub issued_at: u64,
+    /// end of the validity window, in secs
+    pub expires_at: u64,
+    pub revocations: Vec<Revocation>,
+    /// Signature of the whole list, base58
+    pub signature: String,
+    pub sig_type: String,
+}
+
+impl TryFrom<RevocationList> for trust_graph::RevocationList {
+    type Error = DtoConversionError;
+
+    fn try_from(l: RevocationList) -> Result<Self, Self::Error> {
+        let issued_by = public_key_from_peer_id(&l.issued_by)?;
+        let revocations = l
+            .revocations
+            .into_iter()
+            .map(trust_graph::Revocation::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let signature = bs58::decode(&l.signature).into_vec()?;
+        let signature = Signature::from_type_name(&l.sig_type, signature)?;
+        Ok(trust_graph::RevocationList {
+            issued_by,
+            sequence: l.sequence,
+            issued_at: Duration::from_secs(l.issued_at),
+            expires_at: Duration::from_secs(l.expires_at),
+            revocations,
+            signature,
+        })
+    }
+}
+
+impl From<trust_graph::RevocationList> for RevocationList {
+    fn from(l: trust_graph::RevocationList) -> Self {
+        let issued_by = l.issued_by.to_peer_id().to_base58();
+        let revocations = l.revocations.into_iter().map(|r| r.into()).collect();
+        let raw_signature = l.signature.get_raw_signature();
+        let signature = bs58::encode(raw_signature.bytes).into_string();
+        RevocationList {
+            issued_by,
+            sequence: l.sequence,
+            issued_at: l.issued_at.as_secs(),
+            expires_at: l.expires_at.as_secs(),
+            revocations,
+            signature,
+            sig_type: l.signature.get_type_name(),
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct KeyCompromise {
+    /// the compromised key and the signer of this statement, base58 peer id
+    pub peer_id: String,
+    /// everything signed by `peer_id` after this moment is void, in secs
+    pub compromised_at: u64,
+    /// Self-signed signature of the statement, base58
+    pub signature: String,
+    pub sig_type: String,
+}
+
+impl TryFrom<KeyCompromise> for trust_graph::KeyCompromise {
+    type Error = DtoConversionError;
+
+    fn try_from(c: KeyCompromise) -> Result<Self, Self::Error> {
+        let pk = public_key_from_peer_id(&c.peer_id)?;
+        let signature = bs58::decode(&c.signature).into_vec()?;
+        let signature = Signature::from_type_name(&c.sig_type, signature)?;
+        Ok(trust_graph::KeyCompromise {
+            pk,
+            compromised_at: Duration::from_secs(c.compromised_at),
+            signature,
+        })
+    }
+}
+
+impl From<trust_graph::KeyCompromise> for KeyCompromise {
+    fn from(c: trust_graph::KeyCompromise) -> Self {
+        let peer_id = c.pk.to_peer_id().to_base58();
+        let raw_signature = c.signature.get_raw_signature();
+        let signature = bs58::encode(raw_signature.bytes).into_string();
+        KeyCompromise {
+            peer_id,
+            compromised_at: c.compromised_at.as_secs(),
+            signature,
+            sig_type: c.signature.get_type_name(),
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct ThresholdAnchor {
+    /// keys of the anchor, base58 peer ids
+    pub keys: Vec<String>,
+    /// how many of `keys` have to sign a threshold trust
+    pub threshold: u32,
+}
+
+impl TryFrom<ThresholdAnchor> for trust_graph::ThresholdAnchor {
+    type Error = DtoConversionError;
+
+    fn try_from(a: ThresholdAnchor) -> Result<Self, Self::Error> {
+        let keys = a
+            .keys
+            .iter()
+            .map(|k| public_key_from_peer_id(k))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(trust_graph::ThresholdAnchor::new(keys, a.threshold)?)
+    }
+}
+
+impl From<trust_graph::ThresholdAnchor> for ThresholdAnchor {
+    fn from(a: trust_graph::ThresholdAnchor) -> Self {
+        ThresholdAnchor {
+            keys: a
+                .keys()
+                .iter()
+                .map(|k| k.to_peer_id().to_base58())
+                .collect(),
+            threshold: a.threshold(),
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct ThresholdSignature {
+    /// one of the anchor keys, base58 peer id
+    pub signed_by: String,
+    /// base58
+    pub signature: String,
+    pub sig_type: String,
+}
+
+#[marine]
+#[derive(Default)]
+pub struct ThresholdTrust {
+    pub anchor: ThresholdAnchor,
+    /// For whom this trust is issued, base58 peer_id
+    pub issued_for: String,
+    /// Expiration date of a trust, in secs
+    pub expires_at: u64,
+    /// Creation time of a trust, in secs
+    pub issued_at: u64,
+    pub signatures: Vec<ThresholdSignature>,
+}
+
+impl TryFrom<ThresholdTrust> for trust_graph::ThresholdTrust {
+    type Error = DtoConversionError;
+
+    fn try_from(t: ThresholdTrust) -> Result<Self, Self::Error> {
+        let anchor = trust_graph::ThresholdAnchor::try_from(t.anchor)?;
+        let issued_for = public_key_from_peer_id(&t.issued_for)?;
+        let signatures = t
+            .signatures
+            .into_iter()
+            .map(|s| {
+                let signed_by = public_key_from_peer_id(&s.signed_by)?;
+                let signature = bs58::decode(&s.signature).into_vec()?;
+                let signature = Signature::from_type_name(&s.sig_type, signature)?;
+                Ok((signed_by, signature))
+            })
+            .collect::<Result<Vec<_>, DtoConversionError>>()?;
+        Ok(trust_graph::ThresholdTrust::new(
+            anchor,
+            issued_for,
+            Duration::from_secs(t.expires_at),
+            Duration::from_secs(t.issued_at),
+            signatures,
+        ))
+    }
+}
+
+impl From<trust_graph::ThresholdTrust> for ThresholdTrust {
+    fn from(t: trust_graph::ThresholdTrust) -> Self {
+        let signatures = t
+            .signatures
+            .into_iter()
+            .map(|(signed_by, signature)| {
+                let raw_signature = signature.get_raw_signature();
+                ThresholdSignature {
+                    signed_by: signed_by.to_peer_id().to_base58(),
+                    signature: bs58::encode(raw_signature.bytes).into_string(),
+                    sig_type: signature.get_type_name(),
+                }
+            })
+            .collect();
+        ThresholdTrust {
+            anchor: t.anchor.into(),
+            issued_for: t.issued_for.to_peer_id().to_base58(),
+            expires_at: t.expires_at.as_secs(),
+            issued_at: t.issued_at.as_secs(),
+            signatures,
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct TrustedKey {
+    pub peer_id: String,
+    pub weight: u32,
+    /// Certificate that gives the weight, empty if the key is a root itself
+    pub certificate: Vec<Certificate>,
+}
+
+impl From<trust_graph::TrustedKey> for TrustedKey {
+    fn from(k: trust_graph::TrustedKey) -> Self {
+        TrustedKey {
+            peer_id: k.pk.to_peer_id().to_base58(),
+            weight: k.weight,
+            certificate: k.certificate.into_iter().map(|c| c.into()).collect(),
+        }
+    }
+}
+
+#[marine]
+#[derive(Default)]
+pub struct KeyFormatCount {
+    pub key_format: String,
+    pub count: u64,
+}
+
+#[marine]
+#[derive(Default)]
+pub struct GraphStats {
+    pub keys: u64,
+    pub auths: u64,
+    pub revocations: u64,
+    pub distrusts: u64,
+    pub roots: u64,
+    pub anchored_roots: u64,
+    /// Auths that are expired but not garbage collected yet
+    pub expired: u64,
+    /// Number of keys by length of the shortest chain from a root, roots have depth 0
+    pub depth_histogram: Vec<u64>,
+    pub key_formats: Vec<KeyFormatCount>,
+}
+
+impl From<trust_graph::GraphStats> for GraphStats {
+    fn from(s: trust_graph::GraphStats) -> Self {
+        GraphStats {
+            keys: s.keys,
+            auths: s.auths,
+            revocations: s.revocations,
+            distrusts: s.distrusts,
+            roots: s.roots,
+            anchored_roots: s.anchored_roots,
+            expired: s.expired,
+            depth_histogram: s.depth_histogram,
+            key_formats: s
+                .key_formats
+                .into_iter()
+                .map(|(key_format, count)| KeyFormatCount { key_format, count })
+                .collect(),
         }
     }
 }
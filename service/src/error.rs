@@ -16,8 +16,11 @@
 use thiserror::Error as ThisError;
 
 use crate::dto::DtoConversionError;
+use crate::storage_impl::SQLiteStorageError;
 use fluence_keypair::error::DecodingError;
-use trust_graph::{CertificateError, TrustError, TrustGraphError};
+use trust_graph::{
+    AttestationError, CertificateError, ThresholdError, TreeHeadError, TrustError, TrustGraphError,
+};
 
 #[derive(ThisError, Debug)]
 pub enum ServiceError {
@@ -61,4 +64,42 @@ pub enum ServiceError {
     InvalidTimestamp(String),
     #[error("Root could add only by trust graph service owner")]
     NotOwner,
+    #[error("There is no revocation list issued by {0}")]
+    RevocationListNotFound(String),
+    #[error("Key {0} is not declared compromised")]
+    KeyCompromiseNotFound(String),
+    #[error("{0}")]
+    ThresholdError(
+        #[from]
+        #[source]
+        ThresholdError,
+    ),
+    #[error("Got {0} signers for {1} signatures")]
+    SignersMismatch(usize, usize),
+    #[error("Unknown distrust policy {0}, expected one of: ignore, penalty, veto")]
+    UnknownDistrustPolicy(String),
+    #[error("Unknown gc schedule {0}, expected one of: on_read, every, manual")]
+    UnknownGcSchedule(String),
+    #[error("Unknown eviction policy {0}, expected one of: reject, evict_oldest")]
+    UnknownEvictionPolicy(String),
+    #[error("{0}")]
+    AttestationError(
+        #[from]
+        #[source]
+        AttestationError,
+    ),
+    #[error("{0}")]
+    TreeHeadError(
+        #[from]
+        #[source]
+        TreeHeadError,
+    ),
+    #[error("Tree root must be 32 bytes, got {0}")]
+    InvalidTreeRoot(usize),
+    #[error("{0}")]
+    StorageError(
+        #[from]
+        #[source]
+        SQLiteStorageError,
+    ),
 }
@@ -11,6 +11,7 @@ mod error;
 mod misc;
 mod results;
 mod service_api;
+mod settings;
 mod storage_impl;
 mod tests;
 
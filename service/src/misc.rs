@@ -13,8 +13,10 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use crate::dto::decode_public_key;
 use crate::error::ServiceError;
 use crate::error::ServiceError::*;
+use crate::settings::{load_settings, store_last_gc};
 use crate::storage_impl::{SQLiteStorage, DB_PATH};
 use crate::TRUSTED_TIMESTAMP;
 use fluence_keypair::PublicKey;
@@ -50,15 +52,33 @@ fn parse_peer_id(peer_id: String) -> Result<PeerId, ServiceError> {
         .map_err(|e| ServiceError::PeerIdParseError(format!("{e:?}")))
 }
 
-thread_local!(static INSTANCE: RefCell<TrustGraph<SQLiteStorage>> = RefCell::new(TrustGraph::new(
-    SQLiteStorage::new(marine_sqlite_connector::open(DB_PATH).unwrap()),
-)));
+thread_local!(static INSTANCE: RefCell<TrustGraph<SQLiteStorage>> = RefCell::new(load_tg()));
 
+/// Graph over the service database with the settings stored by the owner
+fn load_tg() -> TrustGraph<SQLiteStorage> {
+    let mut tg = TrustGraph::new(SQLiteStorage::new(
+        marine_sqlite_connector::open(DB_PATH).unwrap(),
+    ));
+    load_settings(&mut tg).unwrap();
+    tg
+}
+
+/// Garbage collection may run on any call, so its time is stored after the calls that ran it
 pub fn with_tg<F, T>(func: F) -> T
 where
     F: FnOnce(&mut TrustGraph<SQLiteStorage>) -> T,
 {
-    INSTANCE.with(|tg| func(tg.borrow_mut().deref_mut()))
+    INSTANCE.with(|tg| {
+        let mut tg = tg.borrow_mut();
+        let last_gc = tg.last_gc();
+        let result = func(tg.deref_mut());
+        if tg.last_gc() != last_gc {
+            if let Err(e) = store_last_gc(&tg) {
+                log::error!("store_last_gc: {:?}", e);
+            }
+        }
+        result
+    })
 }
 
 pub fn wrapped_try<F, T>(func: F) -> T
@@ -68,10 +88,10 @@ where
     func()
 }
 
+/// Also accepts base58 encoded public keys, since RSA and P-256 peer ids are hashes
 pub fn extract_public_key(peer_id: String) -> Result<PublicKey, ServiceError> {
-    PublicKey::try_from(
-        parse_peer_id(peer_id)
-            .map_err(|e| ServiceError::PublicKeyExtractionError(e.to_string()))?,
-    )
-    .map_err(ServiceError::PublicKeyDecodeError)
+    parse_peer_id(peer_id.clone())
+        .map_err(|e| ServiceError::PublicKeyExtractionError(e.to_string()))
+        .and_then(|p| PublicKey::try_from(p).map_err(ServiceError::PublicKeyDecodeError))
+        .or_else(|e| decode_public_key(&peer_id).map_err(|_| e))
 }
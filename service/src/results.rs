@@ -1,6 +1,10 @@
-use crate::dto::{Certificate, Revocation, Trust};
+use crate::dto::{
+    Certificate, Distrust, GraphStats, KeyCompromise, Revocation, RevocationList, SignedTreeHead,
+    ThresholdTrust, Trust, TrustedKey, WeightAttestation,
+};
 use crate::error::ServiceError;
 use marine_rs_sdk::marine;
+use trust_graph::GcReport;
 
 #[marine]
 pub struct InsertResult {
@@ -280,3 +284,696 @@ impl From<Result<Vec<Revocation>, ServiceError>> for ExportRevocationsResult {
         }
     }
 }
+
+#[marine]
+pub struct ExportTrustsResult {
+    pub success: bool,
+    pub trusts: Vec<Trust>,
+    pub error: String,
+}
+
+impl From<Result<Vec<Trust>, ServiceError>> for ExportTrustsResult {
+    fn from(result: Result<Vec<Trust>, ServiceError>) -> Self {
+        match result {
+            Ok(trusts) => ExportTrustsResult {
+                success: true,
+                trusts,
+                error: "".to_string(),
+            },
+            Err(e) => ExportTrustsResult {
+                success: false,
+                trusts: vec![],
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct GetRevocationListBytesResult {
+    pub success: bool,
+    pub error: String,
+    pub result: Vec<u8>,
+}
+
+impl From<Result<Vec<u8>, ServiceError>> for GetRevocationListBytesResult {
+    fn from(result: Result<Vec<u8>, ServiceError>) -> Self {
+        match result {
+            Ok(res) => GetRevocationListBytesResult {
+                success: true,
+                error: "".to_string(),
+                result: res,
+            },
+            Err(e) => GetRevocationListBytesResult {
+                success: false,
+                error: format!("{e}"),
+                result: vec![],
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct IssueRevocationListResult {
+    pub success: bool,
+    pub error: String,
+    pub revocation_list: RevocationList,
+}
+
+impl From<Result<RevocationList, ServiceError>> for IssueRevocationListResult {
+    fn from(result: Result<RevocationList, ServiceError>) -> Self {
+        match result {
+            Ok(revocation_list) => IssueRevocationListResult {
+                success: true,
+                error: "".to_string(),
+                revocation_list,
+            },
+            Err(e) => IssueRevocationListResult {
+                success: false,
+                error: format!("{e}"),
+                revocation_list: RevocationList::default(),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct ImportRevocationListResult {
+    pub success: bool,
+    pub error: String,
+}
+
+impl From<Result<(), ServiceError>> for ImportRevocationListResult {
+    fn from(result: Result<(), ServiceError>) -> Self {
+        match result {
+            Ok(()) => ImportRevocationListResult {
+                success: true,
+                error: "".to_string(),
+            },
+            Err(e) => ImportRevocationListResult {
+                success: false,
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct ExportRevocationListResult {
+    pub success: bool,
+    pub revocation_list: RevocationList,
+    pub error: String,
+}
+
+impl From<Result<RevocationList, ServiceError>> for ExportRevocationListResult {
+    fn from(result: Result<RevocationList, ServiceError>) -> Self {
+        match result {
+            Ok(revocation_list) => ExportRevocationListResult {
+                success: true,
+                revocation_list,
+                error: "".to_string(),
+            },
+            Err(e) => ExportRevocationListResult {
+                success: false,
+                revocation_list: RevocationList::default(),
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct GetKeyCompromiseBytesResult {
+    pub success: bool,
+    pub error: String,
+    pub result: Vec<u8>,
+}
+
+impl From<Result<Vec<u8>, ServiceError>> for GetKeyCompromiseBytesResult {
+    fn from(result: Result<Vec<u8>, ServiceError>) -> Self {
+        match result {
+            Ok(res) => GetKeyCompromiseBytesResult {
+                success: true,
+                error: "".to_string(),
+                result: res,
+            },
+            Err(e) => GetKeyCompromiseBytesResult {
+                success: false,
+                error: format!("{e}"),
+                result: vec![],
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct IssueKeyCompromiseResult {
+    pub success: bool,
+    pub error: String,
+    pub compromise: KeyCompromise,
+}
+
+impl From<Result<KeyCompromise, ServiceError>> for IssueKeyCompromiseResult {
+    fn from(result: Result<KeyCompromise, ServiceError>) -> Self {
+        match result {
+            Ok(compromise) => IssueKeyCompromiseResult {
+                success: true,
+                error: "".to_string(),
+                compromise,
+            },
+            Err(e) => IssueKeyCompromiseResult {
+                success: false,
+                error: format!("{e}"),
+                compromise: KeyCompromise::default(),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct DeclareCompromiseResult {
+    pub success: bool,
+    pub error: String,
+}
+
+impl From<Result<(), ServiceError>> for DeclareCompromiseResult {
+    fn from(result: Result<(), ServiceError>) -> Self {
+        match result {
+            Ok(()) => DeclareCompromiseResult {
+                success: true,
+                error: "".to_string(),
+            },
+            Err(e) => DeclareCompromiseResult {
+                success: false,
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct GetKeyCompromiseResult {
+    pub success: bool,
+    pub compromise: KeyCompromise,
+    pub error: String,
+}
+
+impl From<Result<KeyCompromise, ServiceError>> for GetKeyCompromiseResult {
+    fn from(result: Result<KeyCompromise, ServiceError>) -> Self {
+        match result {
+            Ok(compromise) => GetKeyCompromiseResult {
+                success: true,
+                compromise,
+                error: "".to_string(),
+            },
+            Err(e) => GetKeyCompromiseResult {
+                success: false,
+                compromise: KeyCompromise::default(),
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct GetThresholdTrustBytesResult {
+    pub success: bool,
+    pub error: String,
+    pub result: Vec<u8>,
+}
+
+impl From<Result<Vec<u8>, ServiceError>> for GetThresholdTrustBytesResult {
+    fn from(result: Result<Vec<u8>, ServiceError>) -> Self {
+        match result {
+            Ok(res) => GetThresholdTrustBytesResult {
+                success: true,
+                error: "".to_string(),
+                result: res,
+            },
+            Err(e) => GetThresholdTrustBytesResult {
+                success: false,
+                error: format!("{e}"),
+                result: vec![],
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct IssueThresholdTrustResult {
+    pub success: bool,
+    pub error: String,
+    pub trust: ThresholdTrust,
+}
+
+impl From<Result<ThresholdTrust, ServiceError>> for IssueThresholdTrustResult {
+    fn from(result: Result<ThresholdTrust, ServiceError>) -> Self {
+        match result {
+            Ok(trust) => IssueThresholdTrustResult {
+                success: true,
+                error: "".to_string(),
+                trust,
+            },
+            Err(e) => IssueThresholdTrustResult {
+                success: false,
+                error: format!("{e}"),
+                trust: ThresholdTrust::default(),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct AddThresholdTrustResult {
+    pub success: bool,
+    pub error: String,
+    pub weight: u32,
+}
+
+impl From<Result<u32, ServiceError>> for AddThresholdTrustResult {
+    fn from(result: Result<u32, ServiceError>) -> Self {
+        match result {
+            Ok(weight) => AddThresholdTrustResult {
+                success: true,
+                error: "".to_string(),
+                weight,
+            },
+            Err(e) => AddThresholdTrustResult {
+                success: false,
+                error: format!("{e}"),
+                weight: u32::default(),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct GetDistrustBytesResult {
+    pub success: bool,
+    pub error: String,
+    pub result: Vec<u8>,
+}
+
+impl From<Result<Vec<u8>, ServiceError>> for GetDistrustBytesResult {
+    fn from(result: Result<Vec<u8>, ServiceError>) -> Self {
+        match result {
+            Ok(res) => GetDistrustBytesResult {
+                success: true,
+                error: "".to_string(),
+                result: res,
+            },
+            Err(e) => GetDistrustBytesResult {
+                success: false,
+                error: format!("{e}"),
+                result: vec![],
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct IssueDistrustResult {
+    pub success: bool,
+    pub error: String,
+    pub distrust: Distrust,
+}
+
+impl From<Result<Distrust, ServiceError>> for IssueDistrustResult {
+    fn from(result: Result<Distrust, ServiceError>) -> Self {
+        match result {
+            Ok(distrust) => IssueDistrustResult {
+                success: true,
+                error: "".to_string(),
+                distrust,
+            },
+            Err(e) => IssueDistrustResult {
+                success: false,
+                error: format!("{e}"),
+                distrust: Distrust::default(),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct DistrustResult {
+    pub success: bool,
+    pub error: String,
+}
+
+impl From<Result<(), ServiceError>> for DistrustResult {
+    fn from(result: Result<(), ServiceError>) -> Self {
+        match result {
+            Ok(()) => DistrustResult {
+                success: true,
+                error: "".to_string(),
+            },
+            Err(e) => DistrustResult {
+                success: false,
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct ExportDistrustsResult {
+    pub success: bool,
+    pub distrusts: Vec<Distrust>,
+    pub error: String,
+}
+
+impl From<Result<Vec<Distrust>, ServiceError>> for ExportDistrustsResult {
+    fn from(result: Result<Vec<Distrust>, ServiceError>) -> Self {
+        match result {
+            Ok(distrusts) => ExportDistrustsResult {
+                success: true,
+                distrusts,
+                error: "".to_string(),
+            },
+            Err(e) => ExportDistrustsResult {
+                success: false,
+                distrusts: vec![],
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct SetDistrustPolicyResult {
+    pub success: bool,
+    pub error: String,
+}
+
+impl From<Result<(), ServiceError>> for SetDistrustPolicyResult {
+    fn from(result: Result<(), ServiceError>) -> Self {
+        match result {
+            Ok(()) => SetDistrustPolicyResult {
+                success: true,
+                error: "".to_string(),
+            },
+            Err(e) => SetDistrustPolicyResult {
+                success: false,
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct TrustedKeysResult {
+    pub success: bool,
+    pub trusted_keys: Vec<TrustedKey>,
+    pub error: String,
+}
+
+impl From<Result<Vec<TrustedKey>, ServiceError>> for TrustedKeysResult {
+    fn from(result: Result<Vec<TrustedKey>, ServiceError>) -> Self {
+        match result {
+            Ok(trusted_keys) => TrustedKeysResult {
+                success: true,
+                trusted_keys,
+                error: "".to_string(),
+            },
+            Err(e) => TrustedKeysResult {
+                success: false,
+                trusted_keys: vec![],
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct StatsResult {
+    pub success: bool,
+    pub stats: GraphStats,
+    pub error: String,
+}
+
+impl From<Result<GraphStats, ServiceError>> for StatsResult {
+    fn from(result: Result<GraphStats, ServiceError>) -> Self {
+        match result {
+            Ok(stats) => StatsResult {
+                success: true,
+                stats,
+                error: "".to_string(),
+            },
+            Err(e) => StatsResult {
+                success: false,
+                stats: GraphStats::default(),
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct GetWeightAttestationBytesResult {
+    pub success: bool,
+    pub error: String,
+    /// weight the payload is made for, to be passed to `issue_weight_attestation`
+    pub weight: u32,
+    pub result: Vec<u8>,
+}
+
+impl From<Result<(u32, Vec<u8>), ServiceError>> for GetWeightAttestationBytesResult {
+    fn from(result: Result<(u32, Vec<u8>), ServiceError>) -> Self {
+        match result {
+            Ok((weight, res)) => GetWeightAttestationBytesResult {
+                success: true,
+                error: "".to_string(),
+                weight,
+                result: res,
+            },
+            Err(e) => GetWeightAttestationBytesResult {
+                success: false,
+                error: format!("{e}"),
+                weight: 0,
+                result: vec![],
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct IssueWeightAttestationResult {
+    pub success: bool,
+    pub error: String,
+    pub attestation: WeightAttestation,
+}
+
+impl From<Result<WeightAttestation, ServiceError>> for IssueWeightAttestationResult {
+    fn from(result: Result<WeightAttestation, ServiceError>) -> Self {
+        match result {
+            Ok(attestation) => IssueWeightAttestationResult {
+                success: true,
+                error: "".to_string(),
+                attestation,
+            },
+            Err(e) => IssueWeightAttestationResult {
+                success: false,
+                error: format!("{e}"),
+                attestation: WeightAttestation::default(),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct StateRootResult {
+    pub success: bool,
+    pub state_root: Vec<u8>,
+    pub error: String,
+}
+
+impl From<Result<Vec<u8>, ServiceError>> for StateRootResult {
+    fn from(result: Result<Vec<u8>, ServiceError>) -> Self {
+        match result {
+            Ok(state_root) => StateRootResult {
+                success: true,
+                state_root,
+                error: "".to_string(),
+            },
+            Err(e) => StateRootResult {
+                success: false,
+                state_root: vec![],
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct GetTreeHeadBytesResult {
+    pub success: bool,
+    pub error: String,
+    /// log size and tree root the payload is made for, to be passed to `issue_tree_head`
+    pub size: u64,
+    pub root: Vec<u8>,
+    pub result: Vec<u8>,
+}
+
+impl From<Result<(u64, Vec<u8>, Vec<u8>), ServiceError>> for GetTreeHeadBytesResult {
+    fn from(result: Result<(u64, Vec<u8>, Vec<u8>), ServiceError>) -> Self {
+        match result {
+            Ok((size, root, res)) => GetTreeHeadBytesResult {
+                success: true,
+                error: "".to_string(),
+                size,
+                root,
+                result: res,
+            },
+            Err(e) => GetTreeHeadBytesResult {
+                success: false,
+                error: format!("{e}"),
+                size: 0,
+                root: vec![],
+                result: vec![],
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct IssueTreeHeadResult {
+    pub success: bool,
+    pub error: String,
+    pub tree_head: SignedTreeHead,
+}
+
+impl From<Result<SignedTreeHead, ServiceError>> for IssueTreeHeadResult {
+    fn from(result: Result<SignedTreeHead, ServiceError>) -> Self {
+        match result {
+            Ok(tree_head) => IssueTreeHeadResult {
+                success: true,
+                error: "".to_string(),
+                tree_head,
+            },
+            Err(e) => IssueTreeHeadResult {
+                success: false,
+                error: format!("{e}"),
+                tree_head: SignedTreeHead::default(),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct LogProofResult {
+    pub success: bool,
+    pub error: String,
+    /// hashes of the inclusion or consistency proof, 32 bytes each
+    pub path: Vec<Vec<u8>>,
+}
+
+impl From<Result<Vec<Vec<u8>>, ServiceError>> for LogProofResult {
+    fn from(result: Result<Vec<Vec<u8>>, ServiceError>) -> Self {
+        match result {
+            Ok(path) => LogProofResult {
+                success: true,
+                error: "".to_string(),
+                path,
+            },
+            Err(e) => LogProofResult {
+                success: false,
+                error: format!("{e}"),
+                path: vec![],
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct GcResult {
+    pub success: bool,
+    pub error: String,
+    pub expired: u64,
+    pub unreachable: u64,
+    pub revocations: u64,
+}
+
+impl From<Result<GcReport, ServiceError>> for GcResult {
+    fn from(result: Result<GcReport, ServiceError>) -> Self {
+        match result {
+            Ok(report) => GcResult {
+                success: true,
+                error: "".to_string(),
+                expired: report.expired,
+                unreachable: report.unreachable,
+                revocations: report.revocations,
+            },
+            Err(e) => GcResult {
+                success: false,
+                error: format!("{e}"),
+                expired: 0,
+                unreachable: 0,
+                revocations: 0,
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct SetGcScheduleResult {
+    pub success: bool,
+    pub error: String,
+}
+
+impl From<Result<(), ServiceError>> for SetGcScheduleResult {
+    fn from(result: Result<(), ServiceError>) -> Self {
+        match result {
+            Ok(()) => SetGcScheduleResult {
+                success: true,
+                error: "".to_string(),
+            },
+            Err(e) => SetGcScheduleResult {
+                success: false,
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct SetQuotasResult {
+    pub success: bool,
+    pub error: String,
+}
+
+impl From<Result<(), ServiceError>> for SetQuotasResult {
+    fn from(result: Result<(), ServiceError>) -> Self {
+        match result {
+            Ok(()) => SetQuotasResult {
+                success: true,
+                error: "".to_string(),
+            },
+            Err(e) => SetQuotasResult {
+                success: false,
+                error: format!("{e}"),
+            },
+        }
+    }
+}
+
+#[marine]
+pub struct SetPendingPoolResult {
+    pub success: bool,
+    pub error: String,
+}
+
+impl From<Result<(), ServiceError>> for SetPendingPoolResult {
+    fn from(result: Result<(), ServiceError>) -> Self {
+        match result {
+            Ok(()) => SetPendingPoolResult {
+                success: true,
+                error: "".to_string(),
+            },
+            Err(e) => SetPendingPoolResult {
+                success: false,
+                error: format!("{e}"),
+            },
+        }
+    }
+}
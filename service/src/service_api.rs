@@ -1,18 +1,30 @@
-use crate::dto::{Certificate, Revocation, Trust};
+use crate::dto::{
+    Certificate, Distrust, KeyCompromise, Revocation, RevocationList, ThresholdAnchor,
+    ThresholdTrust, Trust, TrustedKey,
+};
 use crate::error::ServiceError;
 use crate::misc::{check_timestamp_tetraplets, extract_public_key, with_tg, wrapped_try};
 use crate::results::{
-    AddTrustResult, AllCertsResult, ExportRevocationsResult, GetRevokeBytesResult,
-    GetTrustBytesResult, InsertResult, IssueRevocationResult, IssueTrustResult, RevokeResult,
-    SetRootResult, VerifyTrustResult, WeightResult,
+    AddThresholdTrustResult, AddTrustResult, AllCertsResult, DeclareCompromiseResult,
+    DistrustResult, ExportDistrustsResult, ExportRevocationListResult, ExportRevocationsResult,
+    ExportTrustsResult, GcResult, GetDistrustBytesResult, GetKeyCompromiseBytesResult,
+    GetKeyCompromiseResult, GetRevocationListBytesResult, GetRevokeBytesResult,
+    GetThresholdTrustBytesResult, GetTreeHeadBytesResult, GetTrustBytesResult,
+    GetWeightAttestationBytesResult, ImportRevocationListResult, InsertResult, IssueDistrustResult,
+    IssueKeyCompromiseResult, IssueRevocationListResult, IssueRevocationResult,
+    IssueThresholdTrustResult, IssueTreeHeadResult, IssueTrustResult, IssueWeightAttestationResult,
+    LogProofResult, RevokeResult, SetDistrustPolicyResult, SetGcScheduleResult,
+    SetPendingPoolResult, SetQuotasResult, SetRootResult, StateRootResult, StatsResult,
+    TrustedKeysResult, VerifyTrustResult, WeightResult,
 };
+use crate::settings;
 use crate::storage_impl::SQLiteStorage;
 use fluence_keypair::Signature;
 use marine_rs_sdk::{get_call_parameters, marine, CallParameters};
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
 use std::time::Duration;
-use trust_graph::TrustGraph;
+use trust_graph::{SigningVersion, TrustGraph};
 
 #[marine]
 /// Only service owner can set roots
@@ -34,6 +46,29 @@ fn set_root(peer_id: String, max_chain_len: u32) -> SetRootResult {
     }
 }
 
+#[marine]
+/// Only service owner can set roots
+fn set_threshold_root(peer_ids: Vec<String>, threshold: u32, max_chain_len: u32) -> SetRootResult {
+    let call_parameters: CallParameters = marine_rs_sdk::get_call_parameters();
+    let init_peer_id = call_parameters.init_peer_id;
+    if call_parameters.service_creator_peer_id == init_peer_id {
+        with_tg(|tg| {
+            let anchor = ThresholdAnchor {
+                keys: peer_ids,
+                threshold,
+            };
+            tg.set_threshold_root(anchor.try_into()?, max_chain_len)?;
+            Ok(())
+        })
+        .into()
+    } else {
+        SetRootResult {
+            success: false,
+            error: ServiceError::NotOwner.to_string(),
+        }
+    }
+}
+
 #[marine]
 /// add a certificate in string representation to trust graph if it is valid
 /// see `trust_graph::Certificate` class for string encoding/decoding
@@ -136,6 +171,59 @@ fn get_weight(peer_id: String, timestamp_sec: u64) -> WeightResult {
     .into()
 }
 
+#[marine]
+/// Weight of `peer_id` and the attestation payload for it to be signed by the host Sig service
+fn get_weight_attestation_bytes(
+    peer_id: String,
+    timestamp_sec: u64,
+) -> GetWeightAttestationBytesResult {
+    with_tg(|tg| {
+        let cp = get_call_parameters();
+        check_timestamp_tetraplets(&cp, 1)?;
+        let host_pk = extract_public_key(cp.host_id)?;
+        let public_key = extract_public_key(peer_id)?;
+        let timestamp = Duration::from_secs(timestamp_sec);
+        let weight = tg.weight(&public_key, timestamp)?;
+
+        Ok((
+            weight,
+            trust_graph::WeightAttestation::signature_bytes(
+                &host_pk,
+                &public_key,
+                weight,
+                timestamp,
+            ),
+        ))
+    })
+    .into()
+}
+
+#[marine]
+/// Attestation of the host made from the payload signature
+fn issue_weight_attestation(
+    peer_id: String,
+    weight: u32,
+    timestamp_sec: u64,
+    signature_bytes: Vec<u8>,
+) -> IssueWeightAttestationResult {
+    wrapped_try(|| {
+        let host_pk = extract_public_key(get_call_parameters().host_id)?;
+        let public_key = extract_public_key(peer_id)?;
+        let signature = Signature::from_bytes(host_pk.get_key_format(), signature_bytes);
+        let attestation = trust_graph::WeightAttestation::new(
+            host_pk,
+            public_key,
+            weight,
+            Duration::from_secs(timestamp_sec),
+            signature,
+        );
+        trust_graph::WeightAttestation::verify(&attestation)?;
+
+        Ok(attestation.into())
+    })
+    .into()
+}
+
 #[marine]
 fn get_weight_from(peer_id: String, issuer: String, timestamp_sec: u64) -> WeightResult {
     with_tg(|tg| {
@@ -150,6 +238,119 @@ fn get_weight_from(peer_id: String, issuer: String, timestamp_sec: u64) -> Weigh
     .into()
 }
 
+#[marine]
+/// Get all keys with weight at least `min_weight`, sorted by weight in descending order
+fn get_trusted_keys(min_weight: u32, timestamp_sec: u64) -> TrustedKeysResult {
+    with_tg(|tg| {
+        check_timestamp_tetraplets(&marine_rs_sdk::get_call_parameters(), 1)?;
+        let keys = tg.trusted_keys(min_weight, Duration::from_secs(timestamp_sec))?;
+        Ok(keys.into_iter().map(TrustedKey::from).collect())
+    })
+    .into()
+}
+
+#[marine]
+/// Same as `get_trusted_keys`, but only certificates starting from `root` are taken into account
+fn get_trusted_keys_from(root: String, min_weight: u32, timestamp_sec: u64) -> TrustedKeysResult {
+    with_tg(|tg| {
+        check_timestamp_tetraplets(&marine_rs_sdk::get_call_parameters(), 2)?;
+        let root_pk = extract_public_key(root)?;
+        let keys = tg.trusted_keys_from(root_pk, min_weight, Duration::from_secs(timestamp_sec))?;
+        Ok(keys.into_iter().map(TrustedKey::from).collect())
+    })
+    .into()
+}
+
+#[marine]
+/// Size and health of the graph for monitoring, expired relations are counted but not removed
+fn get_stats(timestamp_sec: u64) -> StatsResult {
+    with_tg(|tg| {
+        check_timestamp_tetraplets(&marine_rs_sdk::get_call_parameters(), 0)?;
+        Ok(tg.stats(Duration::from_secs(timestamp_sec))?.into())
+    })
+    .into()
+}
+
+#[marine]
+/// Merkle root over roots and relations, equal on nodes that hold the same graph.
+/// Its tree is stored in the database along with the graph, so a restarted service reuses it
+fn get_state_root() -> StateRootResult {
+    with_tg(|tg| Ok(tg.state_root()?.to_vec())).into()
+}
+
+#[marine]
+/// Size and tree root of the transparency log and the tree head payload for them
+/// to be signed by the host Sig service
+fn get_tree_head_bytes(timestamp_sec: u64) -> GetTreeHeadBytesResult {
+    with_tg(|tg| {
+        let cp = get_call_parameters();
+        check_timestamp_tetraplets(&cp, 0)?;
+        let host_pk = extract_public_key(cp.host_id)?;
+        let (size, root) = tg.log_tree_head()?;
+
+        Ok((
+            size,
+            root.to_vec(),
+            trust_graph::SignedTreeHead::signature_bytes(
+                &host_pk,
+                size,
+                &root,
+                Duration::from_secs(timestamp_sec),
+            ),
+        ))
+    })
+    .into()
+}
+
+#[marine]
+/// Tree head of the host made from the payload signature
+fn issue_tree_head(
+    size: u64,
+    root: Vec<u8>,
+    timestamp_sec: u64,
+    signature_bytes: Vec<u8>,
+) -> IssueTreeHeadResult {
+    wrapped_try(|| {
+        let host_pk = extract_public_key(get_call_parameters().host_id)?;
+        let root_len = root.len();
+        let root = root
+            .try_into()
+            .map_err(|_| ServiceError::InvalidTreeRoot(root_len))?;
+        let signature = Signature::from_bytes(host_pk.get_key_format(), signature_bytes);
+        let tree_head = trust_graph::SignedTreeHead::new(
+            host_pk,
+            size,
+            root,
+            Duration::from_secs(timestamp_sec),
+            signature,
+        );
+        trust_graph::SignedTreeHead::verify(&tree_head)?;
+
+        Ok(tree_head.into())
+    })
+    .into()
+}
+
+#[marine]
+/// Proof that the log entry with `sequence` is in the log tree of `size` entries
+fn get_log_inclusion_proof(sequence: u64, size: u64) -> LogProofResult {
+    with_tg(|tg| {
+        let path = tg.log_inclusion_proof(sequence, size)?;
+        Ok(path.iter().map(|h| h.to_vec()).collect())
+    })
+    .into()
+}
+
+#[marine]
+/// Proof that the log tree of `first` entries is a prefix of the log tree of `second` entries
+fn get_log_consistency_proof(first: u64, second: u64) -> LogProofResult {
+    with_tg(|tg| {
+        let path = tg.log_consistency_proof(first, second)?;
+        Ok(path.iter().map(|h| h.to_vec()).collect())
+    })
+    .into()
+}
+
 #[marine]
 fn get_trust_bytes(
     issued_for_peer_id: String,
@@ -159,7 +360,7 @@ fn get_trust_bytes(
     wrapped_try(|| {
         let public_key = extract_public_key(issued_for_peer_id)?;
 
-        Ok(trust_graph::Trust::signature_bytes(
+        Ok(trust_graph::Trust::signature_bytes_v2(
             &public_key,
             Duration::from_secs(expires_at_sec),
             Duration::from_secs(issued_at_sec),
@@ -180,7 +381,8 @@ fn issue_trust(
         let expires_at_sec = Duration::from_secs(expires_at_sec);
         let issued_at_sec = Duration::from_secs(issued_at_sec);
         let signature = Signature::from_bytes(public_key.get_key_format(), trust_bytes);
-        Ok(Trust::from(trust_graph::Trust::new(
+        Ok(Trust::from(trust_graph::Trust::new_with_version(
+            SigningVersion::V2,
             public_key,
             expires_at_sec,
             issued_at_sec,
@@ -190,6 +392,50 @@ fn issue_trust(
     .into()
 }
 
+#[marine]
+fn get_trust_with_confidence_bytes(
+    issued_for_peer_id: String,
+    expires_at_sec: u64,
+    issued_at_sec: u64,
+    confidence: u8,
+) -> GetTrustBytesResult {
+    wrapped_try(|| {
+        let public_key = extract_public_key(issued_for_peer_id)?;
+
+        Ok(trust_graph::Trust::signature_bytes_v3(
+            &public_key,
+            Duration::from_secs(expires_at_sec),
+            Duration::from_secs(issued_at_sec),
+            confidence,
+        ))
+    })
+    .into()
+}
+
+#[marine]
+fn issue_trust_with_confidence(
+    issued_for_peer_id: String,
+    expires_at_sec: u64,
+    issued_at_sec: u64,
+    confidence: u8,
+    trust_bytes: Vec<u8>,
+) -> IssueTrustResult {
+    wrapped_try(|| {
+        let public_key = extract_public_key(issued_for_peer_id)?;
+        let expires_at_sec = Duration::from_secs(expires_at_sec);
+        let issued_at_sec = Duration::from_secs(issued_at_sec);
+        let signature = Signature::from_bytes(public_key.get_key_format(), trust_bytes);
+        Ok(Trust::from(trust_graph::Trust::new_with_confidence(
+            public_key,
+            expires_at_sec,
+            issued_at_sec,
+            confidence,
+            signature,
+        )))
+    })
+    .into()
+}
+
 #[marine]
 fn verify_trust(trust: Trust, issuer_peer_id: String, timestamp_sec: u64) -> VerifyTrustResult {
     wrapped_try(|| {
@@ -229,7 +475,7 @@ fn add_trust(trust: Trust, issuer_peer_id: String, timestamp_sec: u64) -> AddTru
 fn get_revocation_bytes(revoked_peer_id: String, revoked_at: u64) -> GetRevokeBytesResult {
     wrapped_try(|| {
         let public_key = extract_public_key(revoked_peer_id)?;
-        Ok(trust_graph::Revocation::signature_bytes(
+        Ok(trust_graph::Revocation::signature_bytes_v2(
             &public_key,
             Duration::from_secs(revoked_at),
         ))
@@ -250,7 +496,14 @@ fn issue_revocation(
 
         let revoked_at = Duration::from_secs(revoked_at_sec);
         let signature = Signature::from_bytes(revoked_by_pk.get_key_format(), signature_bytes);
-        Ok(trust_graph::Revocation::new(revoked_by_pk, revoked_pk, revoked_at, signature).into())
+        Ok(trust_graph::Revocation::new_with_version(
+            SigningVersion::V2,
+            revoked_by_pk,
+            revoked_pk,
+            revoked_at,
+            signature,
+        )
+        .into())
     })
     .into()
 }
@@ -281,3 +534,434 @@ fn export_revocations(issued_for: String) -> ExportRevocationsResult {
     })
     .into()
 }
+
+#[marine]
+/// Trusts signed by `issued_by`, to audit and renew them
+fn export_issued_trusts(issued_by: String) -> ExportTrustsResult {
+    with_tg(|tg| {
+        let issued_by_pk = extract_public_key(issued_by)?;
+        Ok(tg
+            .get_issued_trusts(issued_by_pk)?
+            .into_iter()
+            .map(|t| t.into())
+            .collect())
+    })
+    .into()
+}
+
+#[marine]
+/// Revocations signed by `issued_by`
+fn export_issued_revocations(issued_by: String) -> ExportRevocationsResult {
+    with_tg(|tg| {
+        let issued_by_pk = extract_public_key(issued_by)?;
+        Ok(tg
+            .get_issued_revocations(issued_by_pk)?
+            .into_iter()
+            .map(|r| r.into())
+            .collect())
+    })
+    .into()
+}
+
+#[marine]
+fn get_distrust_bytes(distrusted_peer_id: String, distrusted_at: u64) -> GetDistrustBytesResult {
+    wrapped_try(|| {
+        let public_key = extract_public_key(distrusted_peer_id)?;
+        Ok(trust_graph::Distrust::signature_bytes(
+            &public_key,
+            Duration::from_secs(distrusted_at),
+        ))
+    })
+    .into()
+}
+
+#[marine]
+fn issue_distrust(
+    distrusted_by_peer_id: String,
+    distrusted_peer_id: String,
+    distrusted_at_sec: u64,
+    signature_bytes: Vec<u8>,
+) -> IssueDistrustResult {
+    wrapped_try(|| {
+        let distrusted_pk = extract_public_key(distrusted_peer_id)?;
+        let distrusted_by_pk = extract_public_key(distrusted_by_peer_id)?;
+
+        let distrusted_at = Duration::from_secs(distrusted_at_sec);
+        let signature = Signature::from_bytes(distrusted_by_pk.get_key_format(), signature_bytes);
+        Ok(
+            trust_graph::Distrust::new(distrusted_by_pk, distrusted_pk, distrusted_at, signature)
+                .into(),
+        )
+    })
+    .into()
+}
+
+#[marine]
+fn distrust(distrust: Distrust, timestamp_sec: u64) -> DistrustResult {
+    with_tg(|tg| {
+        check_timestamp_tetraplets(&marine_rs_sdk::get_call_parameters(), 1)?;
+
+        if distrust.distrusted_at > timestamp_sec {
+            return Err(ServiceError::InvalidTimestamp("distrust".to_string()));
+        }
+
+        Ok(tg.distrust(distrust.try_into()?)?)
+    })
+    .into()
+}
+
+#[marine]
+fn export_distrusts(issued_for: String) -> ExportDistrustsResult {
+    with_tg(|tg| {
+        let issued_for_pk = extract_public_key(issued_for)?;
+        Ok(tg
+            .get_distrusts(issued_for_pk)?
+            .into_iter()
+            .map(|d| d.into())
+            .collect())
+    })
+    .into()
+}
+
+#[marine]
+/// Only service owner can set distrust policy.
+/// `policy` is one of "ignore", "penalty" or "veto", `penalty` is used only by "penalty"
+fn set_distrust_policy(policy: String, penalty: u32, radius: u32) -> SetDistrustPolicyResult {
+    let call_parameters: CallParameters = marine_rs_sdk::get_call_parameters();
+    let init_peer_id = call_parameters.init_peer_id;
+    if call_parameters.service_creator_peer_id == init_peer_id {
+        with_tg(|tg| {
+            let distrust_policy = settings::distrust_policy(&policy, penalty)?;
+            tg.storage()
+                .set_setting(settings::DISTRUST_POLICY, &(policy, penalty, radius))?;
+            tg.set_distrust_policy(distrust_policy, radius);
+            Ok(())
+        })
+        .into()
+    } else {
+        SetDistrustPolicyResult {
+            success: false,
+            error: ServiceError::NotOwner.to_string(),
+        }
+    }
+}
+
+#[marine]
+/// Only service owner can run gc.
+/// Revocations older than `revocation_retention_sec` are removed, 0 keeps them forever
+fn gc(
+    remove_expired: bool,
+    remove_unreachable: bool,
+    revocation_retention_sec: u64,
+    timestamp_sec: u64,
+) -> GcResult {
+    let call_parameters: CallParameters = marine_rs_sdk::get_call_parameters();
+    let init_peer_id = call_parameters.init_peer_id.clone();
+    if call_parameters.service_creator_peer_id == init_peer_id {
+        with_tg(|tg| {
+            check_timestamp_tetraplets(&call_parameters, 3)?;
+            let policy =
+                settings::gc_policy(remove_expired, remove_unreachable, revocation_retention_sec);
+            Ok(tg.gc(Duration::from_secs(timestamp_sec), &policy)?)
+        })
+        .into()
+    } else {
+        Err(ServiceError::NotOwner).into()
+    }
+}
+
+#[marine]
+/// Only service owner can set gc schedule.
+/// `schedule` is one of: on_read, every, manual.
+/// Other arguments are used only by `every`, expired auths are always removed by it
+fn set_gc_schedule(
+    schedule: String,
+    interval_sec: u64,
+    remove_unreachable: bool,
+    revocation_retention_sec: u64,
+) -> SetGcScheduleResult {
+    let call_parameters: CallParameters = marine_rs_sdk::get_call_parameters();
+    let init_peer_id = call_parameters.init_peer_id;
+    if call_parameters.service_creator_peer_id == init_peer_id {
+        with_tg(|tg| {
+            let gc_schedule = settings::gc_schedule(
+                &schedule,
+                interval_sec,
+                remove_unreachable,
+                revocation_retention_sec,
+            )?;
+            tg.storage().set_setting(
+                settings::GC_SCHEDULE,
+                &(
+                    schedule,
+                    interval_sec,
+                    remove_unreachable,
+                    revocation_retention_sec,
+                ),
+            )?;
+            tg.set_gc_schedule(gc_schedule);
+            Ok(())
+        })
+        .into()
+    } else {
+        Err(ServiceError::NotOwner).into()
+    }
+}
+
+#[marine]
+/// Only service owner can set quotas.
+/// Limits trusts issued by a key and issued for a key, 0 means no limit.
+/// `eviction` is one of: reject, evict_oldest
+fn set_quotas(max_issued: u32, max_received: u32, eviction: String) -> SetQuotasResult {
+    let call_parameters: CallParameters = marine_rs_sdk::get_call_parameters();
+    let init_peer_id = call_parameters.init_peer_id;
+    if call_parameters.service_creator_peer_id == init_peer_id {
+        with_tg(|tg| {
+            let quotas = settings::quotas(max_issued, max_received, &eviction)?;
+            tg.storage()
+                .set_setting(settings::QUOTAS, &(max_issued, max_received, eviction))?;
+            tg.set_quotas(quotas);
+            Ok(())
+        })
+        .into()
+    } else {
+        Err(ServiceError::NotOwner).into()
+    }
+}
+
+#[marine]
+/// Only service owner can configure the pending pool.
+/// Trusts from issuers without weight are kept for `ttl_sec` and added once the issuer gains weight.
+/// `max_size` 0 disables the pool
+fn set_pending_pool(max_size: u32, ttl_sec: u64) -> SetPendingPoolResult {
+    let call_parameters: CallParameters = marine_rs_sdk::get_call_parameters();
+    let init_peer_id = call_parameters.init_peer_id;
+    if call_parameters.service_creator_peer_id == init_peer_id {
+        with_tg(|tg| {
+            tg.storage()
+                .set_setting(settings::PENDING_POOL, &(max_size, ttl_sec))?;
+            tg.set_pending_pool(settings::pending_pool(max_size, ttl_sec));
+            Ok(())
+        })
+        .into()
+    } else {
+        Err(ServiceError::NotOwner).into()
+    }
+}
+
+#[marine]
+fn get_revocation_list_bytes(
+    sequence: u64,
+    issued_at_sec: u64,
+    expires_at_sec: u64,
+    revocations: Vec<Revocation>,
+) -> GetRevocationListBytesResult {
+    wrapped_try(|| {
+        let revocations = revocations
+            .into_iter()
+            .map(trust_graph::Revocation::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(trust_graph::RevocationList::signature_bytes(
+            sequence,
+            Duration::from_secs(issued_at_sec),
+            Duration::from_secs(expires_at_sec),
+            &revocations,
+        ))
+    })
+    .into()
+}
+
+#[marine]
+fn issue_revocation_list(
+    issued_by_peer_id: String,
+    sequence: u64,
+    issued_at_sec: u64,
+    expires_at_sec: u64,
+    revocations: Vec<Revocation>,
+    signature_bytes: Vec<u8>,
+) -> IssueRevocationListResult {
+    wrapped_try(|| {
+        let issued_by_pk = extract_public_key(issued_by_peer_id)?;
+        let revocations = revocations
+            .into_iter()
+            .map(trust_graph::Revocation::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let signature = Signature::from_bytes(issued_by_pk.get_key_format(), signature_bytes);
+        Ok(trust_graph::RevocationList::new(
+            issued_by_pk,
+            sequence,
+            Duration::from_secs(issued_at_sec),
+            Duration::from_secs(expires_at_sec),
+            revocations,
+            signature,
+        )
+        .into())
+    })
+    .into()
+}
+
+#[marine]
+fn import_revocation_list(
+    revocation_list: RevocationList,
+    timestamp_sec: u64,
+) -> ImportRevocationListResult {
+    with_tg(|tg| {
+        check_timestamp_tetraplets(&marine_rs_sdk::get_call_parameters(), 1)?;
+
+        if revocation_list.issued_at > timestamp_sec {
+            return Err(ServiceError::InvalidTimestamp(
+                "revocation list".to_string(),
+            ));
+        }
+
+        Ok(tg.import_revocation_list(
+            revocation_list.try_into()?,
+            Duration::from_secs(timestamp_sec),
+        )?)
+    })
+    .into()
+}
+
+#[marine]
+fn export_revocation_list(issued_by: String) -> ExportRevocationListResult {
+    with_tg(|tg| {
+        let issued_by_pk = extract_public_key(issued_by.clone())?;
+        tg.export_revocation_list(issued_by_pk)?
+            .map(RevocationList::from)
+            .ok_or(ServiceError::RevocationListNotFound(issued_by))
+    })
+    .into()
+}
+
+#[marine]
+fn get_key_compromise_bytes(
+    peer_id: String,
+    compromised_at_sec: u64,
+) -> GetKeyCompromiseBytesResult {
+    wrapped_try(|| {
+        let public_key = extract_public_key(peer_id)?;
+        Ok(trust_graph::KeyCompromise::signature_bytes(
+            &public_key,
+            Duration::from_secs(compromised_at_sec),
+        ))
+    })
+    .into()
+}
+
+#[marine]
+fn issue_key_compromise(
+    peer_id: String,
+    compromised_at_sec: u64,
+    signature_bytes: Vec<u8>,
+) -> IssueKeyCompromiseResult {
+    wrapped_try(|| {
+        let public_key = extract_public_key(peer_id)?;
+
+        let compromised_at = Duration::from_secs(compromised_at_sec);
+        let signature = Signature::from_bytes(public_key.get_key_format(), signature_bytes);
+        Ok(trust_graph::KeyCompromise::new(public_key, compromised_at, signature).into())
+    })
+    .into()
+}
+
+#[marine]
+fn declare_compromise(compromise: KeyCompromise, timestamp_sec: u64) -> DeclareCompromiseResult {
+    with_tg(|tg| {
+        check_timestamp_tetraplets(&marine_rs_sdk::get_call_parameters(), 1)?;
+
+        if compromise.compromised_at > timestamp_sec {
+            return Err(ServiceError::InvalidTimestamp("compromise".to_string()));
+        }
+
+        Ok(tg.declare_compromise(compromise.try_into()?)?)
+    })
+    .into()
+}
+
+#[marine]
+fn get_key_compromise(peer_id: String) -> GetKeyCompromiseResult {
+    with_tg(|tg| {
+        let public_key = extract_public_key(peer_id.clone())?;
+        tg.get_key_compromise(public_key)?
+            .map(KeyCompromise::from)
+            .ok_or(ServiceError::KeyCompromiseNotFound(peer_id))
+    })
+    .into()
+}
+
+#[marine]
+fn get_threshold_trust_bytes(
+    anchor: ThresholdAnchor,
+    issued_for_peer_id: String,
+    expires_at_sec: u64,
+    issued_at_sec: u64,
+) -> GetThresholdTrustBytesResult {
+    wrapped_try(|| {
+        let anchor = anchor.try_into()?;
+        let public_key = extract_public_key(issued_for_peer_id)?;
+
+        Ok(trust_graph::ThresholdTrust::signature_bytes(
+            &anchor,
+            &public_key,
+            Duration::from_secs(expires_at_sec),
+            Duration::from_secs(issued_at_sec),
+        ))
+    })
+    .into()
+}
+
+#[marine]
+fn issue_threshold_trust(
+    anchor: ThresholdAnchor,
+    issued_for_peer_id: String,
+    expires_at_sec: u64,
+    issued_at_sec: u64,
+    signed_by: Vec<String>,
+    signatures_bytes: Vec<Vec<u8>>,
+) -> IssueThresholdTrustResult {
+    wrapped_try(|| {
+        if signed_by.len() != signatures_bytes.len() {
+            return Err(ServiceError::SignersMismatch(
+                signed_by.len(),
+                signatures_bytes.len(),
+            ));
+        }
+
+        let anchor = anchor.try_into()?;
+        let public_key = extract_public_key(issued_for_peer_id)?;
+        let mut signatures = vec![];
+        for (peer_id, signature_bytes) in signed_by.into_iter().zip(signatures_bytes) {
+            let signer = extract_public_key(peer_id)?;
+            let signature = Signature::from_bytes(signer.get_key_format(), signature_bytes);
+            signatures.push((signer, signature));
+        }
+
+        Ok(trust_graph::ThresholdTrust::new(
+            anchor,
+            public_key,
+            Duration::from_secs(expires_at_sec),
+            Duration::from_secs(issued_at_sec),
+            signatures,
+        )
+        .into())
+    })
+    .into()
+}
+
+#[marine]
+fn add_threshold_trust(trust: ThresholdTrust, timestamp_sec: u64) -> AddThresholdTrustResult {
+    with_tg(|tg| {
+        check_timestamp_tetraplets(&marine_rs_sdk::get_call_parameters(), 1)?;
+
+        if trust.issued_at > timestamp_sec {
+            return Err(ServiceError::InvalidTimestamp(
+                "threshold trust".to_string(),
+            ));
+        }
+
+        Ok(tg.add_threshold_trust(trust.try_into()?, Duration::from_secs(timestamp_sec))?)
+    })
+    .into()
+}
@@ -0,0 +1,140 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Owner settings of the graph. They are stored in SQLite as the arguments of their setters
+//! and applied again when a new service instance creates its graph.
+
+use crate::error::ServiceError;
+use crate::storage_impl::SQLiteStorage;
+use std::time::Duration;
+use trust_graph::{
+    DistrustPolicy, EvictionPolicy, GcPolicy, GcSchedule, PendingPoolConfig, Quotas, TrustGraph,
+};
+
+pub static DISTRUST_POLICY: &str = "distrust_policy";
+pub static GC_SCHEDULE: &str = "gc_schedule";
+pub static LAST_GC: &str = "last_gc";
+pub static QUOTAS: &str = "quotas";
+pub static PENDING_POOL: &str = "pending_pool";
+
+/// `penalty` is used only by "penalty"
+pub fn distrust_policy(policy: &str, penalty: u32) -> Result<DistrustPolicy, ServiceError> {
+    match policy {
+        "ignore" => Ok(DistrustPolicy::Ignore),
+        "penalty" => Ok(DistrustPolicy::Penalty(penalty)),
+        "veto" => Ok(DistrustPolicy::Veto),
+        _ => Err(ServiceError::UnknownDistrustPolicy(policy.to_string())),
+    }
+}
+
+pub fn gc_policy(
+    remove_expired: bool,
+    remove_unreachable: bool,
+    revocation_retention_sec: u64,
+) -> GcPolicy {
+    GcPolicy {
+        remove_expired,
+        remove_unreachable,
+        // 0 keeps revocations forever
+        revocation_retention: (revocation_retention_sec > 0)
+            .then(|| Duration::from_secs(revocation_retention_sec)),
+    }
+}
+
+/// Arguments besides `schedule` are used only by "every", expired auths are always removed by it
+pub fn gc_schedule(
+    schedule: &str,
+    interval_sec: u64,
+    remove_unreachable: bool,
+    revocation_retention_sec: u64,
+) -> Result<GcSchedule, ServiceError> {
+    match schedule {
+        "on_read" => Ok(GcSchedule::OnRead),
+        "every" => Ok(GcSchedule::Every {
+            interval: Duration::from_secs(interval_sec),
+            policy: gc_policy(true, remove_unreachable, revocation_retention_sec),
+        }),
+        "manual" => Ok(GcSchedule::Manual),
+        _ => Err(ServiceError::UnknownGcSchedule(schedule.to_string())),
+    }
+}
+
+/// 0 means no limit
+pub fn quotas(max_issued: u32, max_received: u32, eviction: &str) -> Result<Quotas, ServiceError> {
+    let eviction = match eviction {
+        "reject" => EvictionPolicy::Reject,
+        "evict_oldest" => EvictionPolicy::EvictOldest,
+        _ => return Err(ServiceError::UnknownEvictionPolicy(eviction.to_string())),
+    };
+
+    Ok(Quotas {
+        max_issued: (max_issued > 0).then_some(max_issued),
+        max_received: (max_received > 0).then_some(max_received),
+        eviction,
+    })
+}
+
+/// `max_size` 0 disables the pool
+pub fn pending_pool(max_size: u32, ttl_sec: u64) -> Option<PendingPoolConfig> {
+    (max_size > 0).then(|| PendingPoolConfig {
+        max_size,
+        ttl: Duration::from_secs(ttl_sec),
+    })
+}
+
+/// Apply settings stored by the owner to a new graph
+pub fn load_settings(tg: &mut TrustGraph<SQLiteStorage>) -> Result<(), ServiceError> {
+    let stored: Option<(String, u32, u32)> = tg.storage().get_setting(DISTRUST_POLICY)?;
+    if let Some((policy, penalty, radius)) = stored {
+        tg.set_distrust_policy(distrust_policy(&policy, penalty)?, radius);
+    }
+
+    let stored: Option<(String, u64, bool, u64)> = tg.storage().get_setting(GC_SCHEDULE)?;
+    if let Some((schedule, interval_sec, remove_unreachable, revocation_retention_sec)) = stored {
+        tg.set_gc_schedule(gc_schedule(
+            &schedule,
+            interval_sec,
+            remove_unreachable,
+            revocation_retention_sec,
+        )?);
+    }
+
+    let stored: Option<u64> = tg.storage().get_setting(LAST_GC)?;
+    if let Some(last_gc_sec) = stored {
+        tg.set_last_gc(Some(Duration::from_secs(last_gc_sec)));
+    }
+
+    let stored: Option<(u32, u32, String)> = tg.storage().get_setting(QUOTAS)?;
+    if let Some((max_issued, max_received, eviction)) = stored {
+        tg.set_quotas(quotas(max_issued, max_received, &eviction)?);
+    }
+
+    let stored: Option<(u32, u64)> = tg.storage().get_setting(PENDING_POOL)?;
+    if let Some((max_size, ttl_sec)) = stored {
+        tg.set_pending_pool(pending_pool(max_size, ttl_sec));
+    }
+
+    Ok(())
+}
+
+/// Store the time of the last garbage collection, so `every` keeps its interval after a restart
+pub fn store_last_gc(tg: &TrustGraph<SQLiteStorage>) -> Result<(), ServiceError> {
+    if let Some(last_gc) = tg.last_gc() {
+        tg.storage().set_setting(LAST_GC, &last_gc.as_secs())?;
+    }
+
+    Ok(())
+}
@@ -12,17 +12,21 @@ use fluence_keypair::Signature;
 use marine_sqlite_connector::{Connection, Error as InternalSqliteError, Value};
 use rmp_serde::decode::Error as RmpDecodeError;
 use rmp_serde::encode::Error as RmpEncodeError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::convert::From;
 use std::str::FromStr;
 use std::time::Duration;
 use thiserror::Error as ThisError;
 use trust_graph::{
-    Auth, PublicKeyHashable as PK, PublicKeyHashable, Revocation, Storage, StorageError, Trust,
-    TrustRelation, WeightFactor,
+    Auth, Distrust, KeyCompromise, LogEntry, MerkleHash, PendingTrust, PublicKeyHashable as PK,
+    PublicKeyHashable, Revocation, RevocationList, SigningVersion, Storage, StorageError,
+    ThresholdAnchor, ThresholdTrust, Trust, TrustRelation, WeightFactor,
 };
 
 static AUTH_TYPE: i64 = 0;
 static REVOCATION_TYPE: i64 = 1;
+static DISTRUST_TYPE: i64 = 2;
 pub static DB_PATH: &str = "/tmp/trust-graph.sqlite";
 
 pub fn create_tables() {
@@ -37,10 +41,33 @@ pub fn create_tables() {
         issued_at INTEGER,
         expires_at INTEGER,
         signature TEXT,
+        signing_version INTEGER DEFAULT 1,
+        confidence INTEGER DEFAULT 255,
         PRIMARY KEY (issued_for, issued_by)
         );",
         )
         .unwrap();
+    // outgoing relations of a key are looked up by issuer
+    connection
+        .execute(
+            "CREATE INDEX IF NOT EXISTS trust_relations_issued_by ON trust_relations(issued_by);",
+        )
+        .unwrap();
+    // tables created before v2 signatures and confidence lack these columns
+    add_column_if_missing(
+        &connection,
+        "trust_relations",
+        "signing_version",
+        "INTEGER DEFAULT 1",
+    )
+    .unwrap();
+    add_column_if_missing(
+        &connection,
+        "trust_relations",
+        "confidence",
+        "INTEGER DEFAULT 255",
+    )
+    .unwrap();
     connection
         .execute(
             "CREATE TABLE IF NOT EXISTS roots(
@@ -49,6 +76,129 @@ pub fn create_tables() {
         );",
         )
         .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS revocation_lists(
+        issued_by TEXT PRIMARY KEY,
+        sequence INTEGER,
+        list BLOB
+        );",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS key_compromises(
+        public_key TEXT PRIMARY KEY,
+        compromised_at INTEGER,
+        signature TEXT
+        );",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS threshold_anchors(
+        id TEXT PRIMARY KEY,
+        anchor BLOB,
+        weight_factor INTEGER
+        );",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS threshold_trusts(
+        issued_for TEXT PRIMARY KEY,
+        trust BLOB
+        );",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS pending_trusts(
+        issued_for TEXT,
+        issued_by TEXT,
+        added_at INTEGER,
+        pending BLOB,
+        PRIMARY KEY (issued_for, issued_by)
+        );",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE INDEX IF NOT EXISTS pending_trusts_issued_by ON pending_trusts(issued_by);",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS revocation_tombstones(
+        issued_for TEXT,
+        issued_by TEXT,
+        revoked_at INTEGER,
+        PRIMARY KEY (issued_for, issued_by)
+        );",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS transparency_log(
+        sequence INTEGER PRIMARY KEY,
+        entry BLOB
+        );",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS state_leaves(
+        key BLOB PRIMARY KEY,
+        bucket INTEGER,
+        leaf BLOB,
+        expires_at INTEGER
+        );",
+        )
+        .unwrap();
+    connection
+        .execute("CREATE INDEX IF NOT EXISTS state_leaves_bucket ON state_leaves(bucket);")
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS state_buckets(
+        bucket INTEGER PRIMARY KEY,
+        root BLOB
+        );",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS settings(
+        name TEXT PRIMARY KEY,
+        value BLOB
+        );",
+        )
+        .unwrap();
+}
+
+/// Add a column to a table created by an older version of the service.
+fn add_column_if_missing(
+    connection: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), SQLiteStorageError> {
+    let mut cursor = connection
+        .prepare(format!("PRAGMA table_info({table});"))?
+        .cursor();
+
+    // rows of table_info are (cid, name, type, notnull, dflt_value, pk)
+    while let Some(row) = cursor.next()? {
+        if row[1].as_string() == Some(column) {
+            return Ok(());
+        }
+    }
+
+    connection.execute(format!(
+        "ALTER TABLE {table} ADD COLUMN {column} {definition};"
+    ))?;
+
+    Ok(())
 }
 
 pub struct SQLiteStorage {
@@ -61,6 +211,45 @@ impl SQLiteStorage {
         SQLiteStorage { connection }
     }
 
+    /// Owner setting of the service, see `crate::settings`
+    pub fn get_setting<T: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, SQLiteStorageError> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT value FROM settings WHERE name = ?")?
+            .cursor();
+
+        cursor.bind(&[Value::String(name.to_string())])?;
+
+        if let Some(row) = cursor.next()? {
+            let value = row[0].as_binary().ok_or(FieldConversionDB)?;
+            Ok(Some(rmp_serde::from_slice(value)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn set_setting<T: Serialize>(
+        &self,
+        name: &str,
+        value: &T,
+    ) -> Result<(), SQLiteStorageError> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO settings VALUES (?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(name.to_string()),
+            Value::Binary(rmp_serde::to_vec(value)?),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
     fn update_relation(&mut self, relation: TrustRelation) -> Result<(), SQLiteStorageError> {
         match self.get_relation(
             relation.issued_for().as_ref(),
@@ -78,14 +267,54 @@ impl SQLiteStorage {
                 }
             }
 
+            Some(TrustRelation::Distrust(distrust)) => {
+                if distrust.distrusted_at < relation.issued_at() {
+                    self.insert(relation)?;
+                }
+            }
+
             None => {
-                self.insert(relation)?;
+                let revoked_at = self.get_tombstone(
+                    relation.issued_for().as_ref(),
+                    relation.issued_by().as_ref(),
+                )?;
+                match revoked_at {
+                    Some(revoked_at) if revoked_at >= relation.issued_at() => {}
+                    _ => self.insert(relation)?,
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Time of the revocation removed by gc between the keys
+    fn get_tombstone(
+        &self,
+        issued_for: &PK,
+        issued_by: &PK,
+    ) -> Result<Option<Duration>, SQLiteStorageError> {
+        let mut cursor = self
+            .connection
+            .prepare(
+                "SELECT revoked_at FROM revocation_tombstones \
+             WHERE issued_for = ? AND issued_by = ?",
+            )?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(format!("{issued_for}")),
+            Value::String(format!("{issued_by}")),
+        ])?;
+
+        if let Some(row) = cursor.next()? {
+            let revoked_at = row[0].as_integer().ok_or(FieldConversionDB)? as u64;
+            Ok(Some(Duration::from_secs(revoked_at)))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn get_relations(
         &self,
         issued_for: &PublicKeyHashable,
@@ -94,8 +323,9 @@ impl SQLiteStorage {
         let mut cursor = self
             .connection
             .prepare(
-                "SELECT relation_type, issued_for, issued_by, issued_at, expires_at, signature \
-             FROM trust_relations WHERE issued_for = ? and relation_type = ?",
+                "SELECT relation_type, issued_for, issued_by, issued_at, expires_at, signature, \
+             signing_version, confidence FROM trust_relations \
+             WHERE issued_for = ? and relation_type = ?",
             )?
             .cursor();
 
@@ -160,6 +390,12 @@ fn parse_relation(row: &[Value]) -> Result<TrustRelation, SQLiteStorageError> {
     let issued_at = Duration::from_secs(row[3].as_integer().ok_or(FieldConversionDB)? as u64);
     let expires_at = Duration::from_secs(row[4].as_integer().ok_or(FieldConversionDB)? as u64);
     let signature = Signature::decode(row[5].as_binary().ok_or(FieldConversionDB)?.to_vec())?;
+    let version = u8::try_from(row[6].as_integer().ok_or(FieldConversionDB)?)
+        .ok()
+        .and_then(SigningVersion::from_u8)
+        .ok_or(FieldConversionDB)?;
+    let confidence = u8::try_from(row[7].as_integer().ok_or(FieldConversionDB)?)
+        .map_err(|_| FieldConversionDB)?;
 
     if relation_type == AUTH_TYPE {
         Ok(TrustRelation::Auth(Auth {
@@ -168,19 +404,37 @@ fn parse_relation(row: &[Value]) -> Result<TrustRelation, SQLiteStorageError> {
                 expires_at,
                 signature,
                 issued_at,
+                version,
+                confidence,
             },
             issued_by: issued_by.into(),
         }))
+    } else if relation_type == DISTRUST_TYPE {
+        Ok(TrustRelation::Distrust(Distrust {
+            pk: issued_for.into(),
+            distrusted_at: issued_at,
+            distrusted_by: issued_by.into(),
+            signature,
+        }))
     } else {
         Ok(TrustRelation::Revocation(Revocation {
             pk: issued_for.into(),
             revoked_at: issued_at,
             revoked_by: issued_by.into(),
             signature,
+            version,
         }))
     }
 }
 
+fn parse_hash(value: &Value) -> Result<MerkleHash, SQLiteStorageError> {
+    value
+        .as_binary()
+        .ok_or(FieldConversionDB)?
+        .try_into()
+        .map_err(|_| FieldConversionDB)
+}
+
 impl From<SQLiteStorageError> for String {
     fn from(err: SQLiteStorageError) -> Self {
         err.into()
@@ -200,8 +454,9 @@ impl Storage for SQLiteStorage {
         let mut cursor = self
             .connection
             .prepare(
-                "SELECT relation_type, issued_for, issued_by, issued_at, expires_at, signature \
-             FROM trust_relations WHERE issued_by = ? AND issued_for = ?",
+                "SELECT relation_type, issued_for, issued_by, issued_at, expires_at, signature, \
+             signing_version, confidence FROM trust_relations \
+             WHERE issued_by = ? AND issued_for = ?",
             )?
             .cursor();
 
@@ -248,14 +503,75 @@ impl Storage for SQLiteStorage {
             }))
     }
 
+    /// return all distrusts issued for pk
+    fn get_distrusts(&self, issued_for: &PublicKeyHashable) -> Result<Vec<Distrust>, Self::Error> {
+        Ok(self
+            .get_relations(issued_for, DISTRUST_TYPE)?
+            .into_iter()
+            .fold(vec![], |mut acc, r| {
+                if let TrustRelation::Distrust(distrust) = r {
+                    acc.push(distrust);
+                }
+
+                acc
+            }))
+    }
+
+    /// return all relations issued by pk
+    fn get_issued_by(
+        &self,
+        issued_by: &PublicKeyHashable,
+    ) -> Result<Vec<TrustRelation>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare(
+                "SELECT relation_type, issued_for, issued_by, issued_at, expires_at, signature, \
+             signing_version, confidence FROM trust_relations WHERE issued_by = ?",
+            )?
+            .cursor();
+
+        cursor.bind(&[Value::String(format!("{issued_by}"))])?;
+        let mut relations: Vec<TrustRelation> = vec![];
+
+        while let Some(row) = cursor.next()? {
+            match parse_relation(row) {
+                Ok(r) => relations.push(r),
+                Err(e) => log::error!("parse_relation: {:?}", e),
+            }
+        }
+
+        Ok(relations)
+    }
+
+    fn get_all_relations(&self) -> Result<Vec<TrustRelation>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare(
+                "SELECT relation_type, issued_for, issued_by, issued_at, expires_at, signature, \
+             signing_version, confidence FROM trust_relations",
+            )?
+            .cursor();
+
+        let mut relations: Vec<TrustRelation> = vec![];
+        while let Some(row) = cursor.next()? {
+            match parse_relation(row) {
+                Ok(r) => relations.push(r),
+                Err(e) => log::error!("parse_relation: {:?}", e),
+            }
+        }
+
+        Ok(relations)
+    }
+
     fn insert(&mut self, relation: TrustRelation) -> Result<(), Self::Error> {
         let mut statement = self
             .connection
-            .prepare("INSERT OR REPLACE INTO trust_relations VALUES (?, ?, ?, ?, ?, ?)")?;
+            .prepare("INSERT OR REPLACE INTO trust_relations VALUES (?, ?, ?, ?, ?, ?, ?, ?)")?;
 
         let relation_type = match relation {
             TrustRelation::Auth(_) => AUTH_TYPE,
             TrustRelation::Revocation(_) => REVOCATION_TYPE,
+            TrustRelation::Distrust(_) => DISTRUST_TYPE,
         };
 
         statement.bind(1, &Value::Integer(relation_type))?;
@@ -270,11 +586,52 @@ impl Storage for SQLiteStorage {
         statement.bind(4, &Value::Integer(relation.issued_at().as_secs() as i64))?;
         statement.bind(5, &Value::Integer(relation.expires_at().as_secs() as i64))?;
         statement.bind(6, &Value::Binary(relation.signature().encode()))?;
+        statement.bind(
+            7,
+            &Value::Integer(i64::from(relation.signing_version().to_u8())),
+        )?;
+        statement.bind(8, &Value::Integer(i64::from(relation.confidence())))?;
 
         statement.next()?;
         Ok(())
     }
 
+    fn remove_relation(&mut self, issued_for: &PK, issued_by: &PK) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("DELETE FROM trust_relations WHERE issued_for = ? AND issued_by = ?")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(format!("{issued_for}")),
+            Value::String(format!("{issued_by}")),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn set_tombstone(
+        &mut self,
+        issued_for: &PK,
+        issued_by: &PK,
+        revoked_at: Duration,
+    ) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO revocation_tombstones VALUES (?, ?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(format!("{issued_for}")),
+            Value::String(format!("{issued_by}")),
+            Value::Integer(revoked_at.as_secs() as i64),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
     fn get_root_weight_factor(&self, pk: &PK) -> Result<Option<WeightFactor>, Self::Error> {
         let mut cursor = self
             .connection
@@ -334,6 +691,10 @@ impl Storage for SQLiteStorage {
         self.update_relation(TrustRelation::Revocation(revoke))
     }
 
+    fn distrust(&mut self, distrust: Distrust) -> Result<(), Self::Error> {
+        self.update_relation(TrustRelation::Distrust(distrust))
+    }
+
     fn update_auth(&mut self, auth: Auth, _cur_time: Duration) -> Result<(), Self::Error> {
         self.update_relation(TrustRelation::Auth(auth))
     }
@@ -353,4 +714,395 @@ impl Storage for SQLiteStorage {
 
         Ok(())
     }
+
+    fn get_revocation_list(&self, issued_by: &PK) -> Result<Option<RevocationList>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT issued_by, sequence, list FROM revocation_lists WHERE issued_by = ?")?
+            .cursor();
+
+        cursor.bind(&[Value::String(format!("{issued_by}"))])?;
+
+        if let Some(row) = cursor.next()? {
+            let list = row[2].as_binary().ok_or(FieldConversionDB)?;
+            Ok(Some(rmp_serde::from_slice(list)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_revocation_list(&mut self, list: RevocationList) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO revocation_lists VALUES (?, ?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(format!("{}", list.issued_by.as_ref())),
+            Value::Integer(list.sequence as i64),
+            Value::Binary(rmp_serde::to_vec(&list)?),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn get_key_compromise(&self, pk: &PK) -> Result<Option<KeyCompromise>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare(
+                "SELECT public_key, compromised_at, signature FROM key_compromises \
+             WHERE public_key = ?",
+            )?
+            .cursor();
+
+        cursor.bind(&[Value::String(format!("{pk}"))])?;
+
+        if let Some(row) = cursor.next()? {
+            let compromised_at =
+                Duration::from_secs(row[1].as_integer().ok_or(FieldConversionDB)? as u64);
+            let signature =
+                Signature::decode(row[2].as_binary().ok_or(FieldConversionDB)?.to_vec())?;
+
+            Ok(Some(KeyCompromise::new(
+                pk.clone().into(),
+                compromised_at,
+                signature,
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_key_compromise(&mut self, compromise: KeyCompromise) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO key_compromises VALUES (?, ?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(format!("{}", compromise.pk.as_ref())),
+            Value::Integer(compromise.compromised_at.as_secs() as i64),
+            Value::Binary(compromise.signature.encode()),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn get_threshold_anchor_weight_factor(
+        &self,
+        anchor: &ThresholdAnchor,
+    ) -> Result<Option<WeightFactor>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT id, weight_factor FROM threshold_anchors WHERE id = ?")?
+            .cursor();
+
+        cursor.bind(&[Value::String(anchor.id())])?;
+
+        if let Some(row) = cursor.next()? {
+            let w = u32::try_from(row[1].as_integer().ok_or(WeightFactorConversionDB)?)
+                .map_err(|_e| WeightFactorConversionDB)?;
+
+            Ok(Some(w))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_threshold_anchor_weight_factor(
+        &mut self,
+        anchor: ThresholdAnchor,
+        weight_factor: WeightFactor,
+    ) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO threshold_anchors VALUES (?, ?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(anchor.id()),
+            Value::Binary(rmp_serde::to_vec(&anchor)?),
+            Value::Integer(i64::from(weight_factor)),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn get_threshold_trust(&self, issued_for: &PK) -> Result<Option<ThresholdTrust>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT issued_for, trust FROM threshold_trusts WHERE issued_for = ?")?
+            .cursor();
+
+        cursor.bind(&[Value::String(format!("{issued_for}"))])?;
+
+        if let Some(row) = cursor.next()? {
+            let trust = row[1].as_binary().ok_or(FieldConversionDB)?;
+            Ok(Some(rmp_serde::from_slice(trust)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_threshold_trust(&mut self, trust: ThresholdTrust) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO threshold_trusts VALUES (?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(format!("{}", trust.issued_for.as_ref())),
+            Value::Binary(rmp_serde::to_vec(&trust)?),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn threshold_trusts(&self) -> Result<Vec<ThresholdTrust>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT issued_for, trust FROM threshold_trusts")?
+            .cursor();
+
+        let mut trusts = vec![];
+
+        while let Some(row) = cursor.next()? {
+            let trust = row[1].as_binary().ok_or(FieldConversionDB)?;
+            trusts.push(rmp_serde::from_slice(trust)?);
+        }
+
+        Ok(trusts)
+    }
+
+    fn get_pending(&self, issued_by: &PK) -> Result<Vec<PendingTrust>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT pending FROM pending_trusts WHERE issued_by = ?")?
+            .cursor();
+
+        cursor.bind(&[Value::String(format!("{issued_by}"))])?;
+
+        let mut pending = vec![];
+        while let Some(row) = cursor.next()? {
+            let p = row[0].as_binary().ok_or(FieldConversionDB)?;
+            pending.push(rmp_serde::from_slice(p)?);
+        }
+
+        Ok(pending)
+    }
+
+    fn get_all_pending(&self) -> Result<Vec<PendingTrust>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT pending FROM pending_trusts")?
+            .cursor();
+
+        let mut pending = vec![];
+        while let Some(row) = cursor.next()? {
+            let p = row[0].as_binary().ok_or(FieldConversionDB)?;
+            pending.push(rmp_serde::from_slice(p)?);
+        }
+
+        Ok(pending)
+    }
+
+    fn insert_pending(&mut self, pending: PendingTrust) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO pending_trusts VALUES (?, ?, ?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(format!("{}", pending.auth.trust.issued_for.as_ref())),
+            Value::String(format!("{}", pending.auth.issued_by.as_ref())),
+            Value::Integer(pending.added_at.as_secs() as i64),
+            Value::Binary(rmp_serde::to_vec(&pending)?),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn remove_pending(&mut self, issued_for: &PK, issued_by: &PK) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("DELETE FROM pending_trusts WHERE issued_for = ? AND issued_by = ?")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::String(format!("{issued_for}")),
+            Value::String(format!("{issued_by}")),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn log_size(&self) -> Result<u64, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT COUNT(*) FROM transparency_log")?
+            .cursor();
+
+        let row = cursor.next()?.ok_or(FieldConversionDB)?;
+        let size = row[0].as_integer().ok_or(FieldConversionDB)?;
+        Ok(size as u64)
+    }
+
+    fn append_log_entry(&mut self, entry: LogEntry) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT INTO transparency_log VALUES (?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::Integer(entry.sequence as i64),
+            Value::Binary(rmp_serde::to_vec(&entry)?),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn get_log_entries(&self, from: u64, to: u64) -> Result<Vec<LogEntry>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare(
+                "SELECT entry FROM transparency_log WHERE sequence >= ? AND sequence < ? \
+                ORDER BY sequence",
+            )?
+            .cursor();
+
+        cursor.bind(&[Value::Integer(from as i64), Value::Integer(to as i64)])?;
+
+        let mut entries = vec![];
+        while let Some(row) = cursor.next()? {
+            let entry = row[0].as_binary().ok_or(FieldConversionDB)?;
+            entries.push(rmp_serde::from_slice(entry)?);
+        }
+
+        Ok(entries)
+    }
+
+    fn get_state_leaves(&self, bucket: u8) -> Result<Vec<(Vec<u8>, MerkleHash)>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT key, leaf FROM state_leaves WHERE bucket = ? ORDER BY key")?
+            .cursor();
+
+        cursor.bind(&[Value::Integer(i64::from(bucket))])?;
+
+        let mut leaves = vec![];
+        while let Some(row) = cursor.next()? {
+            let key = row[0].as_binary().ok_or(FieldConversionDB)?;
+            leaves.push((key.to_vec(), parse_hash(&row[1])?));
+        }
+
+        Ok(leaves)
+    }
+
+    fn insert_state_leaf(
+        &mut self,
+        bucket: u8,
+        key: Vec<u8>,
+        leaf: MerkleHash,
+        expires_at: Option<Duration>,
+    ) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO state_leaves VALUES (?, ?, ?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::Binary(key),
+            Value::Integer(i64::from(bucket)),
+            Value::Binary(leaf.to_vec()),
+            expires_at.map_or(Value::Null, |e| Value::Integer(e.as_secs() as i64)),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn remove_state_leaf(&mut self, key: &[u8]) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("DELETE FROM state_leaves WHERE key = ?")?
+            .cursor();
+
+        cursor.bind(&[Value::Binary(key.to_vec())])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn remove_expired_state_leaves(&mut self, cur_time: Duration) -> Result<Vec<u8>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT DISTINCT bucket FROM state_leaves WHERE expires_at <= ?")?
+            .cursor();
+
+        cursor.bind(&[Value::Integer(cur_time.as_secs() as i64)])?;
+
+        let mut buckets = vec![];
+        while let Some(row) = cursor.next()? {
+            let bucket = row[0].as_integer().ok_or(FieldConversionDB)?;
+            buckets.push(u8::try_from(bucket).map_err(|_| FieldConversionDB)?);
+        }
+
+        let mut cursor = self
+            .connection
+            .prepare("DELETE FROM state_leaves WHERE expires_at <= ?")?
+            .cursor();
+
+        cursor.bind(&[Value::Integer(cur_time.as_secs() as i64)])?;
+
+        cursor.next()?;
+        Ok(buckets)
+    }
+
+    fn get_state_buckets(&self) -> Result<Vec<MerkleHash>, Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("SELECT root FROM state_buckets ORDER BY bucket")?
+            .cursor();
+
+        let mut roots = vec![];
+        while let Some(row) = cursor.next()? {
+            roots.push(parse_hash(&row[0])?);
+        }
+
+        Ok(roots)
+    }
+
+    fn set_state_bucket(&mut self, bucket: u8, root: MerkleHash) -> Result<(), Self::Error> {
+        let mut cursor = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO state_buckets VALUES (?, ?)")?
+            .cursor();
+
+        cursor.bind(&[
+            Value::Integer(i64::from(bucket)),
+            Value::Binary(root.to_vec()),
+        ])?;
+
+        cursor.next()?;
+        Ok(())
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        Ok(self.connection.execute("BEGIN TRANSACTION;")?)
+    }
+
+    fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        Ok(self.connection.execute("COMMIT;")?)
+    }
+
+    fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        Ok(self.connection.execute("ROLLBACK;")?)
+    }
 }
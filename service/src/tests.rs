@@ -19,10 +19,13 @@ mod service_tests {
     marine_rs_sdk_test::include_test_env!("/marine_test_env.rs");
     use crate::error::ServiceError;
     use crate::TRUSTED_TIMESTAMP;
-    use fluence_keypair::KeyPair;
+    use fluence_keypair::{KeyPair, PublicKey};
     use libp2p_identity::PeerId;
     use marine_rs_sdk::{CallParameters, SecurityTetraplet};
-    use marine_test_env::trust_graph::{Certificate, Revocation, ServiceInterface, Trust};
+    use marine_test_env::trust_graph::{
+        Certificate, Distrust, KeyCompromise, Revocation, RevocationList, ServiceInterface,
+        ThresholdAnchor, ThresholdTrust, Trust,
+    };
     use std::collections::HashMap;
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -30,6 +33,8 @@ mod service_tests {
 
     static TEST_DB_PATH: &str = "data/trust-graph.sqlite";
 
+    static RSA_KEY: &[u8] = include_bytes!("../../keypair/src/test/rsa-2048.pk8");
+
     struct Auth {
         issuer: PeerId,
         trust: Trust,
@@ -42,6 +47,9 @@ mod service_tests {
                 && self.issued_for == other.issued_for
                 && self.signature == other.signature
                 && self.sig_type == other.sig_type
+                && self.signing_version == other.signing_version
+                && self.confidence == other.confidence
+                && self.issued_for_pk == other.issued_for_pk
         }
     }
 
@@ -128,6 +136,43 @@ mod service_tests {
         issue_result.trust
     }
 
+    fn add_trust_with_confidence(
+        trust_graph: &mut ServiceInterface,
+        issuer_kp: &KeyPair,
+        issued_for: &PeerId,
+        issued_at_sec: u64,
+        expires_at_sec: u64,
+        confidence: u8,
+    ) -> Trust {
+        let result = trust_graph.get_trust_with_confidence_bytes(
+            issued_for.to_base58(),
+            expires_at_sec,
+            issued_at_sec,
+            confidence,
+        );
+        assert!(result.success, "{}", result.error);
+
+        let trust_bytes = issuer_kp.sign(&result.result).unwrap().to_vec().to_vec();
+        let issue_result = trust_graph.issue_trust_with_confidence(
+            issued_for.to_base58(),
+            expires_at_sec,
+            issued_at_sec,
+            confidence,
+            trust_bytes,
+        );
+        assert!(issue_result.success, "{}", issue_result.error);
+
+        let add_trust_result = trust_graph.add_trust_cp(
+            issue_result.trust.clone(),
+            issuer_kp.get_peer_id().to_base58(),
+            issued_at_sec,
+            get_correct_timestamp_cp(2),
+        );
+        assert!(add_trust_result.success, "{}", add_trust_result.error);
+
+        issue_result.trust
+    }
+
     fn issue_root_trust(
         trust_graph: &mut ServiceInterface,
         issuer_kp: &KeyPair,
@@ -219,6 +264,112 @@ mod service_tests {
         issue_result.revocation
     }
 
+    fn distrust(
+        trust_graph: &mut ServiceInterface,
+        issuer_kp: &KeyPair,
+        distrusted_peer_id: &PeerId,
+        distrusted_at_sec: u64,
+    ) -> Distrust {
+        let result =
+            trust_graph.get_distrust_bytes(distrusted_peer_id.to_base58(), distrusted_at_sec);
+        assert!(result.success, "{}", result.error);
+
+        let distrust_bytes = issuer_kp.sign(&result.result).unwrap().to_vec().to_vec();
+        let issue_result = trust_graph.issue_distrust(
+            issuer_kp.get_peer_id().to_base58(),
+            distrusted_peer_id.to_base58(),
+            distrusted_at_sec,
+            distrust_bytes,
+        );
+        assert!(issue_result.success, "{}", issue_result.error);
+
+        let distrust_result = trust_graph.distrust_cp(
+            issue_result.distrust.clone(),
+            distrusted_at_sec,
+            get_correct_timestamp_cp(1),
+        );
+        assert!(distrust_result.success, "{}", distrust_result.error);
+
+        issue_result.distrust
+    }
+
+    fn issue_revocation_list(
+        trust_graph: &mut ServiceInterface,
+        issuer_kp: &KeyPair,
+        sequence: u64,
+        revoked: &[PeerId],
+        issued_at_sec: u64,
+        expires_at_sec: u64,
+    ) -> RevocationList {
+        let revocations: Vec<Revocation> = revoked
+            .iter()
+            .map(|peer_id| {
+                let result = trust_graph.get_revocation_bytes(peer_id.to_base58(), issued_at_sec);
+                assert!(result.success, "{}", result.error);
+
+                let revoke_bytes = issuer_kp.sign(&result.result).unwrap().to_vec().to_vec();
+                let issue_result = trust_graph.issue_revocation(
+                    issuer_kp.get_peer_id().to_base58(),
+                    peer_id.to_base58(),
+                    issued_at_sec,
+                    revoke_bytes,
+                );
+                assert!(issue_result.success, "{}", issue_result.error);
+                issue_result.revocation
+            })
+            .collect();
+
+        let result = trust_graph.get_revocation_list_bytes(
+            sequence,
+            issued_at_sec,
+            expires_at_sec,
+            revocations.clone(),
+        );
+        assert!(result.success, "{}", result.error);
+
+        let list_bytes = issuer_kp.sign(&result.result).unwrap().to_vec().to_vec();
+        let issue_result = trust_graph.issue_revocation_list(
+            issuer_kp.get_peer_id().to_base58(),
+            sequence,
+            issued_at_sec,
+            expires_at_sec,
+            revocations,
+            list_bytes,
+        );
+        assert!(issue_result.success, "{}", issue_result.error);
+
+        issue_result.revocation_list
+    }
+
+    fn declare_compromise(
+        trust_graph: &mut ServiceInterface,
+        compromised_kp: &KeyPair,
+        compromised_at_sec: u64,
+        cur_time: u64,
+    ) -> KeyCompromise {
+        let peer_id = compromised_kp.get_peer_id().to_base58();
+        let result = trust_graph.get_key_compromise_bytes(peer_id.clone(), compromised_at_sec);
+        assert!(result.success, "{}", result.error);
+
+        let compromise_bytes = compromised_kp
+            .sign(&result.result)
+            .unwrap()
+            .to_vec()
+            .to_vec();
+        let issue_result =
+            trust_graph.issue_key_compromise(peer_id, compromised_at_sec, compromise_bytes);
+        assert!(issue_result.success, "{}", issue_result.error);
+
+        let declare_result = trust_graph.declare_compromise_cp(
+            issue_result.compromise.clone(),
+            cur_time,
+            get_correct_timestamp_cp(1),
+        );
+        assert!(declare_result.success, "{}", declare_result.error);
+
+        issue_result.compromise
+    }
+
     fn generate_trust_chain_with(
         trust_graph: &mut ServiceInterface,
         len: usize,
@@ -288,6 +439,50 @@ mod service_tests {
         generate_trust_chain_with(trust_graph, len, keys, far_future, cur_time)
     }
 
+    fn add_threshold_trust(
+        trust_graph: &mut ServiceInterface,
+        anchor: ThresholdAnchor,
+        signers: &[&KeyPair],
+        issued_for: &PeerId,
+        issued_at_sec: u64,
+        expires_at_sec: u64,
+    ) -> ThresholdTrust {
+        let result = trust_graph.get_threshold_trust_bytes(
+            anchor.clone(),
+            issued_for.to_base58(),
+            expires_at_sec,
+            issued_at_sec,
+        );
+        assert!(result.success, "{}", result.error);
+
+        let signed_by = signers
+            .iter()
+            .map(|kp| kp.get_peer_id().to_base58())
+            .collect();
+        let signatures = signers
+            .iter()
+            .map(|kp| kp.sign(&result.result).unwrap().to_vec().to_vec())
+            .collect();
+        let issue_result = trust_graph.issue_threshold_trust(
+            anchor,
+            issued_for.to_base58(),
+            expires_at_sec,
+            issued_at_sec,
+            signed_by,
+            signatures,
+        );
+        assert!(issue_result.success, "{}", issue_result.error);
+
+        let add_result = trust_graph.add_threshold_trust_cp(
+            issue_result.trust.clone(),
+            issued_at_sec,
+            get_correct_timestamp_cp(1),
+        );
+        assert!(add_result.success, "{}", add_result.error);
+
+        issue_result.trust
+    }
+
     fn get_weight(trust_graph: &mut ServiceInterface, peer_id: PeerId, cur_time: u64) -> u32 {
         let result =
             trust_graph.get_weight_cp(peer_id.to_base58(), cur_time, get_correct_timestamp_cp(1));
@@ -918,4 +1113,1201 @@ mod service_tests {
             assert_eq!(*trust, trusts[i].trust);
         }
     }
+
+    #[test]
+    fn test_revocation_list() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let root_kp = KeyPair::generate_ed25519();
+        let cur_time = 100u64;
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 999, 10);
+
+        let trust_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &trust_kp.get_peer_id(),
+            cur_time,
+            cur_time + 99999,
+        );
+
+        let weight = get_weight(&mut trust_graph, trust_kp.get_peer_id(), cur_time);
+        assert_ne!(weight, 0u32);
+
+        let revoked_time = cur_time + 1;
+        let list = issue_revocation_list(
+            &mut trust_graph,
+            &root_kp,
+            1,
+            &[trust_kp.get_peer_id()],
+            revoked_time,
+            revoked_time + 100,
+        );
+
+        let import_result = trust_graph.import_revocation_list_cp(
+            list.clone(),
+            revoked_time,
+            get_correct_timestamp_cp(1),
+        );
+        assert!(import_result.success, "{}", import_result.error);
+
+        let weight = get_weight(&mut trust_graph, trust_kp.get_peer_id(), revoked_time);
+        assert_eq!(weight, 0u32);
+
+        let export_result = trust_graph.export_revocation_list(root_kp.get_peer_id().to_base58());
+        assert!(export_result.success, "{}", export_result.error);
+        assert_eq!(export_result.revocation_list.sequence, 1);
+        assert_eq!(export_result.revocation_list.signature, list.signature);
+        assert_eq!(export_result.revocation_list.revocations.len(), 1);
+
+        // the same sequence can't be imported twice
+        let import_result =
+            trust_graph.import_revocation_list_cp(list, revoked_time, get_correct_timestamp_cp(1));
+        assert!(!import_result.success);
+    }
+
+    #[test]
+    fn test_key_compromise() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let root_kp = KeyPair::generate_ed25519();
+        let cur_time = 100u64;
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 999, 10);
+
+        let issuer_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &issuer_kp.get_peer_id(),
+            cur_time,
+            cur_time + 99999,
+        );
+
+        let before_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &issuer_kp,
+            &before_kp.get_peer_id(),
+            cur_time + 1,
+            cur_time + 99999,
+        );
+
+        let after_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &issuer_kp,
+            &after_kp.get_peer_id(),
+            cur_time + 3,
+            cur_time + 99999,
+        );
+
+        let compromised_at = cur_time + 2;
+        let compromise =
+            declare_compromise(&mut trust_graph, &issuer_kp, compromised_at, cur_time + 4);
+
+        let weight = get_weight(&mut trust_graph, issuer_kp.get_peer_id(), cur_time + 4);
+        assert_ne!(weight, 0u32);
+        let weight = get_weight(&mut trust_graph, before_kp.get_peer_id(), cur_time + 4);
+        assert_ne!(weight, 0u32);
+        let weight = get_weight(&mut trust_graph, after_kp.get_peer_id(), cur_time + 4);
+        assert_eq!(weight, 0u32);
+
+        // a later declaration doesn't supersede the first one
+        declare_compromise(&mut trust_graph, &issuer_kp, cur_time + 10, cur_time + 10);
+
+        let result = trust_graph.get_key_compromise(issuer_kp.get_peer_id().to_base58());
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.compromise.compromised_at, compromised_at);
+        assert_eq!(result.compromise.signature, compromise.signature);
+    }
+
+    #[test]
+    fn test_threshold_root() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let holders: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate_ed25519()).collect();
+        let anchor = ThresholdAnchor {
+            keys: holders
+                .iter()
+                .map(|kp| kp.get_peer_id().to_base58())
+                .collect(),
+            threshold: 2,
+        };
+        let result = trust_graph.set_threshold_root(anchor.keys.clone(), anchor.threshold, 4);
+        assert!(result.success, "{}", result.error);
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        let root_peer_id = root_kp.get_peer_id();
+
+        // one holder is not enough
+        let result = trust_graph.get_threshold_trust_bytes(
+            anchor.clone(),
+            root_peer_id.to_base58(),
+            cur_time + 9999,
+            cur_time,
+        );
+        assert!(result.success, "{}", result.error);
+        let issue_result = trust_graph.issue_threshold_trust(
+            anchor.clone(),
+            root_peer_id.to_base58(),
+            cur_time + 9999,
+            cur_time,
+            vec![holders[0].get_peer_id().to_base58()],
+            vec![holders[0].sign(&result.result).unwrap().to_vec().to_vec()],
+        );
+        assert!(issue_result.success, "{}", issue_result.error);
+        let add_result = trust_graph.add_threshold_trust_cp(
+            issue_result.trust,
+            cur_time,
+            get_correct_timestamp_cp(1),
+        );
+        assert!(!add_result.success);
+
+        add_threshold_trust(
+            &mut trust_graph,
+            anchor,
+            &[&holders[0], &holders[2]],
+            &root_peer_id,
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &root_peer_id,
+            cur_time,
+            cur_time + 9999,
+        );
+
+        let issued_for_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &issued_for_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+
+        let root_weight = get_weight(&mut trust_graph, root_peer_id, cur_time);
+        assert_ne!(root_weight, 0u32);
+        let weight = get_weight(&mut trust_graph, issued_for_kp.get_peer_id(), cur_time);
+        assert_eq!(weight, root_weight / 2);
+
+        let certs = get_all_certs(&mut trust_graph, issued_for_kp.get_peer_id(), cur_time);
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].anchor_trust.len(), 1);
+    }
+
+    #[test]
+    fn test_distrust_policy() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+
+        // root -> a -> b -> x
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let x_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &a_kp,
+            &b_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &b_kp,
+            &x_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        let weight = get_weight(&mut trust_graph, x_kp.get_peer_id(), cur_time);
+        assert_ne!(weight, 0u32);
+
+        // a has never trusted x directly
+        let distrust = distrust(&mut trust_graph, &a_kp, &x_kp.get_peer_id(), cur_time + 1);
+        let result = trust_graph.export_distrusts(x_kp.get_peer_id().to_base58());
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.distrusts.len(), 1);
+        assert_eq!(result.distrusts[0].signature, distrust.signature);
+
+        // distrusts are ignored by default
+        assert_eq!(
+            get_weight(&mut trust_graph, x_kp.get_peer_id(), cur_time + 1),
+            weight
+        );
+
+        let result = trust_graph.set_distrust_policy("penalty".to_string(), 2, 0);
+        assert!(result.success, "{}", result.error);
+
+        // the policy is applied again by a restarted service
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        assert_eq!(
+            get_weight(&mut trust_graph, x_kp.get_peer_id(), cur_time + 1),
+            weight / 4
+        );
+
+        let result = trust_graph.set_distrust_policy("veto".to_string(), 0, 0);
+        assert!(result.success, "{}", result.error);
+        assert_eq!(
+            get_weight(&mut trust_graph, x_kp.get_peer_id(), cur_time + 1),
+            0u32
+        );
+        // b is not distrusted
+        assert_ne!(
+            get_weight(&mut trust_graph, b_kp.get_peer_id(), cur_time + 1),
+            0u32
+        );
+
+        let result = trust_graph.set_distrust_policy("unknown".to_string(), 0, 0);
+        assert!(!result.success);
+    }
+
+    #[test]
+    fn test_trust_confidence() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+        let root_weight = get_weight(&mut trust_graph, root_kp.get_peer_id(), cur_time);
+
+        let operator_kp = KeyPair::generate_ed25519();
+        let trust = add_trust_with_confidence(
+            &mut trust_graph,
+            &root_kp,
+            &operator_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+            128,
+        );
+        assert_eq!(trust.signing_version, 3);
+        assert_eq!(trust.confidence, 128);
+
+        let issued_for_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &operator_kp,
+            &issued_for_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+
+        let weight = get_weight(&mut trust_graph, operator_kp.get_peer_id(), cur_time);
+        assert_eq!(weight, root_weight / 2 * 128 / 255);
+        let weight = get_weight(&mut trust_graph, issued_for_kp.get_peer_id(), cur_time);
+        assert_eq!(weight, root_weight / 4 * 128 / 255);
+
+        let certs = get_all_certs(&mut trust_graph, issued_for_kp.get_peer_id(), cur_time);
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].chain[1].confidence, 128);
+    }
+
+    #[test]
+    fn test_trusted_keys() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        let root2_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+        add_root_with_trust(&mut trust_graph, &root2_kp, cur_time, cur_time + 9999, 10);
+
+        // root -> a -> b, root2 -> c
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let c_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &a_kp,
+            &b_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &root2_kp,
+            &c_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+
+        let root_weight = get_weight(&mut trust_graph, root_kp.get_peer_id(), cur_time);
+        let result = trust_graph.get_trusted_keys_cp(0, cur_time, get_correct_timestamp_cp(1));
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.trusted_keys.len(), 5);
+        assert_eq!(result.trusted_keys[0].weight, root_weight);
+        assert!(result.trusted_keys[0].certificate.is_empty());
+        let b = result.trusted_keys.last().unwrap();
+        assert_eq!(b.peer_id, b_kp.get_peer_id().to_base58());
+        assert_eq!(b.weight, root_weight / 4);
+        assert_eq!(b.certificate[0].chain.len(), 3);
+
+        let result =
+            trust_graph.get_trusted_keys_cp(root_weight / 2, cur_time, get_correct_timestamp_cp(1));
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.trusted_keys.len(), 4);
+
+        let result = trust_graph.get_trusted_keys_from_cp(
+            root2_kp.get_peer_id().to_base58(),
+            0,
+            cur_time,
+            get_correct_timestamp_cp(2),
+        );
+        assert!(result.success, "{}", result.error);
+        let peer_ids: Vec<String> = result.trusted_keys.into_iter().map(|k| k.peer_id).collect();
+        assert_eq!(
+            peer_ids,
+            vec![
+                root2_kp.get_peer_id().to_base58(),
+                c_kp.get_peer_id().to_base58()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_export_issued() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        let root_trust =
+            add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let a_trust = add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &b_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        let revocation = revoke(
+            &mut trust_graph,
+            &root_kp,
+            &b_kp.get_peer_id(),
+            cur_time + 1,
+        );
+
+        let result = trust_graph.export_issued_trusts(root_kp.get_peer_id().to_base58());
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.trusts.len(), 2);
+        assert!(result.trusts.contains(&root_trust));
+        assert!(result.trusts.contains(&a_trust));
+
+        let result = trust_graph.export_issued_revocations(root_kp.get_peer_id().to_base58());
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.revocations.len(), 1);
+        assert_eq!(result.revocations[0].signature, revocation.signature);
+
+        let result = trust_graph.export_issued_trusts(a_kp.get_peer_id().to_base58());
+        assert!(result.success, "{}", result.error);
+        assert!(result.trusts.is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+
+        // root -> a -> b, root -> c expires soon
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let c_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &a_kp,
+            &b_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &c_kp.get_peer_id(),
+            cur_time,
+            cur_time + 1,
+        );
+        revoke(&mut trust_graph, &a_kp, &root_kp.get_peer_id(), cur_time);
+
+        let result = trust_graph.get_stats_cp(cur_time + 10, get_correct_timestamp_cp(0));
+        assert!(result.success, "{}", result.error);
+        let stats = result.stats;
+        assert_eq!(stats.keys, 4);
+        assert_eq!(stats.auths, 4);
+        assert_eq!(stats.revocations, 1);
+        assert_eq!(stats.distrusts, 0);
+        assert_eq!(stats.roots, 1);
+        assert_eq!(stats.anchored_roots, 0);
+        assert_eq!(stats.expired, 1);
+        assert_eq!(stats.depth_histogram, vec![1, 1, 1]);
+        assert_eq!(stats.key_formats.len(), 1);
+        assert_eq!(stats.key_formats[0].key_format, "ed25519");
+        assert_eq!(stats.key_formats[0].count, 4);
+    }
+
+    #[test]
+    fn test_gc() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let result = trust_graph.set_gc_schedule("manual".to_string(), 0, false, 0);
+        assert!(result.success, "{}", result.error);
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+
+        // root -> a -> b, a -> e expires soon
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let e_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &a_kp,
+            &b_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &a_kp,
+            &e_kp.get_peer_id(),
+            cur_time,
+            cur_time + 5,
+        );
+        // a and everything it issued become unreachable
+        revoke(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time + 1,
+        );
+
+        // with manual schedule expired trust isn't collected on read,
+        // the schedule is kept by a restarted service
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        assert_eq!(
+            get_weight(&mut trust_graph, b_kp.get_peer_id(), cur_time + 10),
+            0u32
+        );
+        let result = trust_graph.get_stats_cp(cur_time + 10, get_correct_timestamp_cp(0));
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.stats.expired, 1);
+
+        let result = trust_graph.gc_cp(true, true, 0, cur_time + 10, get_correct_timestamp_cp(3));
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.expired, 1);
+        assert_eq!(result.unreachable, 1);
+        assert_eq!(result.revocations, 0);
+
+        let result = trust_graph.gc_cp(true, true, 5, cur_time + 10, get_correct_timestamp_cp(3));
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.expired, 0);
+        assert_eq!(result.unreachable, 0);
+        assert_eq!(result.revocations, 1);
+
+        let result = trust_graph.get_stats_cp(cur_time + 10, get_correct_timestamp_cp(0));
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.stats.auths, 1);
+        assert_eq!(result.stats.revocations, 0);
+
+        // trust issued before the removed revocation is still rejected
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        assert_eq!(
+            get_weight(&mut trust_graph, a_kp.get_peer_id(), cur_time + 10),
+            0u32
+        );
+
+        let result = trust_graph.set_gc_schedule("sometimes".to_string(), 0, false, 0);
+        assert!(!result.success);
+    }
+
+    #[test]
+    fn test_gc_every_restart() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let result = trust_graph.set_gc_schedule("every".to_string(), 100, false, 0);
+        assert!(result.success, "{}", result.error);
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+        let a_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 5,
+        );
+        // the first read collects garbage
+        get_all_certs(&mut trust_graph, a_kp.get_peer_id(), cur_time);
+
+        // a restarted service waits for the interval since the last collection
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        get_all_certs(&mut trust_graph, a_kp.get_peer_id(), cur_time + 10);
+        let result = trust_graph.get_stats_cp(cur_time + 10, get_correct_timestamp_cp(0));
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.stats.expired, 1);
+
+        get_all_certs(&mut trust_graph, a_kp.get_peer_id(), cur_time + 100);
+        let result = trust_graph.get_stats_cp(cur_time + 100, get_correct_timestamp_cp(0));
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.stats.expired, 0);
+    }
+
+    #[test]
+    fn test_quotas() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let result = trust_graph.set_quotas(2, 0, "reject".to_string());
+        assert!(result.success, "{}", result.error);
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let c_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &b_kp.get_peer_id(),
+            cur_time + 1,
+            cur_time + 9999,
+        );
+
+        let trust = issue_trust(
+            &mut trust_graph,
+            &root_kp,
+            &c_kp.get_peer_id(),
+            cur_time + 2,
+            cur_time + 9999,
+        );
+        let result = trust_graph.add_trust_cp(
+            trust.clone(),
+            root_kp.get_peer_id().to_base58(),
+            cur_time + 2,
+            get_correct_timestamp_cp(2),
+        );
+        assert!(!result.success);
+
+        // renewal of an existing trust doesn't count
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time + 3,
+            cur_time + 9999,
+        );
+
+        let result = trust_graph.set_quotas(2, 0, "evict_oldest".to_string());
+        assert!(result.success, "{}", result.error);
+        add_trust_checked(&mut trust_graph, trust, root_kp.get_peer_id(), cur_time + 2);
+
+        // b was issued earlier than the renewed trust for a
+        assert_eq!(
+            get_weight(&mut trust_graph, b_kp.get_peer_id(), cur_time + 2),
+            0u32
+        );
+        assert_ne!(
+            get_weight(&mut trust_graph, a_kp.get_peer_id(), cur_time + 2),
+            0u32
+        );
+        assert_ne!(
+            get_weight(&mut trust_graph, c_kp.get_peer_id(), cur_time + 2),
+            0u32
+        );
+
+        let result = trust_graph.set_quotas(2, 0, "evict_random".to_string());
+        assert!(!result.success);
+
+        // quotas are applied again by a restarted service
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        let d_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &d_kp.get_peer_id(),
+            cur_time + 4,
+            cur_time + 9999,
+        );
+        assert_eq!(
+            get_weight(&mut trust_graph, c_kp.get_peer_id(), cur_time + 4),
+            0u32
+        );
+    }
+
+    #[test]
+    fn test_pending_pool() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let result = trust_graph.set_pending_pool(10, 100);
+        assert!(result.success, "{}", result.error);
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+
+        // a -> b -> x and c -> d are issued before their issuers have weight
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let x_kp = KeyPair::generate_ed25519();
+        let c_kp = KeyPair::generate_ed25519();
+        let d_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &a_kp,
+            &b_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &b_kp,
+            &x_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &c_kp,
+            &d_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        assert_eq!(
+            get_weight(&mut trust_graph, x_kp.get_peer_id(), cur_time),
+            0u32
+        );
+
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time + 1,
+            cur_time + 9999,
+        );
+        let a_weight = get_weight(&mut trust_graph, a_kp.get_peer_id(), cur_time + 1);
+        assert_eq!(
+            get_weight(&mut trust_graph, b_kp.get_peer_id(), cur_time + 1),
+            a_weight / 2
+        );
+        assert_eq!(
+            get_weight(&mut trust_graph, x_kp.get_peer_id(), cur_time + 1),
+            a_weight / 4
+        );
+
+        // pending trust from c is stale by now
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &c_kp.get_peer_id(),
+            cur_time + 200,
+            cur_time + 9999,
+        );
+        assert_eq!(
+            get_weight(&mut trust_graph, d_kp.get_peer_id(), cur_time + 200),
+            0u32
+        );
+
+        // the pool is enabled again in a restarted service
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        let e_kp = KeyPair::generate_ed25519();
+        let f_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &e_kp,
+            &f_kp.get_peer_id(),
+            cur_time + 200,
+            cur_time + 9999,
+        );
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &e_kp.get_peer_id(),
+            cur_time + 201,
+            cur_time + 9999,
+        );
+        assert_ne!(
+            get_weight(&mut trust_graph, f_kp.get_peer_id(), cur_time + 201),
+            0u32
+        );
+    }
+
+    #[test]
+    fn test_state_root() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let result = trust_graph.get_state_root();
+        assert!(result.success, "{}", result.error);
+        let empty_root = result.state_root;
+        assert_eq!(empty_root.len(), 32);
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+        let with_root = trust_graph.get_state_root().state_root;
+        assert_ne!(with_root, empty_root);
+
+        let a_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        let with_trust = trust_graph.get_state_root().state_root;
+        assert_ne!(with_trust, with_root);
+
+        revoke(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time + 1,
+        );
+        let with_revocation = trust_graph.get_state_root().state_root;
+        assert_ne!(with_revocation, with_trust);
+        assert_ne!(with_revocation, with_root);
+
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        let result = trust_graph.get_state_root();
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.state_root, with_revocation);
+
+        // the stored tree is updated by a restarted service
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        let b_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &b_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        let with_other_trust = trust_graph.get_state_root().state_root;
+        assert_ne!(with_other_trust, with_revocation);
+    }
+
+    #[test]
+    fn test_weight_attestation() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        let host_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        let weight = get_weight(&mut trust_graph, a_kp.get_peer_id(), cur_time);
+
+        let cp = get_correct_timestamp_cp_with_host_id(1, host_kp.get_peer_id().to_base58());
+        let bytes = trust_graph.get_weight_attestation_bytes_cp(
+            a_kp.get_peer_id().to_base58(),
+            cur_time,
+            cp.clone(),
+        );
+        assert!(bytes.success, "{}", bytes.error);
+        assert_eq!(bytes.weight, weight);
+
+        let signature = host_kp.sign(&bytes.result).unwrap().to_vec().to_vec();
+        let result = trust_graph.issue_weight_attestation_cp(
+            a_kp.get_peer_id().to_base58(),
+            bytes.weight,
+            cur_time,
+            signature.clone(),
+            cp.clone(),
+        );
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.attestation.host, host_kp.get_peer_id().to_base58());
+        assert_eq!(result.attestation.weight, weight);
+
+        // the host signed another weight
+        let result = trust_graph.issue_weight_attestation_cp(
+            a_kp.get_peer_id().to_base58(),
+            bytes.weight * 2,
+            cur_time,
+            signature,
+            cp,
+        );
+        assert!(!result.success);
+
+        // root -> a -> b, b is vetoed by a distrust from root
+        let b_kp = KeyPair::generate_ed25519();
+        add_trust(
+            &mut trust_graph,
+            &a_kp,
+            &b_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+        distrust(
+            &mut trust_graph,
+            &root_kp,
+            &b_kp.get_peer_id(),
+            cur_time + 1,
+        );
+        let result = trust_graph.set_distrust_policy("veto".to_string(), 0, 0);
+        assert!(result.success, "{}", result.error);
+
+        // a restarted host attests weights under the same policy
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        let bytes = trust_graph.get_weight_attestation_bytes_cp(
+            b_kp.get_peer_id().to_base58(),
+            cur_time + 1,
+            get_correct_timestamp_cp_with_host_id(1, host_kp.get_peer_id().to_base58()),
+        );
+        assert!(bytes.success, "{}", bytes.error);
+        assert_eq!(bytes.weight, 0u32);
+    }
+    #[test]
+    fn test_transparency_log() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = 100u64;
+        let root_kp = KeyPair::generate_ed25519();
+        let host_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, cur_time + 9999, 10);
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time,
+            cur_time + 9999,
+        );
+
+        // timestamp doesn't come from the host
+        let wrong_cp = CallParameters {
+            host_id: host_kp.get_peer_id().to_base58(),
+            ..get_correct_timestamp_cp(0)
+        };
+        let bytes = trust_graph.get_tree_head_bytes_cp(cur_time, wrong_cp);
+        assert!(!bytes.success);
+
+        let cp = get_correct_timestamp_cp_with_host_id(0, host_kp.get_peer_id().to_base58());
+        let bytes = trust_graph.get_tree_head_bytes_cp(cur_time, cp.clone());
+        assert!(bytes.success, "{}", bytes.error);
+        assert_eq!(bytes.size, 2);
+
+        let signature = host_kp.sign(&bytes.result).unwrap().to_vec().to_vec();
+        let result = trust_graph.issue_tree_head_cp(
+            bytes.size,
+            bytes.root.clone(),
+            cur_time,
+            signature.clone(),
+            cp.clone(),
+        );
+        assert!(result.success, "{}", result.error);
+        assert_eq!(
+            result.tree_head.log_peer_id,
+            host_kp.get_peer_id().to_base58()
+        );
+        assert_eq!(result.tree_head.root, bytes.root);
+
+        // the host signed another size
+        let result =
+            trust_graph.issue_tree_head_cp(bytes.size + 1, bytes.root, cur_time, signature, cp);
+        assert!(!result.success);
+
+        revoke(
+            &mut trust_graph,
+            &root_kp,
+            &a_kp.get_peer_id(),
+            cur_time + 1,
+        );
+        let proof = trust_graph.get_log_consistency_proof(2, 3);
+        assert!(proof.success, "{}", proof.error);
+        assert!(!proof.path.is_empty());
+        assert!(proof.path.iter().all(|h| h.len() == 32));
+
+        let proof = trust_graph.get_log_inclusion_proof(2, 3);
+        assert!(proof.success, "{}", proof.error);
+        assert_eq!(proof.path.len(), 1);
+
+        let proof = trust_graph.get_log_inclusion_proof(3, 3);
+        assert!(!proof.success);
+    }
+
+    #[test]
+    fn test_eip191_trust() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = current_time();
+        let root_kp = KeyPair::generate_ed25519();
+        let wallet_kp = KeyPair::generate_secp256k1();
+        let issued_for_kp = KeyPair::generate_ed25519();
+        let expires_at_sec = cur_time + 9999;
+
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, expires_at_sec, 10);
+        add_trust(
+            &mut trust_graph,
+            &root_kp,
+            &wallet_kp.get_peer_id(),
+            cur_time,
+            expires_at_sec,
+        );
+
+        // the wallet signs trust bytes with personal_sign
+        let result = trust_graph.get_trust_bytes(
+            issued_for_kp.get_peer_id().to_base58(),
+            expires_at_sec,
+            cur_time,
+        );
+        assert!(result.success, "{}", result.error);
+        let signature = wallet_kp.sign_eip191(&result.result).unwrap();
+
+        let trust = Trust {
+            issued_for: issued_for_kp.get_peer_id().to_base58(),
+            expires_at: expires_at_sec,
+            signature: bs58::encode(signature.to_vec()).into_string(),
+            sig_type: "eip191".to_string(),
+            issued_at: cur_time,
+            signing_version: 2,
+            confidence: 255,
+            issued_for_pk: String::new(),
+        };
+        add_trust_checked(&mut trust_graph, trust, wallet_kp.get_peer_id(), cur_time);
+
+        let certs = get_all_certs(&mut trust_graph, issued_for_kp.get_peer_id(), cur_time);
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].chain[2].sig_type, "eip191");
+    }
+
+    /// Trust signed by a key of any format,
+    /// a key with a hashed peer id is passed base58 encoded
+    fn sign_trust(
+        trust_graph: &mut ServiceInterface,
+        issuer_kp: &KeyPair,
+        issued_for_kp: &KeyPair,
+        cur_time: u64,
+        expires_at_sec: u64,
+    ) -> Trust {
+        let peer_id = issued_for_kp.get_peer_id();
+        let issued_for_pk = match PublicKey::try_from(peer_id) {
+            Ok(_) => String::new(),
+            Err(_) => bs58::encode(issued_for_kp.public().encode()).into_string(),
+        };
+        let issued_for = if issued_for_pk.is_empty() {
+            peer_id.to_base58()
+        } else {
+            issued_for_pk.clone()
+        };
+        let result = trust_graph.get_trust_bytes(issued_for, expires_at_sec, cur_time);
+        assert!(result.success, "{}", result.error);
+
+        let signature = issuer_kp.sign(&result.result).unwrap();
+        Trust {
+            issued_for: peer_id.to_base58(),
+            expires_at: expires_at_sec,
+            signature: bs58::encode(signature.to_vec()).into_string(),
+            sig_type: signature.get_type_name(),
+            issued_at: cur_time,
+            signing_version: 2,
+            confidence: 255,
+            issued_for_pk,
+        }
+    }
+
+    #[test]
+    fn test_rsa_trust() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = current_time();
+        let root_kp = KeyPair::generate_ed25519();
+        let rsa_kp = KeyPair::from_pkcs8_der(RSA_KEY).unwrap();
+        let issued_for_kp = KeyPair::generate_ed25519();
+        let expires_at_sec = cur_time + 9999;
+        let rsa_pk = bs58::encode(rsa_kp.public().encode()).into_string();
+
+        add_root_with_trust(&mut trust_graph, &root_kp, cur_time, expires_at_sec, 10);
+        let trust = sign_trust(
+            &mut trust_graph,
+            &root_kp,
+            &rsa_kp,
+            cur_time,
+            expires_at_sec,
+        );
+        assert_eq!(trust.issued_for_pk, rsa_pk);
+        add_trust_checked(&mut trust_graph, trust, root_kp.get_peer_id(), cur_time);
+
+        // the RSA peer id doesn't contain the key
+        let result = trust_graph.get_weight_cp(
+            rsa_kp.get_peer_id().to_base58(),
+            cur_time,
+            get_correct_timestamp_cp(1),
+        );
+        assert!(!result.success);
+        let result =
+            trust_graph.get_weight_cp(rsa_pk.clone(), cur_time, get_correct_timestamp_cp(1));
+        assert!(result.success, "{}", result.error);
+        assert_ne!(result.weight, 0);
+
+        let trust = sign_trust(
+            &mut trust_graph,
+            &rsa_kp,
+            &issued_for_kp,
+            cur_time,
+            expires_at_sec,
+        );
+        assert_eq!(trust.sig_type, "rsa");
+        let result = trust_graph.verify_trust_cp(
+            trust.clone(),
+            rsa_pk.clone(),
+            cur_time,
+            get_correct_timestamp_cp(2),
+        );
+        assert!(result.success, "{}", result.error);
+        let result =
+            trust_graph.add_trust_cp(trust, rsa_pk.clone(), cur_time, get_correct_timestamp_cp(2));
+        assert!(result.success, "{}", result.error);
+
+        let certs = get_all_certs(&mut trust_graph, issued_for_kp.get_peer_id(), cur_time);
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].chain[1].issued_for_pk, rsa_pk);
+        assert_eq!(certs[0].chain[2].sig_type, "rsa");
+    }
+
+    #[test]
+    fn test_p256_trust_round_trip() {
+        let mut trust_graph = marine_test_env::trust_graph::ServiceInterface::new();
+        clear_env();
+
+        let cur_time = current_time();
+        let root_kp = KeyPair::generate_ed25519();
+        let p256_kp = KeyPair::generate_p256();
+        let issued_for_kp = KeyPair::generate_ed25519();
+        let expires_at_sec = cur_time + 9999;
+        let p256_pk = bs58::encode(p256_kp.public().encode()).into_string();
+
+        let root_trust =
+            add_root_with_trust(&mut trust_graph, &root_kp, cur_time, expires_at_sec, 10);
+        assert!(root_trust.issued_for_pk.is_empty());
+        let p256_trust = sign_trust(
+            &mut trust_graph,
+            &root_kp,
+            &p256_kp,
+            cur_time,
+            expires_at_sec,
+        );
+        assert_eq!(p256_trust.issued_for_pk, p256_pk);
+        add_trust_checked(
+            &mut trust_graph,
+            p256_trust.clone(),
+            root_kp.get_peer_id(),
+            cur_time,
+        );
+
+        let trust = sign_trust(
+            &mut trust_graph,
+            &p256_kp,
+            &issued_for_kp,
+            cur_time,
+            expires_at_sec,
+        );
+        assert_eq!(trust.sig_type, "p256");
+        let result = trust_graph.add_trust_cp(
+            trust.clone(),
+            p256_pk,
+            cur_time,
+            get_correct_timestamp_cp(2),
+        );
+        assert!(result.success, "{}", result.error);
+
+        let certs = get_all_certs(&mut trust_graph, issued_for_kp.get_peer_id(), cur_time);
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].chain[1], p256_trust);
+        assert_eq!(certs[0].chain[2], trust);
+
+        // certificates with hashed peer ids are accepted back
+        let result = trust_graph.insert_cert(certs[0].clone(), cur_time);
+        assert!(result.success, "{}", result.error);
+
+        let mut wrong_cert = certs[0].clone();
+        wrong_cert.chain[1].issued_for_pk =
+            bs58::encode(KeyPair::generate_p256().public().encode()).into_string();
+        let result = trust_graph.insert_cert(wrong_cert, cur_time);
+        assert!(!result.success);
+    }
 }
@@ -0,0 +1,138 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::attestation::AttestationError::IncorrectSignature;
+use crate::signing::{push_prefixed, v2_signature_bytes, PayloadType};
+use fluence_keypair::key_pair::KeyPair;
+use fluence_keypair::public_key::PublicKey;
+use fluence_keypair::signature::Signature;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum AttestationError {
+    #[error("Signature is incorrect: {0}")]
+    IncorrectSignature(
+        #[from]
+        #[source]
+        fluence_keypair::error::VerificationError,
+    ),
+}
+
+/// Statement of `host` that `pk` had `weight` in its trust graph at `timestamp`.
+/// Light clients that trust the host check only its signature instead of certificate chains.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightAttestation {
+    /// the host that computed the weight, also the signer of this statement
+    pub host: PublicKey,
+    pub pk: PublicKey,
+    pub weight: u32,
+    /// when the weight was computed
+    pub timestamp: Duration,
+    /// proof of this statement
+    pub signature: Signature,
+}
+
+impl WeightAttestation {
+    pub fn new(
+        host: PublicKey,
+        pk: PublicKey,
+        weight: u32,
+        timestamp: Duration,
+        signature: Signature,
+    ) -> Self {
+        Self {
+            host,
+            pk,
+            weight,
+            timestamp,
+            signature,
+        }
+    }
+
+    /// Creates new attestation signed by the host.
+    pub fn create(host: &KeyPair, pk: PublicKey, weight: u32, timestamp: Duration) -> Self {
+        let msg = WeightAttestation::signature_bytes(&host.public(), &pk, weight, timestamp);
+        let signature = host.sign(&msg).unwrap();
+
+        WeightAttestation::new(host.public(), pk, weight, timestamp, signature)
+    }
+
+    pub fn signature_bytes(
+        host: &PublicKey,
+        pk: &PublicKey,
+        weight: u32,
+        timestamp: Duration,
+    ) -> Vec<u8> {
+        let mut fields = Vec::new();
+        for key in [host, pk] {
+            push_prefixed(&mut fields, &key.encode());
+        }
+        fields.extend_from_slice(&weight.to_le_bytes());
+        fields.extend_from_slice(&timestamp.as_secs().to_le_bytes());
+
+        v2_signature_bytes(PayloadType::WeightAttestation, &fields)
+    }
+
+    /// Verifies that the attestation is signed by its host.
+    /// Whether the host is trusted is up to the caller.
+    pub fn verify(attestation: &WeightAttestation) -> Result<(), AttestationError> {
+        let msg = WeightAttestation::signature_bytes(
+            &attestation.host,
+            &attestation.pk,
+            attestation.weight,
+            attestation.timestamp,
+        );
+
+        attestation
+            .host
+            .verify(msg.as_slice(), &attestation.signature)
+            .map_err(IncorrectSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gen_attestation_and_validate_ed25519() {
+        let host = KeyPair::generate_ed25519();
+        let pk = KeyPair::generate_ed25519().public();
+
+        let attestation = WeightAttestation::create(&host, pk, 1024, Duration::new(100, 0));
+
+        assert!(WeightAttestation::verify(&attestation).is_ok());
+    }
+
+    #[test]
+    fn test_tampered_attestation_ed25519() {
+        let host = KeyPair::generate_ed25519();
+        let other = KeyPair::generate_ed25519();
+        let pk = KeyPair::generate_ed25519().public();
+
+        let attestation = WeightAttestation::create(&host, pk, 1024, Duration::new(100, 0));
+
+        let mut raised = attestation.clone();
+        raised.weight = 2048;
+        assert!(WeightAttestation::verify(&raised).is_err());
+
+        let mut other_host = attestation;
+        other_host.host = other.public();
+        assert!(WeightAttestation::verify(&other_host).is_err());
+    }
+}
@@ -15,10 +15,12 @@
  */
 
 use crate::certificate::CertificateError::{
-    CertificateLengthError, DecodeError, DecodeTrustError, ExpirationError,
-    IncorrectCertificateFormat, KeyInCertificateError, MalformedRoot, NoTrustedRoot,
-    VerificationError,
+    CertificateLengthError, DecodeAnchorTrustError, DecodeError, DecodeTrustError, ExpirationError,
+    IncorrectCertificateFormat, KeyInCertificateError, MalformedAnchorTrust, MalformedRoot,
+    NoTrustedAnchor, NoTrustedRoot, UnsupportedVersion, VerificationError,
 };
+use crate::signing::{prefixed_len, push_prefixed, SigningVersion};
+use crate::threshold::{ThresholdAnchor, ThresholdError, ThresholdTrust};
 use crate::trust::{Trust, TrustError};
 use fluence_keypair::key_pair::KeyPair;
 use fluence_keypair::public_key::PublicKey;
@@ -29,15 +31,24 @@ use thiserror::Error as ThisError;
 /// Serialization format of a certificate.
 /// TODO
 const FORMAT: &[u8; 2] = &[0, 0];
-/// Serialization format version of a certificate.
-/// TODO
-const VERSION: &[u8; 4] = &[0, 0, 0, 0];
+/// Serialization format version of a certificate where every trust is signed over v1 payload.
+const LEGACY_VERSION: &[u8; 4] = &[0, 0, 0, 0];
+/// Serialization format version of a certificate where every trust carries its signing version.
+const VERSIONED_TRUSTS_VERSION: &[u8; 4] = &[0, 0, 0, 1];
+/// Serialization format version of a certificate where trusts may also carry their confidence.
+const CONFIDENCE_VERSION: &[u8; 4] = &[0, 0, 0, 2];
+/// Serialization format version of a certificate where the trusts are followed by the
+/// threshold trust of the root.
+const ANCHORED_VERSION: &[u8; 4] = &[0, 0, 0, 3];
 const TRUST_NUMBER_LEN: usize = 1;
 
 /// Chain of trusts started from self-signed root trust.
+/// The root may be certified by a threshold anchor instead of being trusted directly.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Certificate {
     pub chain: Vec<Trust>,
+    /// threshold trust issued for the root of the chain
+    pub anchor_trust: Option<ThresholdTrust>,
 }
 
 #[derive(ThisError, Debug)]
@@ -67,11 +78,29 @@ pub enum CertificateError {
     VerificationError(usize, #[source] TrustError),
     #[error("there cannot be paths without any nodes after adding verified certificates")]
     Unexpected,
+    #[error("Unsupported certificate version: {0}")]
+    UnsupportedVersion(String),
+    #[error("Certificate root is not certified by a trusted anchor.")]
+    NoTrustedAnchor,
+    #[error("Anchor trust did not pass verification: {0}")]
+    MalformedAnchorTrust(#[source] ThresholdError),
+    #[error("Error while decoding the anchor trust in a certificate: {0}")]
+    DecodeAnchorTrustError(#[source] ThresholdError),
 }
 
 impl Certificate {
     pub fn new_unverified(chain: Vec<Trust>) -> Self {
-        Self { chain }
+        Self {
+            chain,
+            anchor_trust: None,
+        }
+    }
+
+    pub fn new_anchored_unverified(chain: Vec<Trust>, anchor_trust: ThresholdTrust) -> Self {
+        Self {
+            chain,
+            anchor_trust: Some(anchor_trust),
+        }
     }
 
     pub fn new_from_root_trust(
@@ -83,9 +112,7 @@ impl Certificate {
         Trust::verify(&issued_trust, &root_trust.issued_for, cur_time)
             .map_err(|e| VerificationError(1, e))?;
 
-        Ok(Self {
-            chain: vec![root_trust, issued_trust],
-        })
+        Ok(Self::new_unverified(vec![root_trust, issued_trust]))
     }
 
     pub fn issue_with_trust(
@@ -127,7 +154,10 @@ impl Certificate {
 
         new_chain.push(trust);
 
-        Ok(Self { chain: new_chain })
+        Ok(Self {
+            chain: new_chain,
+            anchor_trust: extend_cert.anchor_trust.clone(),
+        })
     }
 
     /// Creates new certificate with root trust (self-signed public key) from a key pair.
@@ -145,7 +175,7 @@ impl Certificate {
         let trust = Trust::create(root_kp, for_pk, expires_at, issued_at);
 
         let chain = vec![root_trust, trust];
-        Self { chain }
+        Self::new_unverified(chain)
     }
 
     /// Adds a new trust into chain of trust in certificate.
@@ -197,7 +227,10 @@ impl Certificate {
 
         new_chain.push(trust);
 
-        Ok(Self { chain: new_chain })
+        Ok(Self {
+            chain: new_chain,
+            anchor_trust: extend_cert.anchor_trust.clone(),
+        })
     }
 
     /// Verifies that a certificate is valid and you trust to this certificate.
@@ -205,6 +238,17 @@ impl Certificate {
         cert: &Certificate,
         trusted_roots: &[PublicKey],
         cur_time: Duration,
+    ) -> Result<(), CertificateError> {
+        Self::verify_with_anchors(cert, trusted_roots, &[], cur_time)
+    }
+
+    /// Verifies that a certificate is valid and its root is either trusted
+    /// or certified by one of the trusted threshold anchors.
+    pub fn verify_with_anchors(
+        cert: &Certificate,
+        trusted_roots: &[PublicKey],
+        trusted_anchors: &[ThresholdAnchor],
+        cur_time: Duration,
     ) -> Result<(), CertificateError> {
         let chain = &cert.chain;
 
@@ -212,11 +256,23 @@ impl Certificate {
             return Err(CertificateLengthError);
         }
 
-        // check root trust and its existence in trusted roots list
+        // check root trust and its existence in trusted roots list or its anchor trust
         let root = &chain[0];
         Trust::verify(root, &root.issued_for, cur_time).map_err(MalformedRoot)?;
-        if !trusted_roots.contains(&root.issued_for) {
-            return Err(NoTrustedRoot);
+        match &cert.anchor_trust {
+            Some(anchor_trust) => {
+                if !trusted_anchors.contains(&anchor_trust.anchor)
+                    || anchor_trust.issued_for != root.issued_for
+                {
+                    return Err(NoTrustedAnchor);
+                }
+                ThresholdTrust::verify(anchor_trust, cur_time).map_err(MalformedAnchorTrust)?;
+            }
+            None => {
+                if !trusted_roots.contains(&root.issued_for) {
+                    return Err(NoTrustedRoot);
+                }
+            }
         }
 
         // check if every element in a chain is not expired and has the correct signature
@@ -232,19 +288,44 @@ impl Certificate {
         Ok(())
     }
 
+    /// The oldest version able to carry all the trusts is used, so older decoders can still read it
+    fn version(&self) -> &'static [u8; 4] {
+        if self.anchor_trust.is_some() {
+            return ANCHORED_VERSION;
+        }
+
+        match self.chain.iter().map(|t| t.version).max() {
+            None | Some(SigningVersion::V1) => LEGACY_VERSION,
+            Some(SigningVersion::V2) => VERSIONED_TRUSTS_VERSION,
+            Some(SigningVersion::V3) => CONFIDENCE_VERSION,
+        }
+    }
+
     /// Convert certificate to byte format
-    /// 2 format + 4 version + 1 trusts number + ((1 trust size byte + trust) for each trust)
+    /// 2 format + 4 version + 1 trusts number + ((trust size + trust) for each trust)
+    /// + anchor trust till the end in the anchored version
+    ///
+    /// Trust size is 1 byte in the legacy version and 2 bytes since, so RSA trusts fit
     #[allow(dead_code)]
     pub fn encode(&self) -> Vec<u8> {
+        let version = self.version();
         let mut encoded = Vec::new();
         encoded.extend_from_slice(FORMAT);
-        encoded.extend_from_slice(VERSION);
+        encoded.extend_from_slice(version);
         encoded.push(self.chain.len() as u8);
 
         for t in &self.chain {
             let trust = t.encode();
-            encoded.push(trust.len() as u8);
-            encoded.extend(trust);
+            if version == LEGACY_VERSION {
+                encoded.push(trust.len() as u8);
+                encoded.extend(trust);
+            } else {
+                push_prefixed(&mut encoded, &trust);
+            }
+        }
+
+        if let Some(anchor_trust) = &self.anchor_trust {
+            encoded.extend(anchor_trust.encode());
         }
 
         encoded
@@ -260,17 +341,19 @@ impl Certificate {
 
     #[allow(dead_code)]
     pub fn decode(arr: &[u8]) -> Result<Self, CertificateError> {
-        // TODO do match different formats and versions
-        Self::check_arr_len(arr, FORMAT.len() + VERSION.len() + TRUST_NUMBER_LEN)?;
+        // TODO do match different formats
+        Self::check_arr_len(arr, FORMAT.len() + LEGACY_VERSION.len() + TRUST_NUMBER_LEN)?;
         let mut offset = 0;
         let _format = &arr[offset..offset + FORMAT.len()];
         offset += FORMAT.len();
 
-        let _version = &arr[offset..offset + VERSION.len()];
-        offset += VERSION.len();
+        let version = &arr[offset..offset + LEGACY_VERSION.len()];
+        let max_trust_version = Self::max_trust_version(version)?;
+        offset += LEGACY_VERSION.len();
 
         let number_of_trusts = arr[offset] as usize;
         offset += TRUST_NUMBER_LEN;
+        let trust_len_size = if version == LEGACY_VERSION { 1 } else { 2 };
 
         if number_of_trusts < 2 {
             return Err(CertificateLengthError);
@@ -278,27 +361,67 @@ impl Certificate {
         let mut chain = Vec::with_capacity(number_of_trusts);
 
         for _ in 0..number_of_trusts {
-            Self::check_arr_len(arr, offset + 1)?;
-            let trust_len = arr[offset] as usize;
-            let from = offset + 1;
+            Self::check_arr_len(arr, offset + trust_len_size)?;
+            let trust_len = prefixed_len(&arr[offset..offset + trust_len_size]);
+            let from = offset + trust_len_size;
             let to = from + trust_len;
             Self::check_arr_len(arr, to)?;
             let slice = &arr[from..to];
             let t = Trust::decode(slice).map_err(DecodeError)?;
+            if t.version > max_trust_version {
+                return Err(IncorrectCertificateFormat(format!(
+                    "trust with signing version {} in a certificate of version {version:?}",
+                    t.version.to_u8()
+                )));
+            }
             chain.push(t);
-            offset += 1 + trust_len;
+            offset = to;
+        }
+
+        if version == ANCHORED_VERSION {
+            let anchor_trust =
+                ThresholdTrust::decode(&arr[offset..]).map_err(DecodeAnchorTrustError)?;
+            return Ok(Self::new_anchored_unverified(chain, anchor_trust));
         }
 
-        Ok(Self { chain })
+        Ok(Self::new_unverified(chain))
+    }
+
+    /// Returns the latest signing version of trusts the certificate version can carry
+    fn max_trust_version(version: &[u8]) -> Result<SigningVersion, CertificateError> {
+        if version == LEGACY_VERSION {
+            Ok(SigningVersion::V1)
+        } else if version == VERSIONED_TRUSTS_VERSION {
+            Ok(SigningVersion::V2)
+        } else if version == CONFIDENCE_VERSION || version == ANCHORED_VERSION {
+            Ok(SigningVersion::V3)
+        } else {
+            Err(UnsupportedVersion(format!("{version:?}")))
+        }
     }
 }
 
+/// Every trust takes 4 lines in the legacy version,
+/// 5 lines in the version with versioned trusts, the last one is the signing version,
+/// and 6 lines in the version with confidence, the last one is the confidence.
+/// The anchored version has trusts as the version with confidence
+/// and one more line of base58 encoded anchor trust at the end
 impl std::fmt::Display for Certificate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let version = self.version();
         writeln!(f, "{}", bs58::encode(FORMAT).into_string())?;
-        writeln!(f, "{}", bs58::encode(VERSION).into_string())?;
+        writeln!(f, "{}", bs58::encode(version).into_string())?;
         for trust in self.chain.iter() {
             writeln!(f, "{}", trust.to_string())?;
+            if version != LEGACY_VERSION {
+                writeln!(f, "{}", trust.version.to_u8())?;
+            }
+            if version == CONFIDENCE_VERSION || version == ANCHORED_VERSION {
+                writeln!(f, "{}", trust.confidence)?;
+            }
+        }
+        if let Some(anchor_trust) = &self.anchor_trust {
+            writeln!(f, "{}", bs58::encode(anchor_trust.encode()).into_string())?;
         }
         Ok(())
     }
@@ -308,21 +431,44 @@ impl FromStr for Certificate {
     type Err = CertificateError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let str_lines: Vec<&str> = s.lines().collect();
+        let mut str_lines: Vec<&str> = s.lines().collect();
+
+        if str_lines.len() < 2 {
+            return Err(IncorrectCertificateFormat(s.to_string()));
+        }
 
         // TODO for future purposes
         let _format = str_lines[0];
-        let _version = str_lines[1];
+        let version = bs58::decode(str_lines[1])
+            .into_vec()
+            .map_err(|_| IncorrectCertificateFormat(s.to_string()))?;
+        let max_trust_version = Self::max_trust_version(&version)?;
+        let anchor_trust = if version == ANCHORED_VERSION {
+            if str_lines.len() < 3 {
+                return Err(IncorrectCertificateFormat(s.to_string()));
+            }
+            let encoded = bs58::decode(str_lines.pop().unwrap_or_default())
+                .into_vec()
+                .map_err(|_| IncorrectCertificateFormat(s.to_string()))?;
+            Some(ThresholdTrust::decode(&encoded).map_err(DecodeAnchorTrustError)?)
+        } else {
+            None
+        };
+        let trust_lines = match max_trust_version {
+            SigningVersion::V1 => 4,
+            SigningVersion::V2 => 5,
+            SigningVersion::V3 => 6,
+        };
 
-        if (str_lines.len() - 2) % 4 != 0 {
+        if (str_lines.len() - 2) % trust_lines != 0 {
             return Err(IncorrectCertificateFormat(s.to_string()));
         }
 
-        let num_of_trusts = (str_lines.len() - 2) / 4;
+        let num_of_trusts = (str_lines.len() - 2) / trust_lines;
         let mut trusts = Vec::with_capacity(num_of_trusts);
 
-        for i in (2..str_lines.len()).step_by(4) {
-            let trust = Trust::convert_from_strings(
+        for i in (2..str_lines.len()).step_by(trust_lines) {
+            let mut trust = Trust::convert_from_strings(
                 str_lines[i],
                 str_lines[i + 1],
                 str_lines[i + 2],
@@ -330,10 +476,27 @@ impl FromStr for Certificate {
             )
             .map_err(|e| DecodeTrustError(i, e))?;
 
+            if max_trust_version > SigningVersion::V1 {
+                trust.version = str_lines[i + 4]
+                    .parse()
+                    .ok()
+                    .and_then(SigningVersion::from_u8)
+                    .filter(|v| *v <= max_trust_version)
+                    .ok_or_else(|| IncorrectCertificateFormat(s.to_string()))?;
+            }
+            if max_trust_version > SigningVersion::V2 {
+                trust.confidence = str_lines[i + 5]
+                    .parse()
+                    .map_err(|_| IncorrectCertificateFormat(s.to_string()))?;
+            }
+
             trusts.push(trust);
         }
 
-        Ok(Self::new_unverified(trusts))
+        Ok(Self {
+            chain: trusts,
+            anchor_trust,
+        })
     }
 }
 
@@ -412,7 +575,7 @@ mod tests {
 
     #[test]
     fn test_small_chain_ed25519() {
-        let bad_cert = Certificate { chain: Vec::new() };
+        let bad_cert = Certificate::new_unverified(Vec::new());
 
         let check = Certificate::verify(&bad_cert, &[], current_time());
         assert!(check.is_err());
@@ -603,7 +766,7 @@ mod tests {
         // forged cert
         let mut bad_chain = cert.chain;
         bad_chain.remove(0);
-        let bad_cert = Certificate { chain: bad_chain };
+        let bad_cert = Certificate::new_unverified(bad_chain);
 
         assert!(Certificate::verify(&bad_cert, &trusted_roots, cur_time).is_err());
     }
@@ -620,4 +783,162 @@ mod tests {
         assert_eq!(cert.chain[1].issued_for, second_kp.public());
         assert!(Certificate::verify(&cert, &trusted_roots, cur_time).is_ok());
     }
+
+    fn v1_trust(issued_by: &KeyPair, issued_for: PublicKey, expires_at: Duration) -> Trust {
+        let issued_at = current_time();
+        let msg = Trust::signature_bytes(&issued_for, expires_at, issued_at);
+        let signature = issued_by.sign(&msg).unwrap();
+
+        Trust::new(issued_for, expires_at, issued_at, signature)
+    }
+
+    #[test]
+    fn test_legacy_cert_encoding_decoding_ed25519() {
+        let root_kp = KeyPair::generate_ed25519();
+        let second_kp = KeyPair::generate_ed25519();
+        let expires_at = current_time().checked_add(one_year()).unwrap();
+
+        let cert = Certificate::new_unverified(vec![
+            v1_trust(&root_kp, root_kp.public(), expires_at),
+            v1_trust(&root_kp, second_kp.public(), expires_at),
+        ]);
+
+        let encoded = cert.encode();
+        assert_eq!(&encoded[FORMAT.len()..FORMAT.len() + 4], LEGACY_VERSION);
+        assert_eq!(Certificate::decode(encoded.as_slice()).unwrap(), cert);
+        assert_eq!(Certificate::from_str(&cert.to_string()).unwrap(), cert);
+        assert!(Certificate::verify(&cert, &[root_kp.public()], current_time()).is_ok());
+    }
+
+    #[test]
+    fn test_mixed_versions_cert_ed25519() {
+        let (root_kp, second_kp, cert) = generate_root_cert();
+        let expires_at = current_time().checked_add(one_year()).unwrap();
+
+        let mut chain = cert.chain;
+        chain.push(v1_trust(
+            &second_kp,
+            KeyPair::generate_ed25519().public(),
+            expires_at,
+        ));
+        let cert = Certificate::new_unverified(chain);
+
+        let encoded = cert.encode();
+        assert_eq!(
+            &encoded[FORMAT.len()..FORMAT.len() + 4],
+            VERSIONED_TRUSTS_VERSION
+        );
+        assert_eq!(Certificate::decode(encoded.as_slice()).unwrap(), cert);
+        assert_eq!(Certificate::from_str(&cert.to_string()).unwrap(), cert);
+        assert!(Certificate::verify(&cert, &[root_kp.public()], current_time()).is_ok());
+
+        // v2 trusts can't be smuggled into a legacy certificate
+        let mut legacy = encoded;
+        legacy[FORMAT.len()..FORMAT.len() + 4].copy_from_slice(LEGACY_VERSION);
+        assert!(Certificate::decode(legacy.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_cert_encoding_decoding_rsa() {
+        let root_kp =
+            KeyPair::from_pkcs8_der(include_bytes!("../../keypair/src/test/rsa-2048.pk8")).unwrap();
+        let second_kp =
+            KeyPair::from_pkcs8_der(include_bytes!("../../keypair/src/test/rsa-4096.pk8")).unwrap();
+        let cur_time = current_time();
+
+        let cert = Certificate::issue_root(
+            &root_kp,
+            second_kp.public(),
+            cur_time.checked_add(one_year()).unwrap(),
+            cur_time,
+        );
+
+        let encoded = cert.encode();
+        assert_eq!(
+            &encoded[FORMAT.len()..FORMAT.len() + 4],
+            VERSIONED_TRUSTS_VERSION
+        );
+        assert_eq!(Certificate::decode(encoded.as_slice()).unwrap(), cert);
+        assert!(Certificate::verify(&cert, &[root_kp.public()], cur_time).is_ok());
+    }
+
+    #[test]
+    fn test_confidence_cert_ed25519() {
+        let (root_kp, second_kp, cert) = generate_root_cert();
+        let expires_at = current_time().checked_add(one_year()).unwrap();
+
+        let mut chain = cert.chain;
+        chain.push(Trust::create_with_confidence(
+            &second_kp,
+            KeyPair::generate_ed25519().public(),
+            expires_at,
+            current_time(),
+            100,
+        ));
+        let cert = Certificate::new_unverified(chain);
+
+        let encoded = cert.encode();
+        assert_eq!(&encoded[FORMAT.len()..FORMAT.len() + 4], CONFIDENCE_VERSION);
+        assert_eq!(Certificate::decode(encoded.as_slice()).unwrap(), cert);
+        assert_eq!(Certificate::from_str(&cert.to_string()).unwrap(), cert);
+        assert!(Certificate::verify(&cert, &[root_kp.public()], current_time()).is_ok());
+
+        let mut versioned = encoded;
+        versioned[FORMAT.len()..FORMAT.len() + 4].copy_from_slice(VERSIONED_TRUSTS_VERSION);
+        assert!(Certificate::decode(versioned.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_anchored_cert_ed25519() {
+        let (root_kp, _second_kp, cert) = generate_root_cert();
+        let cur_time = current_time();
+        let expires_at = cur_time.checked_add(one_year()).unwrap();
+
+        let holders: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate_ed25519()).collect();
+        let anchor =
+            ThresholdAnchor::new(holders.iter().map(|kp| kp.public()).collect(), 2).unwrap();
+
+        let anchor_trust = ThresholdTrust::create(
+            anchor.clone(),
+            &[&holders[0], &holders[1]],
+            root_kp.public(),
+            expires_at,
+            cur_time,
+        );
+        let anchored = Certificate::new_anchored_unverified(cert.chain.clone(), anchor_trust);
+
+        let encoded = anchored.encode();
+        assert_eq!(&encoded[FORMAT.len()..FORMAT.len() + 4], ANCHORED_VERSION);
+        assert_eq!(Certificate::decode(encoded.as_slice()).unwrap(), anchored);
+        assert_eq!(
+            Certificate::from_str(&anchored.to_string()).unwrap(),
+            anchored
+        );
+        assert!(matches!(
+            Certificate::decode(&encoded[..encoded.len() - 1]),
+            Err(DecodeAnchorTrustError(_))
+        ));
+
+        assert!(Certificate::verify_with_anchors(
+            &anchored,
+            &[],
+            std::slice::from_ref(&anchor),
+            cur_time
+        )
+        .is_ok());
+        assert!(Certificate::verify(&anchored, &[], cur_time).is_err());
+
+        let weak_anchor_trust = ThresholdTrust::create(
+            anchor.clone(),
+            &[&holders[2]],
+            root_kp.public(),
+            expires_at,
+            cur_time,
+        );
+        let weak = Certificate::new_anchored_unverified(cert.chain, weak_anchor_trust);
+        assert!(matches!(
+            Certificate::verify_with_anchors(&weak, &[], &[anchor], cur_time),
+            Err(MalformedAnchorTrust(_))
+        ));
+    }
 }
@@ -39,6 +39,30 @@ mod single {
     }
 }
 
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S>(value: &Option<Certificate>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value.as_ref().map(|c| c.to_string()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Certificate>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|str| {
+                Certificate::from_str(&str).map_err(|e| {
+                    Error::custom(format!("certificate deserialization failed for {e:?}"))
+                })
+            })
+            .transpose()
+    }
+}
+
 pub mod vec {
     use super::*;
     use serde::ser::SerializeSeq;
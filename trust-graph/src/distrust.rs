@@ -0,0 +1,137 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::distrust::DistrustError::IncorrectSignature;
+use crate::signing::{push_prefixed, v2_signature_bytes, PayloadType};
+use fluence_keypair::key_pair::KeyPair;
+use fluence_keypair::public_key::PublicKey;
+use fluence_keypair::signature::Signature;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum DistrustError {
+    #[error("Signature is incorrect: {0}")]
+    IncorrectSignature(
+        #[from]
+        #[source]
+        fluence_keypair::error::VerificationError,
+    ),
+}
+
+/// Signed statement that `distrusted_by` considers `pk` untrusted.
+/// Unlike a revocation, it doesn't require a trust given before.
+/// Canceled by a later trust or revocation between the same keys.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Distrust {
+    /// who is distrusted
+    pub pk: PublicKey,
+    /// date when distrust was created
+    pub distrusted_at: Duration,
+    /// the issuer of this distrust
+    pub distrusted_by: PublicKey,
+    /// proof of this distrust
+    pub signature: Signature,
+}
+
+/// How distrust affects weights of certificates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DistrustPolicy {
+    /// distrusts are stored but don't affect weights
+    #[default]
+    Ignore,
+    /// every applicable distrust adds the given number to the certificate weight factor,
+    /// i.e. halves its weight that many times
+    Penalty(u32),
+    /// a certificate with at least one applicable distrust gives no weight
+    Veto,
+}
+
+impl Distrust {
+    pub fn new(
+        distrusted_by: PublicKey,
+        pk: PublicKey,
+        distrusted_at: Duration,
+        signature: Signature,
+    ) -> Self {
+        Self {
+            pk,
+            distrusted_at,
+            distrusted_by,
+            signature,
+        }
+    }
+
+    /// Creates new distrust signed by a distruster.
+    pub fn create(distruster: &KeyPair, to_distrust: PublicKey, distrusted_at: Duration) -> Self {
+        let msg = Distrust::signature_bytes(&to_distrust, distrusted_at);
+        let signature = distruster.sign(&msg).unwrap();
+
+        Distrust::new(distruster.public(), to_distrust, distrusted_at, signature)
+    }
+
+    pub fn signature_bytes(pk: &PublicKey, distrusted_at: Duration) -> Vec<u8> {
+        let mut fields = Vec::new();
+        push_prefixed(&mut fields, &pk.encode());
+        fields.extend_from_slice(&distrusted_at.as_secs().to_le_bytes());
+
+        v2_signature_bytes(PayloadType::Distrust, &fields)
+    }
+
+    /// Verifies that distrust is cryptographically correct.
+    pub fn verify(distrust: &Distrust) -> Result<(), DistrustError> {
+        let msg = Distrust::signature_bytes(&distrust.pk, distrust.distrusted_at);
+
+        distrust
+            .distrusted_by
+            .verify(msg.as_slice(), &distrust.signature)
+            .map_err(IncorrectSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::revoke::Revocation;
+
+    #[test]
+    fn test_gen_distrust_and_validate_ed25519() {
+        let distruster = KeyPair::generate_ed25519();
+        let to_distrust = KeyPair::generate_ed25519();
+
+        let distrust = Distrust::create(&distruster, to_distrust.public(), Duration::new(100, 0));
+
+        assert!(Distrust::verify(&distrust).is_ok());
+    }
+
+    #[test]
+    fn test_revocation_is_not_a_distrust_ed25519() {
+        let revoker = KeyPair::generate_ed25519();
+        let to_revoke = KeyPair::generate_ed25519();
+        let duration = Duration::new(100, 0);
+
+        let revocation = Revocation::create(&revoker, to_revoke.public(), duration);
+        let distrust = Distrust::new(
+            revoker.public(),
+            to_revoke.public(),
+            duration,
+            revocation.signature,
+        );
+
+        assert!(Distrust::verify(&distrust).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::public_key_hashable::PublicKeyHashable as PK;
+use crate::trust_relation::TrustRelation;
+use std::collections::HashSet;
+
+/// Format of the graph export, see `TrustGraph::export_graph`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    GraphMl,
+}
+
+/// Edge of the exported graph, distrusts are not exported
+struct Edge {
+    from: String,
+    to: String,
+    revoked: bool,
+    issued_at: u64,
+    /// None for revocations, they never expire
+    expires_at: Option<u64>,
+}
+
+/// Render roots and relations, every relation is an edge from its issuer.
+/// Keys are named by their peer ids.
+pub(crate) fn render(format: GraphFormat, roots: &[PK], relations: &[TrustRelation]) -> String {
+    let edges: Vec<Edge> = relations
+        .iter()
+        .filter_map(|r| {
+            let (revoked, expires_at) = match r {
+                TrustRelation::Auth(auth) => (false, Some(auth.trust.expires_at.as_secs())),
+                TrustRelation::Revocation(_) => (true, None),
+                TrustRelation::Distrust(_) => return None,
+            };
+
+            Some(Edge {
+                from: r.issued_by().to_peer_id().to_base58(),
+                to: r.issued_for().to_peer_id().to_base58(),
+                revoked,
+                issued_at: r.issued_at().as_secs(),
+                expires_at,
+            })
+        })
+        .collect();
+
+    let roots: Vec<String> = roots
+        .iter()
+        .map(|pk| pk.as_ref().to_peer_id().to_base58())
+        .collect();
+
+    // roots go first, other keys in order of appearance
+    let mut seen = HashSet::new();
+    let nodes: Vec<(&String, bool)> = roots
+        .iter()
+        .map(|k| (k, true))
+        .chain(
+            edges
+                .iter()
+                .flat_map(|e| [(&e.from, false), (&e.to, false)]),
+        )
+        .filter(|(k, _)| seen.insert(*k))
+        .collect();
+
+    match format {
+        GraphFormat::Dot => to_dot(&nodes, &edges),
+        GraphFormat::GraphMl => to_graphml(&nodes, &edges),
+    }
+}
+
+fn to_dot(nodes: &[(&String, bool)], edges: &[Edge]) -> String {
+    let mut lines = vec!["digraph trust_graph {".to_string()];
+
+    for (key, root) in nodes {
+        if *root {
+            lines.push(format!("  \"{key}\" [shape=doublecircle, root=true];"));
+        } else {
+            lines.push(format!("  \"{key}\";"));
+        }
+    }
+
+    for e in edges {
+        let mut attrs = vec![format!("issued_at={}", e.issued_at)];
+        if let Some(expires_at) = e.expires_at {
+            attrs.push(format!("expires_at={expires_at}"));
+        }
+        if e.revoked {
+            attrs.push("revoked=true, label=\"revoked\", style=dashed, color=red".to_string());
+        } else {
+            attrs.push("revoked=false, label=\"trust\"".to_string());
+        }
+        lines.push(format!(
+            "  \"{}\" -> \"{}\" [{}];",
+            e.from,
+            e.to,
+            attrs.join(", ")
+        ));
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
+
+fn to_graphml(nodes: &[(&String, bool)], edges: &[Edge]) -> String {
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#.to_string(),
+        r#"  <key id="root" for="node" attr.name="root" attr.type="boolean"><default>false</default></key>"#.to_string(),
+        r#"  <key id="issued_at" for="edge" attr.name="issued_at" attr.type="long"/>"#.to_string(),
+        r#"  <key id="expires_at" for="edge" attr.name="expires_at" attr.type="long"/>"#.to_string(),
+        r#"  <key id="revoked" for="edge" attr.name="revoked" attr.type="boolean"/>"#.to_string(),
+        r#"  <graph id="trust_graph" edgedefault="directed">"#.to_string(),
+    ];
+
+    // base58 has no characters to escape in XML
+    for (key, root) in nodes {
+        if *root {
+            lines.push(format!(
+                r#"    <node id="{key}"><data key="root">true</data></node>"#
+            ));
+        } else {
+            lines.push(format!(r#"    <node id="{key}"/>"#));
+        }
+    }
+
+    for e in edges {
+        lines.push(format!(
+            r#"    <edge source="{}" target="{}">"#,
+            e.from, e.to
+        ));
+        lines.push(format!(
+            r#"      <data key="issued_at">{}</data>"#,
+            e.issued_at
+        ));
+        if let Some(expires_at) = e.expires_at {
+            lines.push(format!(
+                r#"      <data key="expires_at">{expires_at}</data>"#
+            ));
+        }
+        lines.push(format!(r#"      <data key="revoked">{}</data>"#, e.revoked));
+        lines.push("    </edge>".to_string());
+    }
+
+    lines.push("  </graph>".to_string());
+    lines.push("</graphml>".to_string());
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::revoke::Revocation;
+    use crate::trust::Trust;
+    use crate::trust_relation::Auth;
+    use fluence_keypair::key_pair::KeyPair;
+    use std::time::Duration;
+
+    fn relations() -> (KeyPair, KeyPair, Vec<TrustRelation>) {
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let auth = Auth {
+            trust: Trust::create(
+                &root_kp,
+                a_kp.public(),
+                Duration::from_secs(200),
+                Duration::from_secs(100),
+            ),
+            issued_by: root_kp.public(),
+        };
+        let revocation = Revocation::create(&a_kp, root_kp.public(), Duration::from_secs(150));
+
+        (
+            root_kp,
+            a_kp,
+            vec![
+                TrustRelation::Auth(auth),
+                TrustRelation::Revocation(revocation),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_dot() {
+        let (root_kp, a_kp, relations) = relations();
+        let root = root_kp.get_peer_id().to_base58();
+        let a = a_kp.get_peer_id().to_base58();
+
+        let dot = render(GraphFormat::Dot, &[root_kp.public().into()], &relations);
+
+        assert!(dot.starts_with("digraph trust_graph {\n"));
+        assert!(dot.contains(&format!("\"{root}\" [shape=doublecircle, root=true];")));
+        assert!(dot.contains(&format!("  \"{a}\";")));
+        assert!(dot.contains(&format!(
+            "\"{root}\" -> \"{a}\" [issued_at=100, expires_at=200, revoked=false"
+        )));
+        assert!(dot.contains(&format!(
+            "\"{a}\" -> \"{root}\" [issued_at=150, revoked=true"
+        )));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_graphml() {
+        let (root_kp, a_kp, relations) = relations();
+        let root = root_kp.get_peer_id().to_base58();
+        let a = a_kp.get_peer_id().to_base58();
+
+        let graphml = render(GraphFormat::GraphMl, &[root_kp.public().into()], &relations);
+
+        assert_eq!(graphml.matches("<node ").count(), 2);
+        assert_eq!(graphml.matches("<edge ").count(), 2);
+        assert!(graphml.contains(&format!(
+            r#"<node id="{root}"><data key="root">true</data></node>"#
+        )));
+        assert!(graphml.contains(&format!(r#"<edge source="{a}" target="{root}">"#)));
+        assert!(graphml.contains(r#"<data key="expires_at">200</data>"#));
+        assert!(graphml.contains(r#"<data key="revoked">true</data>"#));
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+}
@@ -0,0 +1,143 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+/// What `TrustGraph::gc` removes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GcPolicy {
+    /// remove expired auths
+    pub remove_expired: bool,
+    /// remove all relations issued by keys that can't be reached from any root by valid trusts
+    pub remove_unreachable: bool,
+    /// remove revocations older than this.
+    /// Only the time of a removed revocation is kept,
+    /// so trusts issued before it are still rejected.
+    pub revocation_retention: Option<Duration>,
+}
+
+impl Default for GcPolicy {
+    fn default() -> Self {
+        Self {
+            remove_expired: true,
+            remove_unreachable: false,
+            revocation_retention: None,
+        }
+    }
+}
+
+/// Numbers of relations removed by `TrustGraph::gc`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub expired: u64,
+    pub unreachable: u64,
+    pub revocations: u64,
+}
+
+/// When garbage collection runs besides explicit `TrustGraph::gc` calls
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GcSchedule {
+    /// expired auths are removed on every read of certificates
+    #[default]
+    OnRead,
+    /// gc with the policy runs at most once in `interval`, checked on reads of certificates
+    Every {
+        interval: Duration,
+        policy: GcPolicy,
+    },
+    /// only explicit gc calls
+    Manual,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_memory_storage::InMemoryStorage;
+    use crate::revoke::Revocation;
+    use crate::sync::LocalTransport;
+    use crate::trust::Trust;
+    use crate::trust_graph::TrustGraph;
+    use fluence_keypair::key_pair::KeyPair;
+
+    fn graph_with_trust(root_kp: &KeyPair, trust: &Trust) -> TrustGraph<InMemoryStorage> {
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_root(root_kp.public(), 10).unwrap();
+        let root_trust = Trust::create(
+            root_kp,
+            root_kp.public(),
+            Duration::from_secs(1000),
+            Duration::from_secs(1),
+        );
+        graph
+            .add_trust(root_trust, root_kp.public(), Duration::from_secs(1))
+            .unwrap();
+        graph
+            .add_trust(trust.clone(), root_kp.public(), trust.issued_at)
+            .unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_revoked_trust_is_not_replayed_after_gc() {
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::from_secs(100);
+        let trust = Trust::create(
+            &root_kp,
+            a_kp.public(),
+            Duration::from_secs(1000),
+            Duration::from_secs(10),
+        );
+
+        let mut graph = graph_with_trust(&root_kp, &trust);
+        // the replica never saw the revocation
+        let replica = graph_with_trust(&root_kp, &trust);
+        graph
+            .revoke(Revocation::create(
+                &root_kp,
+                a_kp.public(),
+                Duration::from_secs(20),
+            ))
+            .unwrap();
+
+        let policy = GcPolicy {
+            revocation_retention: Some(Duration::from_secs(50)),
+            ..GcPolicy::default()
+        };
+        let report = graph.gc(cur_time, &policy).unwrap();
+        assert_eq!(report.revocations, 1);
+
+        // the revoked trust is still valid, but issued before the removed revocation
+        graph
+            .add_trust(trust.clone(), root_kp.public(), cur_time)
+            .unwrap();
+        assert_eq!(graph.weight(a_kp.public(), cur_time).unwrap(), 0);
+
+        graph
+            .sync_from(&mut LocalTransport::new(&replica), cur_time)
+            .unwrap();
+        assert_eq!(graph.weight(a_kp.public(), cur_time).unwrap(), 0);
+
+        let newer = Trust::create(
+            &root_kp,
+            a_kp.public(),
+            Duration::from_secs(1000),
+            Duration::from_secs(30),
+        );
+        graph.add_trust(newer, root_kp.public(), cur_time).unwrap();
+        assert_ne!(graph.weight(a_kp.public(), cur_time).unwrap(), 0);
+    }
+}
@@ -0,0 +1,378 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::distrust::Distrust;
+use crate::key_compromise::KeyCompromise;
+use crate::merkle::Hash;
+use crate::pending::PendingTrust;
+use crate::public_key_hashable::PublicKeyHashable as PK;
+use crate::revocation_list::RevocationList;
+use crate::revoke::Revocation;
+use crate::threshold::{ThresholdAnchor, ThresholdTrust};
+use crate::transparency_log::LogEntry;
+use crate::trust_graph::WeightFactor;
+use crate::trust_graph_storage::{Storage, StorageError};
+use crate::trust_relation::{Auth, TrustRelation};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+#[error("In-memory storage never fails")]
+pub(crate) struct InMemoryStorageError;
+
+impl StorageError for InMemoryStorageError {}
+
+/// Storage for tests, keeps the same replacement rules as the service storage
+#[derive(Clone, Default)]
+pub(crate) struct InMemoryStorage {
+    /// keyed by (issued_for, issued_by)
+    relations: HashMap<(PK, PK), TrustRelation>,
+    roots: HashMap<PK, WeightFactor>,
+    revocation_lists: HashMap<PK, RevocationList>,
+    compromises: HashMap<PK, KeyCompromise>,
+    threshold_anchors: Vec<(ThresholdAnchor, WeightFactor)>,
+    threshold_trusts: HashMap<PK, ThresholdTrust>,
+    pending: HashMap<(PK, PK), PendingTrust>,
+    log: Vec<LogEntry>,
+    /// times of revocations removed by gc
+    tombstones: HashMap<(PK, PK), Duration>,
+    /// state tree leaves by their keys with their buckets and expiration times
+    state_leaves: BTreeMap<Vec<u8>, (u8, Hash, Option<Duration>)>,
+    state_buckets: BTreeMap<u8, Hash>,
+    /// contents before the current transaction
+    snapshot: Option<Box<InMemoryStorage>>,
+}
+
+impl InMemoryStorage {
+    /// only a newer relation replaces the stored one
+    fn update_relation(&mut self, relation: TrustRelation) {
+        let key = (
+            relation.issued_for().clone().into(),
+            relation.issued_by().clone().into(),
+        );
+
+        match self.relations.get(&key) {
+            Some(current) if current.issued_at() >= relation.issued_at() => {}
+            None if self
+                .tombstones
+                .get(&key)
+                .is_some_and(|revoked_at| *revoked_at >= relation.issued_at()) => {}
+            _ => {
+                self.relations.insert(key, relation);
+            }
+        }
+    }
+
+    fn relations_for(&self, issued_for: &PK) -> impl Iterator<Item = &TrustRelation> + '_ {
+        let issued_for = issued_for.clone();
+        self.relations
+            .iter()
+            .filter(move |((pk, _), _)| *pk == issued_for)
+            .map(|(_, r)| r)
+    }
+}
+
+impl Storage for InMemoryStorage {
+    type Error = InMemoryStorageError;
+
+    fn get_relation(
+        &self,
+        issued_for: &PK,
+        issued_by: &PK,
+    ) -> Result<Option<TrustRelation>, Self::Error> {
+        Ok(self
+            .relations
+            .get(&(issued_for.clone(), issued_by.clone()))
+            .cloned())
+    }
+
+    fn get_authorizations(&self, issued_for: &PK) -> Result<Vec<Auth>, Self::Error> {
+        Ok(self
+            .relations_for(issued_for)
+            .filter_map(|r| match r {
+                TrustRelation::Auth(auth) => Some(auth.clone()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn get_revocations(&self, issued_for: &PK) -> Result<Vec<Revocation>, Self::Error> {
+        Ok(self
+            .relations_for(issued_for)
+            .filter_map(|r| match r {
+                TrustRelation::Revocation(revocation) => Some(revocation.clone()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn get_distrusts(&self, issued_for: &PK) -> Result<Vec<Distrust>, Self::Error> {
+        Ok(self
+            .relations_for(issued_for)
+            .filter_map(|r| match r {
+                TrustRelation::Distrust(distrust) => Some(distrust.clone()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn get_issued_by(&self, issued_by: &PK) -> Result<Vec<TrustRelation>, Self::Error> {
+        Ok(self
+            .relations
+            .iter()
+            .filter(|((_, pk), _)| pk == issued_by)
+            .map(|(_, r)| r.clone())
+            .collect())
+    }
+
+    fn get_all_relations(&self) -> Result<Vec<TrustRelation>, Self::Error> {
+        Ok(self.relations.values().cloned().collect())
+    }
+
+    fn insert(&mut self, relation: TrustRelation) -> Result<(), Self::Error> {
+        self.relations.insert(
+            (
+                relation.issued_for().clone().into(),
+                relation.issued_by().clone().into(),
+            ),
+            relation,
+        );
+        Ok(())
+    }
+
+    fn set_tombstone(
+        &mut self,
+        issued_for: &PK,
+        issued_by: &PK,
+        revoked_at: Duration,
+    ) -> Result<(), Self::Error> {
+        self.tombstones
+            .insert((issued_for.clone(), issued_by.clone()), revoked_at);
+        Ok(())
+    }
+
+    fn remove_relation(&mut self, issued_for: &PK, issued_by: &PK) -> Result<(), Self::Error> {
+        self.relations
+            .remove(&(issued_for.clone(), issued_by.clone()));
+        Ok(())
+    }
+
+    fn get_root_weight_factor(&self, pk: &PK) -> Result<Option<WeightFactor>, Self::Error> {
+        Ok(self.roots.get(pk).copied())
+    }
+
+    fn set_root_weight_factor(
+        &mut self,
+        pk: PK,
+        weight_factor: WeightFactor,
+    ) -> Result<(), Self::Error> {
+        self.roots.insert(pk, weight_factor);
+        Ok(())
+    }
+
+    fn root_keys(&self) -> Result<Vec<PK>, Self::Error> {
+        Ok(self.roots.keys().cloned().collect())
+    }
+
+    fn revoke(&mut self, revocation: Revocation) -> Result<(), Self::Error> {
+        self.update_relation(TrustRelation::Revocation(revocation));
+        Ok(())
+    }
+
+    fn distrust(&mut self, distrust: Distrust) -> Result<(), Self::Error> {
+        self.update_relation(TrustRelation::Distrust(distrust));
+        Ok(())
+    }
+
+    fn update_auth(&mut self, auth: Auth, _cur_time: Duration) -> Result<(), Self::Error> {
+        self.update_relation(TrustRelation::Auth(auth));
+        Ok(())
+    }
+
+    fn remove_expired(&mut self, cur_time: Duration) -> Result<(), Self::Error> {
+        self.relations.retain(|_, r| match r {
+            TrustRelation::Auth(auth) => auth.trust.expires_at > cur_time,
+            _ => true,
+        });
+        Ok(())
+    }
+
+    fn get_revocation_list(&self, issued_by: &PK) -> Result<Option<RevocationList>, Self::Error> {
+        Ok(self.revocation_lists.get(issued_by).cloned())
+    }
+
+    fn set_revocation_list(&mut self, list: RevocationList) -> Result<(), Self::Error> {
+        self.revocation_lists
+            .insert(list.issued_by.clone().into(), list);
+        Ok(())
+    }
+
+    fn get_key_compromise(&self, pk: &PK) -> Result<Option<KeyCompromise>, Self::Error> {
+        Ok(self.compromises.get(pk).cloned())
+    }
+
+    fn set_key_compromise(&mut self, compromise: KeyCompromise) -> Result<(), Self::Error> {
+        self.compromises
+            .insert(compromise.pk.clone().into(), compromise);
+        Ok(())
+    }
+
+    fn get_threshold_anchor_weight_factor(
+        &self,
+        anchor: &ThresholdAnchor,
+    ) -> Result<Option<WeightFactor>, Self::Error> {
+        Ok(self
+            .threshold_anchors
+            .iter()
+            .find(|(a, _)| a == anchor)
+            .map(|(_, wf)| *wf))
+    }
+
+    fn set_threshold_anchor_weight_factor(
+        &mut self,
+        anchor: ThresholdAnchor,
+        weight_factor: WeightFactor,
+    ) -> Result<(), Self::Error> {
+        self.threshold_anchors.retain(|(a, _)| *a != anchor);
+        self.threshold_anchors.push((anchor, weight_factor));
+        Ok(())
+    }
+
+    fn get_threshold_trust(&self, issued_for: &PK) -> Result<Option<ThresholdTrust>, Self::Error> {
+        Ok(self.threshold_trusts.get(issued_for).cloned())
+    }
+
+    fn set_threshold_trust(&mut self, trust: ThresholdTrust) -> Result<(), Self::Error> {
+        self.threshold_trusts
+            .insert(trust.issued_for.clone().into(), trust);
+        Ok(())
+    }
+
+    fn threshold_trusts(&self) -> Result<Vec<ThresholdTrust>, Self::Error> {
+        Ok(self.threshold_trusts.values().cloned().collect())
+    }
+
+    fn get_pending(&self, issued_by: &PK) -> Result<Vec<PendingTrust>, Self::Error> {
+        Ok(self
+            .pending
+            .iter()
+            .filter(|((_, pk), _)| pk == issued_by)
+            .map(|(_, p)| p.clone())
+            .collect())
+    }
+
+    fn get_all_pending(&self) -> Result<Vec<PendingTrust>, Self::Error> {
+        Ok(self.pending.values().cloned().collect())
+    }
+
+    fn insert_pending(&mut self, pending: PendingTrust) -> Result<(), Self::Error> {
+        self.pending.insert(
+            (
+                pending.auth.trust.issued_for.clone().into(),
+                pending.auth.issued_by.clone().into(),
+            ),
+            pending,
+        );
+        Ok(())
+    }
+
+    fn remove_pending(&mut self, issued_for: &PK, issued_by: &PK) -> Result<(), Self::Error> {
+        self.pending
+            .remove(&(issued_for.clone(), issued_by.clone()));
+        Ok(())
+    }
+
+    fn log_size(&self) -> Result<u64, Self::Error> {
+        Ok(self.log.len() as u64)
+    }
+
+    fn append_log_entry(&mut self, entry: LogEntry) -> Result<(), Self::Error> {
+        self.log.push(entry);
+        Ok(())
+    }
+
+    fn get_log_entries(&self, from: u64, to: u64) -> Result<Vec<LogEntry>, Self::Error> {
+        let to = std::cmp::min(to as usize, self.log.len());
+        let from = std::cmp::min(from as usize, to);
+        Ok(self.log[from..to].to_vec())
+    }
+
+    fn get_state_leaves(&self, bucket: u8) -> Result<Vec<(Vec<u8>, Hash)>, Self::Error> {
+        Ok(self
+            .state_leaves
+            .iter()
+            .filter(|(_, (b, _, _))| *b == bucket)
+            .map(|(key, (_, leaf, _))| (key.clone(), *leaf))
+            .collect())
+    }
+
+    fn insert_state_leaf(
+        &mut self,
+        bucket: u8,
+        key: Vec<u8>,
+        leaf: Hash,
+        expires_at: Option<Duration>,
+    ) -> Result<(), Self::Error> {
+        self.state_leaves.insert(key, (bucket, leaf, expires_at));
+        Ok(())
+    }
+
+    fn remove_state_leaf(&mut self, key: &[u8]) -> Result<(), Self::Error> {
+        self.state_leaves.remove(key);
+        Ok(())
+    }
+
+    fn remove_expired_state_leaves(&mut self, cur_time: Duration) -> Result<Vec<u8>, Self::Error> {
+        let mut buckets = vec![];
+        self.state_leaves.retain(|_, (bucket, _, expires_at)| {
+            let expired = expires_at.is_some_and(|e| e <= cur_time);
+            if expired {
+                buckets.push(*bucket);
+            }
+            !expired
+        });
+        buckets.sort_unstable();
+        buckets.dedup();
+        Ok(buckets)
+    }
+
+    fn get_state_buckets(&self) -> Result<Vec<Hash>, Self::Error> {
+        Ok(self.state_buckets.values().copied().collect())
+    }
+
+    fn set_state_bucket(&mut self, bucket: u8, root: Hash) -> Result<(), Self::Error> {
+        self.state_buckets.insert(bucket, root);
+        Ok(())
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        self.snapshot = Some(Box::new(self.clone()));
+        Ok(())
+    }
+
+    fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        self.snapshot = None;
+        Ok(())
+    }
+
+    fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        if let Some(snapshot) = self.snapshot.take() {
+            *self = *snapshot;
+        }
+        Ok(())
+    }
+}
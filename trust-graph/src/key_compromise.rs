@@ -0,0 +1,133 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::key_compromise::KeyCompromiseError::IncorrectSignature;
+use crate::signing::{push_prefixed, v2_signature_bytes, PayloadType};
+use fluence_keypair::key_pair::KeyPair;
+use fluence_keypair::public_key::PublicKey;
+use fluence_keypair::signature::Signature;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum KeyCompromiseError {
+    #[error("Signature is incorrect: {0}")]
+    IncorrectSignature(
+        #[from]
+        #[source]
+        fluence_keypair::error::VerificationError,
+    ),
+}
+
+/// Self-signed statement that the private key of `pk` leaked at `compromised_at`.
+/// Everything signed by `pk` after that moment is considered void.
+/// The statement never expires and can't be canceled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyCompromise {
+    /// the compromised key, also the signer of this statement
+    pub pk: PublicKey,
+    /// signatures made after this moment are void
+    pub compromised_at: Duration,
+    /// proof of this statement
+    pub signature: Signature,
+}
+
+impl KeyCompromise {
+    pub fn new(pk: PublicKey, compromised_at: Duration, signature: Signature) -> Self {
+        Self {
+            pk,
+            compromised_at,
+            signature,
+        }
+    }
+
+    /// Creates new compromise statement signed by the compromised key.
+    pub fn create(compromised: &KeyPair, compromised_at: Duration) -> Self {
+        let msg = KeyCompromise::signature_bytes(&compromised.public(), compromised_at);
+        let signature = compromised.sign(&msg).unwrap();
+
+        KeyCompromise::new(compromised.public(), compromised_at, signature)
+    }
+
+    pub fn signature_bytes(pk: &PublicKey, compromised_at: Duration) -> Vec<u8> {
+        let mut fields = Vec::new();
+        push_prefixed(&mut fields, &pk.encode());
+        fields.extend_from_slice(&compromised_at.as_secs().to_le_bytes());
+
+        v2_signature_bytes(PayloadType::KeyCompromise, &fields)
+    }
+
+    /// Verifies that the statement is signed by the compromised key itself.
+    pub fn verify(compromise: &KeyCompromise) -> Result<(), KeyCompromiseError> {
+        let msg = KeyCompromise::signature_bytes(&compromise.pk, compromise.compromised_at);
+
+        compromise
+            .pk
+            .verify(msg.as_slice(), &compromise.signature)
+            .map_err(IncorrectSignature)
+    }
+
+    /// Returns true if something signed by `pk` at `signed_at` must be ignored.
+    pub fn voids(&self, signed_at: Duration) -> bool {
+        signed_at > self.compromised_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::revoke::Revocation;
+
+    #[test]
+    fn test_gen_key_compromise_and_validate_ed25519() {
+        let compromised = KeyPair::generate_ed25519();
+        let compromised_at = Duration::new(100, 0);
+
+        let compromise = KeyCompromise::create(&compromised, compromised_at);
+
+        assert!(KeyCompromise::verify(&compromise).is_ok());
+        assert!(!compromise.voids(compromised_at));
+        assert!(compromise.voids(compromised_at + Duration::new(1, 0)));
+    }
+
+    #[test]
+    fn test_key_compromise_signed_by_other_key_ed25519() {
+        let compromised = KeyPair::generate_ed25519();
+        let other = KeyPair::generate_ed25519();
+        let compromised_at = Duration::new(100, 0);
+
+        let msg = KeyCompromise::signature_bytes(&compromised.public(), compromised_at);
+        let compromise = KeyCompromise::new(
+            compromised.public(),
+            compromised_at,
+            other.sign(&msg).unwrap(),
+        );
+
+        assert!(KeyCompromise::verify(&compromise).is_err());
+    }
+
+    #[test]
+    fn test_self_revocation_is_not_a_key_compromise_ed25519() {
+        let kp = KeyPair::generate_ed25519();
+        let duration = Duration::new(100, 0);
+
+        let revocation = Revocation::create(&kp, kp.public(), duration);
+        let compromise = KeyCompromise::new(kp.public(), duration, revocation.signature);
+
+        assert!(KeyCompromise::verify(&compromise).is_err());
+    }
+}
@@ -27,22 +27,64 @@
 )]
 #![allow(dead_code)]
 
+mod attestation;
 mod certificate;
 pub mod certificate_serde;
 mod chain;
+mod distrust;
+mod export;
+mod gc;
+#[cfg(test)]
+mod in_memory_storage;
+mod key_compromise;
+mod merkle;
 mod misc;
+mod pending;
 mod public_key_hashable;
+mod quota;
+mod reputation;
+mod revocation_list;
 mod revoke;
+mod signing;
+mod state;
+mod stats;
+mod sync;
+mod threshold;
+mod transparency_log;
 mod trust;
 mod trust_graph;
 mod trust_graph_storage;
 mod trust_relation;
+mod weight_proof;
 
+pub use crate::attestation::{AttestationError, WeightAttestation};
 pub use crate::certificate::{Certificate, CertificateError};
+pub use crate::distrust::{Distrust, DistrustError, DistrustPolicy};
+pub use crate::export::GraphFormat;
+pub use crate::gc::{GcPolicy, GcReport, GcSchedule};
+pub use crate::key_compromise::{KeyCompromise, KeyCompromiseError};
+pub use crate::merkle::Hash as MerkleHash;
 pub use crate::misc::current_time;
+pub use crate::pending::{PendingPoolConfig, PendingTrust};
 pub use crate::public_key_hashable::PublicKeyHashable;
+pub use crate::quota::{EvictionPolicy, QuotaKind, Quotas};
+pub use crate::reputation::{Reputation, ReputationConfig};
+pub use crate::revocation_list::{RevocationList, RevocationListError};
 pub use crate::revoke::Revocation;
-pub use crate::trust::{Trust, TrustError};
-pub use crate::trust_graph::{TrustGraph, TrustGraphError, WeightFactor, MAX_WEIGHT_FACTOR};
+pub use crate::signing::{SigningVersion, DOMAIN_TAG};
+pub use crate::state::{NonRevocationProof, StateLeaf, StateProof};
+pub use crate::stats::GraphStats;
+pub use crate::sync::{
+    LocalTransport, RelationDigest, SyncReport, SyncSummary, SyncTransport, SYNC_BUCKETS,
+};
+pub use crate::threshold::{ThresholdAnchor, ThresholdError, ThresholdTrust};
+pub use crate::transparency_log::{
+    verify_log_consistency, verify_log_inclusion, LogEntry, SignedTreeHead, TreeHeadError,
+};
+pub use crate::trust::{Trust, TrustError, MAX_CONFIDENCE};
+pub use crate::trust_graph::{
+    TrustGraph, TrustGraphError, TrustedKey, WeightFactor, MAX_WEIGHT_FACTOR,
+};
 pub use crate::trust_graph_storage::{Storage, StorageError};
 pub use crate::trust_relation::{Auth, TrustRelation};
+pub use crate::weight_proof::{verify_weight_proof, WeightProof, WeightProofError};
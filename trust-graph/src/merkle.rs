@@ -0,0 +1,244 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Merkle trees as in RFC 6962, leaves and nodes are hashed with different prefixes
+
+use sha2::Digest;
+
+pub type Hash = [u8; 32];
+
+pub(crate) fn leaf_hash(data: &[u8]) -> Hash {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+pub(crate) fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// The largest power of two less than `n`, where the tree of `n` leaves is split
+fn split(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+/// Root of the tree over leaf hashes, sha256 of nothing for the empty tree
+pub(crate) fn root(leaves: &[Hash]) -> Hash {
+    match leaves.len() {
+        0 => sha2::Sha256::digest(b"").into(),
+        1 => leaves[0],
+        n => {
+            let k = split(n);
+            node_hash(&root(&leaves[..k]), &root(&leaves[k..]))
+        }
+    }
+}
+
+/// Hashes of siblings on the path from the leaf to the root, the lowest first
+pub(crate) fn inclusion_path(leaves: &[Hash], index: usize) -> Vec<Hash> {
+    let n = leaves.len();
+    if n <= 1 || index >= n {
+        return vec![];
+    }
+
+    let k = split(n);
+    if index < k {
+        let mut path = inclusion_path(&leaves[..k], index);
+        path.push(root(&leaves[k..]));
+        path
+    } else {
+        let mut path = inclusion_path(&leaves[k..], index - k);
+        path.push(root(&leaves[..k]));
+        path
+    }
+}
+
+/// Root of the tree of `size` leaves with `leaf` at `index`, None if the path doesn't fit the tree
+pub(crate) fn root_from_path(leaf: &Hash, index: u64, size: u64, path: &[Hash]) -> Option<Hash> {
+    if index >= size {
+        return None;
+    }
+
+    let (mut index, mut last) = (index, size - 1);
+    let mut hash = *leaf;
+    for sibling in path {
+        if last == 0 {
+            return None;
+        }
+
+        if index % 2 == 1 || index == last {
+            hash = node_hash(sibling, &hash);
+            // skip levels where the node has no right sibling
+            while index % 2 == 0 && index != 0 {
+                index >>= 1;
+                last >>= 1;
+            }
+        } else {
+            hash = node_hash(&hash, sibling);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+
+    (last == 0).then_some(hash)
+}
+
+/// Proof that the tree of the first `size` leaves is a prefix of the tree of all `leaves`
+pub(crate) fn consistency_path(leaves: &[Hash], size: usize) -> Vec<Hash> {
+    if size == 0 || size >= leaves.len() {
+        return vec![];
+    }
+
+    subproof(leaves, size, true)
+}
+
+fn subproof(leaves: &[Hash], m: usize, complete: bool) -> Vec<Hash> {
+    let n = leaves.len();
+    if m == n {
+        return if complete { vec![] } else { vec![root(leaves)] };
+    }
+
+    let k = split(n);
+    if m <= k {
+        let mut path = subproof(&leaves[..k], m, complete);
+        path.push(root(&leaves[k..]));
+        path
+    } else {
+        let mut path = subproof(&leaves[k..], m - k, false);
+        path.push(root(&leaves[..k]));
+        path
+    }
+}
+
+/// Check that the tree of `first` leaves with `first_root` is a prefix
+/// of the tree of `second` leaves with `second_root`
+pub(crate) fn verify_consistency(
+    first: u64,
+    second: u64,
+    first_root: &Hash,
+    second_root: &Hash,
+    path: &[Hash],
+) -> bool {
+    if first > second {
+        return false;
+    }
+    // the empty tree is a prefix of any tree
+    if first == 0 {
+        return path.is_empty();
+    }
+    if first == second {
+        return path.is_empty() && first_root == second_root;
+    }
+
+    let mut path = path.to_vec();
+    if first.is_power_of_two() {
+        path.insert(0, *first_root);
+    }
+    let (first_hash, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return false,
+    };
+
+    let (mut index, mut last) = (first - 1, second - 1);
+    while index % 2 == 1 {
+        index >>= 1;
+        last >>= 1;
+    }
+
+    let (mut first_hash, mut second_hash) = (*first_hash, *first_hash);
+    for sibling in rest {
+        if last == 0 {
+            return false;
+        }
+
+        if index % 2 == 1 || index == last {
+            first_hash = node_hash(sibling, &first_hash);
+            second_hash = node_hash(sibling, &second_hash);
+            while index % 2 == 0 && index != 0 {
+                index >>= 1;
+                last >>= 1;
+            }
+        } else {
+            second_hash = node_hash(&second_hash, sibling);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+
+    last == 0 && first_hash == *first_root && second_hash == *second_root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inclusion_paths() {
+        for n in 1..=20usize {
+            let leaves: Vec<Hash> = (0..n).map(|i| leaf_hash(&i.to_le_bytes())).collect();
+            let tree_root = root(&leaves);
+
+            for (i, leaf) in leaves.iter().enumerate() {
+                let path = inclusion_path(&leaves, i);
+                let (i, n) = (i as u64, n as u64);
+                assert_eq!(root_from_path(leaf, i, n, &path), Some(tree_root));
+                assert_ne!(
+                    root_from_path(&leaf_hash(b"other"), i, n, &path),
+                    Some(tree_root)
+                );
+                assert_eq!(root_from_path(leaf, n, n, &path), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_paths() {
+        let leaves: Vec<Hash> = (0..20usize).map(|i| leaf_hash(&i.to_le_bytes())).collect();
+
+        for n in 1..=leaves.len() {
+            let second_root = root(&leaves[..n]);
+            for m in 0..=n {
+                let first_root = root(&leaves[..m]);
+                let path = consistency_path(&leaves[..n], m);
+                let (m, n) = (m as u64, n as u64);
+                assert!(verify_consistency(m, n, &first_root, &second_root, &path));
+
+                if m > 0 && m < n {
+                    let forged = root(&leaves[1..=m as usize]);
+                    assert!(!verify_consistency(m, n, &forged, &second_root, &path));
+                    let mut tampered = path.clone();
+                    tampered[0] = leaf_hash(b"other");
+                    assert!(!verify_consistency(
+                        m,
+                        n,
+                        &first_root,
+                        &second_root,
+                        &tampered
+                    ));
+                }
+            }
+        }
+    }
+}
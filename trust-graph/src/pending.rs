@@ -0,0 +1,42 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::trust_relation::Auth;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Verified trust from an issuer without weight, waiting for the issuer to be certified
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingTrust {
+    pub auth: Auth,
+    /// when the trust was put into the pool
+    pub added_at: Duration,
+}
+
+/// Limits of the pending pool, see `TrustGraph::set_pending_pool`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingPoolConfig {
+    /// maximum number of pending trusts, the earliest added are evicted first
+    pub max_size: u32,
+    /// pending trusts are dropped after this time in the pool
+    pub ttl: Duration,
+}
+
+impl PendingTrust {
+    pub fn is_stale(&self, ttl: Duration, cur_time: Duration) -> bool {
+        self.added_at + ttl <= cur_time
+    }
+}
@@ -0,0 +1,52 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::{Display, Formatter};
+
+/// What happens to a new trust over a quota
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// the new trust is rejected with `QuotaExceeded`
+    #[default]
+    Reject,
+    /// the earliest issued trust is removed to make room for the new one
+    EvictOldest,
+}
+
+/// Limits on stored trusts, self-signed trusts are not counted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quotas {
+    /// maximum number of trusts issued by one key
+    pub max_issued: Option<u32>,
+    /// maximum number of trusts issued for one key
+    pub max_received: Option<u32>,
+    pub eviction: EvictionPolicy,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuotaKind {
+    Issued,
+    Received,
+}
+
+impl Display for QuotaKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaKind::Issued => write!(f, "issued"),
+            QuotaKind::Received => write!(f, "received"),
+        }
+    }
+}
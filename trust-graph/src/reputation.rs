@@ -0,0 +1,253 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::public_key_hashable::PublicKeyHashable as PK;
+use fluence_keypair::public_key::PublicKey;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+
+/// Parameters of the reputation computation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReputationConfig {
+    /// probability to follow a trust instead of jumping back to the roots
+    pub damping: f64,
+    /// iteration stops when the sum of score changes gets below it
+    pub tolerance: f64,
+    /// iteration stops after this many rounds even if scores still change
+    pub max_iterations: u32,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            damping: 0.85,
+            tolerance: 1e-9,
+            max_iterations: 100,
+        }
+    }
+}
+
+/// Global reputation scores, personalized PageRank over trusts seeded by the roots.
+/// Unlike weight, a key vouched for by several independent peers scores higher
+/// than a key with a single path of the same length. Scores of all keys sum up to 1.
+#[derive(Clone, Debug, Default)]
+pub struct Reputation {
+    scores: HashMap<PK, f64>,
+    iterations: u32,
+}
+
+impl Reputation {
+    /// Score of the key, 0 for keys not reachable from the roots
+    pub fn score<P>(&self, pk: P) -> f64
+    where
+        P: Borrow<PublicKey>,
+    {
+        self.scores
+            .get(pk.borrow().as_ref())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn scores(&self) -> &HashMap<PK, f64> {
+        &self.scores
+    }
+
+    /// Number of iterations it took to compute the scores
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+}
+
+/// Run power iteration over `edges` given as (issued_by, issued_for, local trust).
+/// Outgoing trusts of every key are normalized, so a key can't raise its influence
+/// by issuing more trusts. Score of keys without outgoing trusts returns to `seeds`,
+/// which are weighted by their values.
+/// If `previous` is given, iteration starts from it, which takes a few rounds
+/// when the graph has changed a little since then.
+pub(crate) fn compute(
+    edges: &[(PK, PK, f64)],
+    seeds: &[(PK, f64)],
+    config: &ReputationConfig,
+    previous: Option<&Reputation>,
+) -> Reputation {
+    let mut index: HashMap<&PK, usize> = HashMap::new();
+    let mut keys: Vec<&PK> = vec![];
+
+    let mut personalization: Vec<(usize, f64)> = vec![];
+    for (pk, weight) in seeds.iter().filter(|(_, w)| *w > 0.0) {
+        personalization.push((pk_index(&mut index, &mut keys, pk), *weight));
+    }
+    let seeds_total: f64 = personalization.iter().map(|(_, w)| w).sum();
+    if seeds_total <= 0.0 {
+        return Reputation::default();
+    }
+
+    let mut outgoing: Vec<Vec<(usize, f64)>> = vec![];
+    for (issued_by, issued_for, weight) in edges {
+        // self-signed trusts only mark the key as existing
+        if issued_by == issued_for || *weight <= 0.0 {
+            continue;
+        }
+
+        let from = pk_index(&mut index, &mut keys, issued_by);
+        let to = pk_index(&mut index, &mut keys, issued_for);
+        if outgoing.len() <= from {
+            outgoing.resize(from + 1, vec![]);
+        }
+        outgoing[from].push((to, *weight));
+    }
+
+    let n = keys.len();
+    outgoing.resize(n, vec![]);
+    for out in outgoing.iter_mut() {
+        let total: f64 = out.iter().map(|(_, w)| w).sum();
+        out.iter_mut().for_each(|(_, w)| *w /= total);
+    }
+
+    let mut base = vec![0f64; n];
+    for (i, weight) in personalization {
+        base[i] += weight / seeds_total;
+    }
+
+    let mut scores = vec![0f64; n];
+    if let Some(previous) = previous {
+        for (i, pk) in keys.iter().enumerate() {
+            scores[i] = previous.scores.get(*pk).copied().unwrap_or_default();
+        }
+    }
+    let total: f64 = scores.iter().sum();
+    if total > 0.0 {
+        scores.iter_mut().for_each(|s| *s /= total);
+    } else {
+        scores.clone_from(&base);
+    }
+
+    let mut iterations = 0;
+    while iterations < config.max_iterations {
+        iterations += 1;
+
+        let mut next = vec![0f64; n];
+        let mut dangling = 0f64;
+        for (i, out) in outgoing.iter().enumerate() {
+            if out.is_empty() {
+                dangling += scores[i];
+            }
+            for (j, w) in out {
+                next[*j] += config.damping * scores[i] * w;
+            }
+        }
+
+        let restart = 1.0 - config.damping + config.damping * dangling;
+        for (s, b) in next.iter_mut().zip(base.iter()) {
+            *s += restart * b;
+        }
+
+        let delta: f64 = next
+            .iter()
+            .zip(scores.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum();
+        scores = next;
+        if delta < config.tolerance {
+            break;
+        }
+    }
+
+    Reputation {
+        scores: keys
+            .into_iter()
+            .zip(scores)
+            .filter(|(_, s)| *s > 0.0)
+            .map(|(pk, s)| (pk.clone(), s))
+            .collect(),
+        iterations,
+    }
+}
+
+fn pk_index<'a>(index: &mut HashMap<&'a PK, usize>, keys: &mut Vec<&'a PK>, pk: &'a PK) -> usize {
+    *index.entry(pk).or_insert_with(|| {
+        keys.push(pk);
+        keys.len() - 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluence_keypair::key_pair::KeyPair;
+
+    fn key() -> PK {
+        KeyPair::generate_ed25519().public().into()
+    }
+
+    #[test]
+    fn test_independent_vouchers() {
+        let root = key();
+        let (a, b) = (key(), key());
+        let (x, y) = (key(), key());
+        let unknown = key();
+
+        let edges = vec![
+            (root.clone(), root.clone(), 1.0),
+            (root.clone(), a.clone(), 1.0),
+            (root.clone(), b.clone(), 1.0),
+            (a.clone(), x.clone(), 1.0),
+            (b.clone(), x.clone(), 1.0),
+            (a.clone(), y.clone(), 1.0),
+            (unknown.clone(), x.clone(), 1.0),
+        ];
+
+        let reputation = compute(
+            &edges,
+            &[(root.clone(), 1.0)],
+            &ReputationConfig::default(),
+            None,
+        );
+
+        let score = |pk: &PK| reputation.score(pk.as_ref());
+        assert!(score(&x) > score(&y));
+        assert!(score(&root) > score(&a));
+        assert_eq!(score(&unknown), 0.0);
+
+        let total: f64 = reputation.scores().values().sum();
+        assert!((total - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_warm_start() {
+        let root = key();
+        let keys: Vec<PK> = (0..20).map(|_| key()).collect();
+
+        let mut edges = vec![(root.clone(), keys[0].clone(), 1.0)];
+        for pair in keys.windows(2) {
+            edges.push((pair[0].clone(), pair[1].clone(), 1.0));
+            edges.push((pair[1].clone(), pair[0].clone(), 0.5));
+        }
+
+        let config = ReputationConfig::default();
+        let seeds = [(root.clone(), 1.0)];
+        let previous = compute(&edges, &seeds, &config, None);
+
+        edges.push((keys[5].clone(), keys[15].clone(), 0.1));
+        let cold = compute(&edges, &seeds, &config, None);
+        let warm = compute(&edges, &seeds, &config, Some(&previous));
+
+        assert!(warm.iterations() < cold.iterations());
+        for (pk, score) in cold.scores() {
+            assert!((warm.score(pk.as_ref()) - score).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::revocation_list::RevocationListError::{
+    Expired, ForeignRevocation, IncorrectSignature, IssuedInFuture, RevocationCheckError,
+};
+use crate::revoke::{Revocation, RevokeError};
+use crate::signing::{push_prefixed, v2_signature_bytes, PayloadType};
+use fluence_keypair::key_pair::KeyPair;
+use fluence_keypair::public_key::PublicKey;
+use fluence_keypair::signature::Signature;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum RevocationListError {
+    #[error("Revocation list is expired at: '{0:?}', current time: '{1:?}'")]
+    Expired(Duration, Duration),
+    #[error("Revocation list is issued in the future at: '{0:?}', current time: '{1:?}'")]
+    IssuedInFuture(Duration, Duration),
+    #[error("Revocation of {0} is issued by {1}, not by the list issuer {2}")]
+    ForeignRevocation(String, String, String),
+    #[error("Revocation {0} in the list did not pass verification: {1}")]
+    RevocationCheckError(usize, #[source] RevokeError),
+    #[error("Revocation list signature is incorrect: {0}")]
+    IncorrectSignature(
+        #[from]
+        #[source]
+        fluence_keypair::error::VerificationError,
+    ),
+}
+
+/// Full revocation state of one issuer, signed as a whole.
+/// Every revocation in the list is signed by the same issuer on its own,
+/// so it can be stored as a regular revocation after import.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevocationList {
+    /// the issuer of the list and of every revocation in it
+    pub issued_by: PublicKey,
+    /// lists with a lower or equal sequence number are replaced by this one
+    pub sequence: u64,
+    /// start of the validity window
+    pub issued_at: Duration,
+    /// end of the validity window
+    pub expires_at: Duration,
+    pub revocations: Vec<Revocation>,
+    /// proof of this list
+    pub signature: Signature,
+}
+
+impl RevocationList {
+    pub fn new(
+        issued_by: PublicKey,
+        sequence: u64,
+        issued_at: Duration,
+        expires_at: Duration,
+        revocations: Vec<Revocation>,
+        signature: Signature,
+    ) -> Self {
+        Self {
+            issued_by,
+            sequence,
+            issued_at,
+            expires_at,
+            revocations,
+            signature,
+        }
+    }
+
+    /// Creates new revocation list signed by an issuer.
+    pub fn create(
+        issuer: &KeyPair,
+        sequence: u64,
+        issued_at: Duration,
+        expires_at: Duration,
+        revocations: Vec<Revocation>,
+    ) -> Self {
+        let msg = Self::signature_bytes(sequence, issued_at, expires_at, &revocations);
+        let signature = issuer.sign(&msg).unwrap();
+
+        Self::new(
+            issuer.public(),
+            sequence,
+            issued_at,
+            expires_at,
+            revocations,
+            signature,
+        )
+    }
+
+    pub fn signature_bytes(
+        sequence: u64,
+        issued_at: Duration,
+        expires_at: Duration,
+        revocations: &[Revocation],
+    ) -> Vec<u8> {
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&sequence.to_le_bytes());
+        fields.extend_from_slice(&issued_at.as_secs().to_le_bytes());
+        fields.extend_from_slice(&expires_at.as_secs().to_le_bytes());
+        fields.extend_from_slice(&(revocations.len() as u64).to_le_bytes());
+
+        for revocation in revocations {
+            push_prefixed(&mut fields, &revocation.pk.encode());
+            fields.extend_from_slice(&revocation.revoked_at.as_secs().to_le_bytes());
+        }
+
+        v2_signature_bytes(PayloadType::RevocationList, &fields)
+    }
+
+    /// Verifies the list signature, its validity window and every revocation in it.
+    pub fn verify(list: &RevocationList, cur_time: Duration) -> Result<(), RevocationListError> {
+        if list.expires_at < cur_time {
+            return Err(Expired(list.expires_at, cur_time));
+        }
+
+        if list.issued_at > cur_time {
+            return Err(IssuedInFuture(list.issued_at, cur_time));
+        }
+
+        for (i, revocation) in list.revocations.iter().enumerate() {
+            if revocation.revoked_by != list.issued_by {
+                return Err(ForeignRevocation(
+                    revocation.pk.to_peer_id().to_base58(),
+                    revocation.revoked_by.to_peer_id().to_base58(),
+                    list.issued_by.to_peer_id().to_base58(),
+                ));
+            }
+
+            Revocation::verify(revocation).map_err(|e| RevocationCheckError(i, e))?;
+        }
+
+        let msg = Self::signature_bytes(
+            list.sequence,
+            list.issued_at,
+            list.expires_at,
+            &list.revocations,
+        );
+
+        list.issued_by
+            .verify(msg.as_slice(), &list.signature)
+            .map_err(IncorrectSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_memory_storage::InMemoryStorage;
+    use crate::trust_graph::{TrustGraph, TrustGraphError};
+    use crate::trust_graph_storage::Storage;
+
+    fn revocations(revoker: &KeyPair, count: usize, revoked_at: Duration) -> Vec<Revocation> {
+        (0..count)
+            .map(|_| {
+                let to_revoke = KeyPair::generate_ed25519();
+                Revocation::create(revoker, to_revoke.public(), revoked_at)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gen_revocation_list_and_validate_ed25519() {
+        let issuer = KeyPair::generate_ed25519();
+        let issued_at = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+
+        let list = RevocationList::create(
+            &issuer,
+            1,
+            issued_at,
+            expires_at,
+            revocations(&issuer, 3, issued_at),
+        );
+
+        assert!(RevocationList::verify(&list, issued_at).is_ok());
+        assert!(RevocationList::verify(&list, expires_at + Duration::new(1, 0)).is_err());
+        assert!(matches!(
+            RevocationList::verify(&list, issued_at - Duration::new(1, 0)),
+            Err(IssuedInFuture(..))
+        ));
+    }
+
+    #[test]
+    fn test_validate_corrupted_revocation_list_ed25519() {
+        let issuer = KeyPair::generate_ed25519();
+        let issued_at = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+
+        let mut list = RevocationList::create(
+            &issuer,
+            1,
+            issued_at,
+            expires_at,
+            revocations(&issuer, 3, issued_at),
+        );
+        list.revocations.pop();
+
+        assert!(RevocationList::verify(&list, issued_at).is_err());
+    }
+
+    #[test]
+    fn test_foreign_revocation_in_list_ed25519() {
+        let issuer = KeyPair::generate_ed25519();
+        let other = KeyPair::generate_ed25519();
+        let issued_at = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+
+        let list = RevocationList::create(
+            &issuer,
+            1,
+            issued_at,
+            expires_at,
+            revocations(&other, 1, issued_at),
+        );
+
+        assert!(matches!(
+            RevocationList::verify(&list, issued_at),
+            Err(ForeignRevocation(..))
+        ));
+    }
+
+    #[test]
+    fn test_import_revocation_list() {
+        let issuer = KeyPair::generate_ed25519();
+        let issued_at = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+        let listed = revocations(&issuer, 2, issued_at);
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+
+        let list = RevocationList::create(&issuer, 1, issued_at, expires_at, listed.clone());
+        graph.import_revocation_list(list, issued_at).unwrap();
+
+        // lists are additive, a revocation left out of a newer list is kept
+        let list = RevocationList::create(&issuer, 2, issued_at, expires_at, listed[1..].to_vec());
+        graph
+            .import_revocation_list(list.clone(), issued_at)
+            .unwrap();
+        for revocation in listed.iter() {
+            assert_eq!(graph.get_revocations(&revocation.pk).unwrap().len(), 1);
+        }
+        assert!(matches!(
+            graph.import_revocation_list(list, issued_at),
+            Err(TrustGraphError::OutdatedRevocationList(2, 2))
+        ));
+
+        // a failed import discards its writes by rolling back the storage transaction
+        let mut storage = graph.storage().clone();
+        let other = revocations(&issuer, 1, issued_at).remove(0);
+        storage.begin_transaction().unwrap();
+        storage.revoke(other.clone()).unwrap();
+        storage.rollback_transaction().unwrap();
+        assert!(storage
+            .get_revocations(other.pk.as_ref())
+            .unwrap()
+            .is_empty());
+    }
+}
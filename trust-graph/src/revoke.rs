@@ -15,6 +15,9 @@
  */
 
 use crate::revoke::RevokeError::IncorrectSignature;
+use crate::signing::{
+    push_prefixed, v2_signature_bytes, v3_signature_bytes, PayloadType, SigningVersion,
+};
 use fluence_keypair::key_pair::KeyPair;
 use fluence_keypair::public_key::PublicKey;
 use fluence_keypair::signature::Signature;
@@ -45,31 +48,65 @@ pub struct Revocation {
     pub revoked_by: PublicKey,
     /// proof of this revocation
     pub signature: Signature,
+    /// Format of the payload the signature is made over
+    #[serde(default)]
+    pub version: SigningVersion,
 }
 
 impl Revocation {
+    /// Creates a revocation with a signature over v1 payload.
     pub fn new(
         revoked_by: PublicKey,
         pk: PublicKey,
         revoked_at: Duration,
         signature: Signature,
+    ) -> Self {
+        Self::new_with_version(SigningVersion::V1, revoked_by, pk, revoked_at, signature)
+    }
+
+    pub fn new_with_version(
+        version: SigningVersion,
+        revoked_by: PublicKey,
+        pk: PublicKey,
+        revoked_at: Duration,
+        signature: Signature,
     ) -> Self {
         Self {
             pk,
             revoked_at,
             revoked_by,
             signature,
+            version,
         }
     }
 
     /// Creates new revocation signed by a revoker.
     pub fn create(revoker: &KeyPair, to_revoke: PublicKey, revoked_at: Duration) -> Self {
-        let msg = Revocation::signature_bytes(&to_revoke, revoked_at);
+        let msg = Revocation::signature_bytes_v2(&to_revoke, revoked_at);
         let signature = revoker.sign(&msg).unwrap();
 
-        Revocation::new(revoker.public(), to_revoke, revoked_at, signature)
+        Revocation::new_with_version(
+            SigningVersion::V2,
+            revoker.public(),
+            to_revoke,
+            revoked_at,
+            signature,
+        )
     }
 
+    pub fn versioned_signature_bytes(
+        version: SigningVersion,
+        pk: &PublicKey,
+        revoked_at: Duration,
+    ) -> Vec<u8> {
+        match version {
+            SigningVersion::V1 => Revocation::signature_bytes(pk, revoked_at),
+            SigningVersion::V2 => Revocation::signature_bytes_v2(pk, revoked_at),
+            SigningVersion::V3 => Revocation::signature_bytes_v3(pk, revoked_at),
+        }
+    }
+
+    /// Legacy v1 payload, kept to verify revocations signed before v2
     pub fn signature_bytes(pk: &PublicKey, revoked_at: Duration) -> Vec<u8> {
         let mut metadata = Vec::new();
         let pk_bytes = &pk.encode();
@@ -80,9 +117,27 @@ impl Revocation {
         sha2::Sha256::digest(&metadata).to_vec()
     }
 
+    pub fn signature_bytes_v2(pk: &PublicKey, revoked_at: Duration) -> Vec<u8> {
+        let mut fields = Vec::new();
+        push_prefixed(&mut fields, &pk.encode());
+        fields.extend_from_slice(&revoked_at.as_secs().to_le_bytes());
+
+        v2_signature_bytes(PayloadType::Revocation, &fields)
+    }
+
+    /// Revocations carry no confidence, so v3 payload differs from v2 only in the version byte
+    pub fn signature_bytes_v3(pk: &PublicKey, revoked_at: Duration) -> Vec<u8> {
+        let mut fields = Vec::new();
+        push_prefixed(&mut fields, &pk.encode());
+        fields.extend_from_slice(&revoked_at.as_secs().to_le_bytes());
+
+        v3_signature_bytes(PayloadType::Revocation, &fields)
+    }
+
     /// Verifies that revocation is cryptographically correct.
     pub fn verify(revoke: &Revocation) -> Result<(), RevokeError> {
-        let msg = Revocation::signature_bytes(&revoke.pk, revoke.revoked_at);
+        let msg =
+            Revocation::versioned_signature_bytes(revoke.version, &revoke.pk, revoke.revoked_at);
 
         revoke
             .revoked_by
@@ -126,4 +181,18 @@ mod tests {
 
         assert_eq!(Revocation::verify(&corrupted_revoke).is_ok(), false);
     }
+
+    #[test]
+    fn test_validate_v1_revoke_ed25519() {
+        let revoker = KeyPair::generate_ed25519();
+        let to_revoke = KeyPair::generate_ed25519();
+
+        let duration = Duration::new(100, 0);
+
+        let msg = Revocation::signature_bytes(&to_revoke.public(), duration);
+        let signature = revoker.sign(&msg).unwrap();
+        let revoke = Revocation::new(revoker.public(), to_revoke.public(), duration, signature);
+
+        assert!(Revocation::verify(&revoke).is_ok());
+    }
 }
@@ -0,0 +1,139 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+/// Prefix of every v2 payload, so trust graph signatures can't be replayed in other applications.
+pub const DOMAIN_TAG: &[u8] = b"fluence-trust-graph";
+
+/// Format of the payload a signature is made over.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum SigningVersion {
+    /// sha256 of raw fields without any context
+    #[default]
+    V1,
+    /// sha256 of the domain tag, the format version, the payload type and the fields
+    V2,
+    /// same as v2, trusts additionally sign their confidence
+    V3,
+}
+
+impl SigningVersion {
+    pub fn to_u8(self) -> u8 {
+        match self {
+            SigningVersion::V1 => 1,
+            SigningVersion::V2 => 2,
+            SigningVersion::V3 => 3,
+        }
+    }
+
+    pub fn from_u8(version: u8) -> Option<Self> {
+        match version {
+            1 => Some(SigningVersion::V1),
+            2 => Some(SigningVersion::V2),
+            3 => Some(SigningVersion::V3),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of signed structure, part of a v2 payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadType {
+    Trust = 1,
+    Revocation = 2,
+    RevocationList = 3,
+    KeyCompromise = 4,
+    ThresholdTrust = 5,
+    Distrust = 6,
+    WeightAttestation = 7,
+    TreeHead = 8,
+}
+
+/// Builds v2 payload from already encoded fields of a structure.
+pub(crate) fn v2_signature_bytes(payload_type: PayloadType, fields: &[u8]) -> Vec<u8> {
+    domain_separated_bytes(SigningVersion::V2, payload_type, fields)
+}
+
+/// Builds v3 payload from already encoded fields of a structure.
+pub(crate) fn v3_signature_bytes(payload_type: PayloadType, fields: &[u8]) -> Vec<u8> {
+    domain_separated_bytes(SigningVersion::V3, payload_type, fields)
+}
+
+/// Length prefix of a variable-size field in v2 payloads and encodings.
+/// 2 bytes, so RSA keys and signatures fit
+pub(crate) fn length_prefix(field: &[u8]) -> [u8; 2] {
+    u16::try_from(field.len())
+        .expect("keys and signatures are shorter than u16::MAX")
+        .to_le_bytes()
+}
+
+/// Appends a field prefixed by its length
+pub(crate) fn push_prefixed(fields: &mut Vec<u8>, field: &[u8]) {
+    fields.extend_from_slice(&length_prefix(field));
+    fields.extend_from_slice(field);
+}
+
+/// Reads a little-endian length prefix, 1 byte in legacy encodings and 2 bytes since
+pub(crate) fn prefixed_len(prefix: &[u8]) -> usize {
+    prefix
+        .iter()
+        .rev()
+        .fold(0, |len, byte| (len << 8) | *byte as usize)
+}
+
+fn domain_separated_bytes(
+    version: SigningVersion,
+    payload_type: PayloadType,
+    fields: &[u8],
+) -> Vec<u8> {
+    let mut metadata = Vec::with_capacity(DOMAIN_TAG.len() + 2 + fields.len());
+    metadata.extend_from_slice(DOMAIN_TAG);
+    metadata.push(version.to_u8());
+    metadata.push(payload_type as u8);
+    metadata.extend_from_slice(fields);
+
+    sha2::Sha256::digest(&metadata).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_version_to_from_u8() {
+        for version in [SigningVersion::V1, SigningVersion::V2, SigningVersion::V3] {
+            assert_eq!(SigningVersion::from_u8(version.to_u8()), Some(version));
+        }
+        assert_eq!(SigningVersion::from_u8(0), None);
+    }
+
+    #[test]
+    fn test_payload_type_separates_payloads() {
+        let fields = [1u8, 2, 3];
+        assert_ne!(
+            v2_signature_bytes(PayloadType::Trust, &fields),
+            v2_signature_bytes(PayloadType::Revocation, &fields)
+        );
+        assert_ne!(
+            v2_signature_bytes(PayloadType::Trust, &fields),
+            v3_signature_bytes(PayloadType::Trust, &fields)
+        );
+    }
+}
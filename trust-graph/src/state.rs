@@ -0,0 +1,655 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::merkle::{inclusion_path, leaf_hash, root, root_from_path, Hash};
+use crate::public_key_hashable::PublicKeyHashable as PK;
+use crate::signing::{prefixed_len, push_prefixed};
+use crate::trust_graph::WeightFactor;
+use crate::trust_graph_storage::Storage;
+use crate::trust_relation::TrustRelation;
+use fluence_keypair::public_key::PublicKey;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::time::Duration;
+
+/// Number of buckets the state tree splits leaves into by their keys
+const STATE_BUCKETS: usize = 256;
+
+/// Proof that a relation is a leaf of the state tree with a given root.
+/// Leaves are split into buckets, so the proof consists of the path
+/// to the bucket root and the path from the bucket root to the state root.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateProof {
+    /// position of the leaf in its bucket
+    pub index: u64,
+    /// number of leaves in the bucket
+    pub size: u64,
+    pub path: Vec<Hash>,
+    pub bucket_path: Vec<Hash>,
+}
+
+impl StateProof {
+    /// Check that `relation` is included into the state with `state_root`
+    pub fn verify(&self, relation: &TrustRelation, state_root: &Hash) -> bool {
+        let key = relation_key(
+            relation.issued_for().as_ref(),
+            relation.issued_by().as_ref(),
+        );
+        self.verify_hash(&key, &relation_leaf(relation), state_root)
+    }
+
+    /// Check that `leaf` is included into the state with `state_root`
+    pub fn verify_leaf(&self, leaf: &StateLeaf, state_root: &Hash) -> bool {
+        self.verify_hash(&leaf.key(), &leaf.hash(), state_root)
+    }
+
+    fn verify_hash(&self, key: &[u8], leaf: &Hash, state_root: &Hash) -> bool {
+        let bucket_root = root_from_path(leaf, self.index, self.size, &self.path);
+
+        bucket_root
+            .and_then(|r| {
+                root_from_path(
+                    &r,
+                    bucket(key) as u64,
+                    STATE_BUCKETS as u64,
+                    &self.bucket_path,
+                )
+            })
+            .is_some_and(|r| r == *state_root)
+    }
+}
+
+/// Leaf of the state tree along with the data it's hashed from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StateLeaf {
+    Root(PublicKey, WeightFactor),
+    Relation(Box<TrustRelation>),
+}
+
+impl StateLeaf {
+    fn key(&self) -> Vec<u8> {
+        match self {
+            StateLeaf::Root(pk, _) => root_key(pk.as_ref()),
+            StateLeaf::Relation(relation) => relation_key(
+                relation.issued_for().as_ref(),
+                relation.issued_by().as_ref(),
+            ),
+        }
+    }
+
+    fn hash(&self) -> Hash {
+        match self {
+            StateLeaf::Root(pk, weight_factor) => root_leaf(pk.as_ref(), *weight_factor),
+            StateLeaf::Relation(relation) => relation_leaf(relation),
+        }
+    }
+}
+
+/// Proof that the state has no revocation of one key by another
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum NonRevocationProof {
+    /// the relation between the keys is not a revocation
+    Relation(TrustRelation, StateProof),
+    /// there is no relation between the keys: leaves around its place in the bucket,
+    /// None at the edges of the bucket, and the path of the bucket for an empty one
+    Absent {
+        prev: Option<(StateLeaf, StateProof)>,
+        next: Option<(StateLeaf, StateProof)>,
+        bucket_path: Vec<Hash>,
+    },
+}
+
+impl NonRevocationProof {
+    /// Check that the state with `state_root` has no revocation of `issued_for` by `issued_by`
+    pub fn verify(&self, issued_for: &PublicKey, issued_by: &PublicKey, state_root: &Hash) -> bool {
+        let key = relation_key(issued_for.as_ref(), issued_by.as_ref());
+        match self {
+            NonRevocationProof::Relation(relation, proof) => {
+                !matches!(relation, TrustRelation::Revocation(_))
+                    && relation.issued_for() == issued_for
+                    && relation.issued_by() == issued_by
+                    && proof.verify(relation, state_root)
+            }
+            NonRevocationProof::Absent {
+                prev,
+                next,
+                bucket_path,
+            } => {
+                let included = |(leaf, proof): &(StateLeaf, StateProof)| {
+                    bucket(&leaf.key()) == bucket(&key) && proof.verify_leaf(leaf, state_root)
+                };
+                if prev
+                    .iter()
+                    .any(|prev| !included(prev) || prev.0.key() >= key)
+                    || next
+                        .iter()
+                        .any(|next| !included(next) || next.0.key() <= key)
+                {
+                    return false;
+                }
+
+                // the leaves are neighbours, so there is nothing between them
+                match (prev, next) {
+                    (Some((_, prev)), Some((_, next))) => {
+                        next.index == prev.index + 1 && next.size == prev.size
+                    }
+                    (None, Some((_, next))) => next.index == 0,
+                    (Some((_, prev)), None) => prev.index + 1 == prev.size,
+                    (None, None) => {
+                        root_from_path(
+                            &root(&[]),
+                            bucket(&key) as u64,
+                            STATE_BUCKETS as u64,
+                            bucket_path,
+                        ) == Some(*state_root)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Keys a leaf of the state tree is made for
+pub(crate) enum LeafId {
+    Root(PK),
+    Relation { issued_for: PK, issued_by: PK },
+}
+
+impl LeafId {
+    fn parse(key: &[u8]) -> Option<Self> {
+        let decode = |bytes: &[u8]| PublicKey::decode(bytes).ok().map(PK::from);
+        match key.split_first()? {
+            (0, pk) => Some(LeafId::Root(decode(pk)?)),
+            (1, rest) => {
+                let (issued_for, rest) = split_prefixed(rest)?;
+                let (issued_by, rest) = split_prefixed(rest)?;
+                if !rest.is_empty() {
+                    return None;
+                }
+                Some(LeafId::Relation {
+                    issued_for: decode(issued_for)?,
+                    issued_by: decode(issued_by)?,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Splits a field prefixed by its length off the bytes
+fn split_prefixed(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let len = prefixed_len(bytes.get(..2)?);
+    let field = bytes.get(2..2 + len)?;
+    Some((field, &bytes[2 + len..]))
+}
+
+/// Leaves around the place of a missing relation, see `NonRevocationProof::Absent`
+pub(crate) struct AbsenceProof {
+    pub(crate) prev: Option<(LeafId, StateProof)>,
+    pub(crate) next: Option<(LeafId, StateProof)>,
+    pub(crate) bucket_path: Vec<Hash>,
+}
+
+fn root_key(pk: &PK) -> Vec<u8> {
+    let mut key = vec![0u8];
+    key.extend(pk.as_ref().encode());
+    key
+}
+
+fn relation_key(issued_for: &PK, issued_by: &PK) -> Vec<u8> {
+    let mut key = vec![1u8];
+    for pk in [issued_for, issued_by] {
+        push_prefixed(&mut key, &pk.as_ref().encode());
+    }
+    key
+}
+
+fn bucket(key: &[u8]) -> u8 {
+    sha2::Sha256::digest(key)[0]
+}
+
+fn root_leaf(pk: &PK, weight_factor: WeightFactor) -> Hash {
+    let mut data = root_key(pk);
+    data.extend(weight_factor.to_le_bytes());
+    leaf_hash(&data)
+}
+
+fn relation_leaf(relation: &TrustRelation) -> Hash {
+    let mut data = vec![1u8];
+    data.extend(relation.digest());
+    leaf_hash(&data)
+}
+
+/// Store the tree over the graph contents, bucket roots are stored last,
+/// so the tree is considered stored only when it's complete.
+/// Then it's updated along with the graph.
+pub(crate) fn build<S: Storage>(
+    storage: &mut S,
+    roots: &[(PK, WeightFactor)],
+    relations: &[TrustRelation],
+) -> Result<(), S::Error> {
+    for (pk, weight_factor) in roots {
+        let key = root_key(pk);
+        storage.insert_state_leaf(bucket(&key), key, root_leaf(pk, *weight_factor), None)?;
+    }
+    for relation in relations {
+        insert_relation(storage, relation)?;
+    }
+    for b in 0..STATE_BUCKETS {
+        rehash(storage, b as u8)?;
+    }
+
+    Ok(())
+}
+
+/// Whether the tree is stored, see `build`
+pub(crate) fn is_stored<S: Storage>(storage: &S) -> Result<bool, S::Error> {
+    Ok(storage.get_state_buckets()?.len() == STATE_BUCKETS)
+}
+
+pub(crate) fn state_root<S: Storage>(storage: &S) -> Result<Hash, S::Error> {
+    Ok(root(&storage.get_state_buckets()?))
+}
+
+pub(crate) fn set_root<S: Storage>(
+    storage: &mut S,
+    pk: &PK,
+    weight_factor: WeightFactor,
+) -> Result<(), S::Error> {
+    let key = root_key(pk);
+    let b = bucket(&key);
+    storage.insert_state_leaf(b, key, root_leaf(pk, weight_factor), None)?;
+    rehash(storage, b)
+}
+
+/// Replace the leaf of the relation between the keys, None removes it
+pub(crate) fn set_relation<S: Storage>(
+    storage: &mut S,
+    issued_for: &PK,
+    issued_by: &PK,
+    relation: Option<&TrustRelation>,
+) -> Result<(), S::Error> {
+    let key = relation_key(issued_for, issued_by);
+    let b = bucket(&key);
+    match relation {
+        Some(relation) => insert_relation(storage, relation)?,
+        None => storage.remove_state_leaf(&key)?,
+    }
+    rehash(storage, b)
+}
+
+/// Drop leaves of auths that expire by `cur_time`
+pub(crate) fn remove_expired<S: Storage>(
+    storage: &mut S,
+    cur_time: Duration,
+) -> Result<(), S::Error> {
+    for b in storage.remove_expired_state_leaves(cur_time)? {
+        rehash(storage, b)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn prove<S: Storage>(
+    storage: &S,
+    relation: &TrustRelation,
+) -> Result<Option<StateProof>, S::Error> {
+    let key = relation_key(
+        relation.issued_for().as_ref(),
+        relation.issued_by().as_ref(),
+    );
+    let b = bucket(&key);
+    let leaves = storage.get_state_leaves(b)?;
+    let index = match leaves.binary_search_by(|(k, _)| k.cmp(&key)) {
+        Ok(index) if leaves[index].1 == relation_leaf(relation) => index,
+        _ => return Ok(None),
+    };
+
+    let hashes: Vec<Hash> = leaves.iter().map(|(_, leaf)| *leaf).collect();
+    Ok(Some(StateProof {
+        index: index as u64,
+        size: leaves.len() as u64,
+        path: inclusion_path(&hashes, index),
+        bucket_path: inclusion_path(&storage.get_state_buckets()?, b as usize),
+    }))
+}
+
+/// Neighbours of the relation between the keys in its bucket, None if there is the relation
+pub(crate) fn prove_absence<S: Storage>(
+    storage: &S,
+    issued_for: &PK,
+    issued_by: &PK,
+) -> Result<Option<AbsenceProof>, S::Error> {
+    let key = relation_key(issued_for, issued_by);
+    let b = bucket(&key);
+    let leaves = storage.get_state_leaves(b)?;
+    let index = match leaves.binary_search_by(|(k, _)| k.cmp(&key)) {
+        Ok(_) => return Ok(None),
+        Err(index) => index,
+    };
+
+    let hashes: Vec<Hash> = leaves.iter().map(|(_, leaf)| *leaf).collect();
+    let bucket_path = inclusion_path(&storage.get_state_buckets()?, b as usize);
+    let neighbour = |index: usize| {
+        let (key, _) = leaves.get(index)?;
+        let proof = StateProof {
+            index: index as u64,
+            size: leaves.len() as u64,
+            path: inclusion_path(&hashes, index),
+            bucket_path: bucket_path.clone(),
+        };
+        Some((LeafId::parse(key)?, proof))
+    };
+
+    Ok(Some(AbsenceProof {
+        prev: index.checked_sub(1).and_then(neighbour),
+        next: neighbour(index),
+        bucket_path,
+    }))
+}
+
+fn insert_relation<S: Storage>(storage: &mut S, relation: &TrustRelation) -> Result<(), S::Error> {
+    let key = relation_key(
+        relation.issued_for().as_ref(),
+        relation.issued_by().as_ref(),
+    );
+    let expires_at = match relation {
+        TrustRelation::Auth(auth) => Some(auth.trust.expires_at),
+        _ => None,
+    };
+    storage.insert_state_leaf(bucket(&key), key, relation_leaf(relation), expires_at)
+}
+
+fn rehash<S: Storage>(storage: &mut S, b: u8) -> Result<(), S::Error> {
+    let hashes: Vec<Hash> = storage
+        .get_state_leaves(b)?
+        .into_iter()
+        .map(|(_, leaf)| leaf)
+        .collect();
+    storage.set_state_bucket(b, root(&hashes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_memory_storage::InMemoryStorage;
+    use crate::revoke::Revocation;
+    use crate::trust::Trust;
+    use crate::trust_graph::TrustGraph;
+    use crate::trust_relation::Auth;
+    use fluence_keypair::key_pair::KeyPair;
+
+    fn auth(issuer: &KeyPair, issued_for: &KeyPair, expires_at: u64) -> TrustRelation {
+        TrustRelation::Auth(Auth {
+            trust: Trust::create(
+                issuer,
+                issued_for.public(),
+                Duration::from_secs(expires_at),
+                Duration::from_secs(1),
+            ),
+            issued_by: issuer.public(),
+        })
+    }
+
+    fn keys(relation: &TrustRelation) -> (PK, PK) {
+        (
+            relation.issued_for().clone().into(),
+            relation.issued_by().clone().into(),
+        )
+    }
+
+    fn built_root(roots: &[(PK, WeightFactor)], relations: &[TrustRelation]) -> Hash {
+        let mut storage = InMemoryStorage::default();
+        build(&mut storage, roots, relations).unwrap();
+        state_root(&storage).unwrap()
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let kps: Vec<KeyPair> = (0..6).map(|_| KeyPair::generate_ed25519()).collect();
+        let roots = vec![(PK::from(kps[0].public()), 2)];
+        let relations: Vec<TrustRelation> = kps
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| auth(&pair[0], &pair[1], 100 + i as u64))
+            .collect();
+
+        let mut storage = InMemoryStorage::default();
+        build(&mut storage, &roots, &relations[..2]).unwrap();
+        for relation in relations[2..].iter() {
+            let (issued_for, issued_by) = keys(relation);
+            set_relation(&mut storage, &issued_for, &issued_by, Some(relation)).unwrap();
+        }
+        assert_eq!(
+            state_root(&storage).unwrap(),
+            built_root(&roots, &relations)
+        );
+
+        let revocation = TrustRelation::Revocation(Revocation::create(
+            &kps[0],
+            kps[1].public(),
+            Duration::from_secs(2),
+        ));
+        let (issued_for, issued_by) = keys(&revocation);
+        set_relation(&mut storage, &issued_for, &issued_by, Some(&revocation)).unwrap();
+        remove_expired(&mut storage, Duration::from_secs(102)).unwrap();
+
+        let mut remaining = vec![revocation];
+        remaining.extend_from_slice(&relations[3..]);
+        assert_eq!(
+            state_root(&storage).unwrap(),
+            built_root(&roots, &remaining)
+        );
+
+        set_root(&mut storage, &PK::from(kps[1].public()), 4).unwrap();
+        assert_ne!(
+            state_root(&storage).unwrap(),
+            built_root(&roots, &remaining)
+        );
+    }
+
+    #[test]
+    fn test_parse_leaf_id() {
+        let root_pk = PK::from(KeyPair::generate_ed25519().public());
+        let issued_for = PK::from(KeyPair::generate_secp256k1().public());
+        let issued_by = PK::from(KeyPair::generate_p256().public());
+
+        assert!(matches!(
+            LeafId::parse(&root_key(&root_pk)),
+            Some(LeafId::Root(pk)) if pk == root_pk
+        ));
+        let key = relation_key(&issued_for, &issued_by);
+        assert!(matches!(
+            LeafId::parse(&key),
+            Some(LeafId::Relation { issued_for: f, issued_by: b })
+                if f == issued_for && b == issued_by
+        ));
+        assert!(LeafId::parse(&key[..key.len() - 1]).is_none());
+        assert!(LeafId::parse(&[key.as_slice(), &[0]].concat()).is_none());
+    }
+
+    #[test]
+    fn test_proofs() {
+        let kps: Vec<KeyPair> = (0..40).map(|_| KeyPair::generate_ed25519()).collect();
+        let relations: Vec<TrustRelation> = kps
+            .windows(2)
+            .map(|pair| auth(&pair[0], &pair[1], 100))
+            .collect();
+        let mut storage = InMemoryStorage::default();
+        build(&mut storage, &[], &relations).unwrap();
+        let state_root = state_root(&storage).unwrap();
+
+        for relation in relations.iter() {
+            let proof = prove(&storage, relation).unwrap().unwrap();
+            assert!(proof.verify(relation, &state_root));
+        }
+
+        let proof = prove(&storage, &relations[0]).unwrap().unwrap();
+        assert!(!proof.verify(&relations[1], &state_root));
+        assert!(!proof.verify(&relations[0], &relations[0].digest()));
+
+        // same keys, different trust
+        let other = auth(&kps[0], &kps[1], 200);
+        assert!(prove(&storage, &other).unwrap().is_none());
+        assert!(!proof.verify(&other, &state_root));
+    }
+
+    #[test]
+    fn test_graph_state_root() {
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::from_secs(10);
+
+        let mut incremental = TrustGraph::new(InMemoryStorage::default());
+        let mut rebuilt = TrustGraph::new(InMemoryStorage::default());
+        let empty_root = incremental.state_root().unwrap();
+
+        for graph in [&mut incremental, &mut rebuilt] {
+            graph.set_root(root_kp.public(), 10).unwrap();
+            for (issuer, issued_for) in [(&root_kp, &root_kp), (&root_kp, &a_kp), (&a_kp, &b_kp)] {
+                let trust = Trust::create(
+                    issuer,
+                    issued_for.public(),
+                    Duration::from_secs(100),
+                    Duration::from_secs(1),
+                );
+                graph.add_trust(trust, issuer.public(), cur_time).unwrap();
+            }
+            graph
+                .revoke(Revocation::create(
+                    &a_kp,
+                    b_kp.public(),
+                    Duration::from_secs(2),
+                ))
+                .unwrap();
+        }
+
+        let state_root = incremental.state_root().unwrap();
+        assert_ne!(state_root, empty_root);
+        assert_eq!(state_root, rebuilt.state_root().unwrap());
+
+        let (relation, proof) = incremental
+            .prove_relation(a_kp.public(), root_kp.public())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(relation, TrustRelation::Auth(_)));
+        assert!(proof.verify(&relation, &state_root));
+
+        // trusts expire on the next read
+        incremental
+            .weight(a_kp.public(), Duration::from_secs(100))
+            .unwrap();
+        assert!(!proof.verify(&relation, &incremental.state_root().unwrap()));
+        assert!(incremental
+            .prove_relation(a_kp.public(), root_kp.public())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_stored_state() {
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::from_secs(10);
+
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_root(root_kp.public(), 10).unwrap();
+        let state_root = graph.state_root().unwrap();
+
+        // a graph over the same storage keeps the stored tree up to date
+        // before its state root is ever requested
+        let mut restarted = TrustGraph::new(graph.storage().clone());
+        let trust = Trust::create(
+            &root_kp,
+            a_kp.public(),
+            Duration::from_secs(100),
+            Duration::from_secs(1),
+        );
+        restarted
+            .add_trust(trust.clone(), root_kp.public(), cur_time)
+            .unwrap();
+        let stored_root = super::state_root(restarted.storage()).unwrap();
+        assert_ne!(stored_root, state_root);
+
+        let mut rebuilt = TrustGraph::new(InMemoryStorage::default());
+        rebuilt.set_root(root_kp.public(), 10).unwrap();
+        rebuilt
+            .add_trust(trust, root_kp.public(), cur_time)
+            .unwrap();
+        assert_eq!(rebuilt.state_root().unwrap(), stored_root);
+        assert_eq!(restarted.state_root().unwrap(), stored_root);
+    }
+
+    #[test]
+    fn test_non_revocation_proofs() {
+        let kps: Vec<KeyPair> = (0..20).map(|_| KeyPair::generate_ed25519()).collect();
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+
+        // every bucket is empty
+        let proof = graph
+            .prove_non_revocation(&kps[1].public(), &kps[0].public())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            proof,
+            NonRevocationProof::Absent {
+                prev: None,
+                next: None,
+                ..
+            }
+        ));
+
+        graph.set_root(kps[0].public(), 10).unwrap();
+        for pair in kps.windows(2) {
+            let trust = Trust::create(
+                &pair[0],
+                pair[1].public(),
+                Duration::from_secs(100),
+                Duration::from_secs(1),
+            );
+            graph
+                .add_trust(trust, pair[0].public(), Duration::from_secs(1))
+                .unwrap();
+        }
+        graph
+            .revoke(Revocation::create(
+                &kps[0],
+                kps[5].public(),
+                Duration::from_secs(2),
+            ))
+            .unwrap();
+        let state_root = graph.state_root().unwrap();
+
+        for later in 1..kps.len() {
+            let (issued_for, issued_by) = (kps[later].public(), kps[0].public());
+            let proof = graph.prove_non_revocation(&issued_for, &issued_by).unwrap();
+            if later == 5 {
+                assert!(proof.is_none());
+                continue;
+            }
+
+            let proof = proof.unwrap();
+            assert_eq!(
+                matches!(proof, NonRevocationProof::Relation(..)),
+                later == 1
+            );
+            assert!(proof.verify(&issued_for, &issued_by, &state_root));
+            assert!(!proof.verify(&issued_for, &issued_by, &[0u8; 32]));
+            assert!(!proof.verify(&kps[5].public(), &issued_by, &state_root));
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+
+/// Size and health of a trust graph, see `TrustGraph::stats`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphStats {
+    /// distinct keys in relations and roots
+    pub keys: u64,
+    pub auths: u64,
+    pub revocations: u64,
+    pub distrusts: u64,
+    pub roots: u64,
+    /// keys that are roots by a valid threshold trust
+    pub anchored_roots: u64,
+    /// auths that are expired but not garbage collected yet
+    pub expired: u64,
+    /// number of keys by length of the shortest valid chain from a root,
+    /// roots themselves have depth 0, unreachable keys aren't counted
+    pub depth_histogram: Vec<u64>,
+    /// number of keys by key format
+    pub key_formats: BTreeMap<String, u64>,
+}
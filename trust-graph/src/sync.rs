@@ -0,0 +1,338 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::trust_graph::{TrustGraph, TrustGraphError};
+use crate::trust_graph_storage::Storage;
+use crate::trust_relation::TrustRelation;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::collections::HashSet;
+
+/// Digest of a relation, see `TrustRelation::digest`
+pub type RelationDigest = [u8; 32];
+
+/// Number of buckets relations are split into by their keys
+pub const SYNC_BUCKETS: usize = 256;
+
+/// Digests of all relation buckets of a replica and the root digest over them.
+/// Replicas that hold the same relations have equal summaries.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncSummary {
+    pub root: RelationDigest,
+    pub buckets: Vec<RelationDigest>,
+}
+
+/// Result of `TrustGraph::sync_from`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// buckets that differ between the replicas
+    pub buckets: u32,
+    /// relations fetched from the remote replica
+    pub fetched: u32,
+    /// relations verified and applied to this replica
+    pub applied: u32,
+    /// relations that failed verification or weren't requested
+    pub rejected: u32,
+    /// verified trusts from issuers without weight on this replica, they are not stored
+    pub unanchored: u32,
+}
+
+/// Remote side of the sync. Implementations only pass requests to another replica,
+/// everything they return is verified by `TrustGraph::sync_from`.
+pub trait SyncTransport {
+    type Error: std::error::Error + 'static;
+
+    fn summary(&mut self) -> Result<SyncSummary, Self::Error>;
+    /// digests of all relations in the buckets
+    fn digests(&mut self, buckets: &[u8]) -> Result<Vec<RelationDigest>, Self::Error>;
+    /// relations with the given digests, unknown digests are skipped
+    fn relations(&mut self, digests: &[RelationDigest]) -> Result<Vec<TrustRelation>, Self::Error>;
+}
+
+/// Transport to a replica in the same process
+pub struct LocalTransport<'a, S: Storage> {
+    graph: &'a TrustGraph<S>,
+}
+
+impl<'a, S: Storage> LocalTransport<'a, S> {
+    pub fn new(graph: &'a TrustGraph<S>) -> Self {
+        Self { graph }
+    }
+}
+
+impl<'a, S: Storage> SyncTransport for LocalTransport<'a, S> {
+    type Error = TrustGraphError;
+
+    fn summary(&mut self) -> Result<SyncSummary, Self::Error> {
+        self.graph.sync_summary()
+    }
+
+    fn digests(&mut self, buckets: &[u8]) -> Result<Vec<RelationDigest>, Self::Error> {
+        self.graph.sync_digests(buckets)
+    }
+
+    fn relations(&mut self, digests: &[RelationDigest]) -> Result<Vec<TrustRelation>, Self::Error> {
+        self.graph.sync_relations(digests)
+    }
+}
+
+/// Relations are bucketed by their keys, so all versions of a relation
+/// between two keys fall into the same bucket on every replica
+pub(crate) fn bucket(relation: &TrustRelation) -> u8 {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(relation.issued_for().encode());
+    hasher.update(relation.issued_by().encode());
+    hasher.finalize()[0]
+}
+
+/// Every bucket digest is sha256 over sorted digests of its relations,
+/// the root digest is sha256 over all bucket digests
+pub(crate) fn summarize(relations: &[TrustRelation]) -> SyncSummary {
+    let mut buckets: Vec<Vec<RelationDigest>> = vec![vec![]; SYNC_BUCKETS];
+    for relation in relations {
+        buckets[bucket(relation) as usize].push(relation.digest());
+    }
+
+    let buckets: Vec<RelationDigest> = buckets
+        .into_iter()
+        .map(|mut digests| {
+            digests.sort_unstable();
+            let mut hasher = sha2::Sha256::new();
+            digests.iter().for_each(|d| hasher.update(d));
+            hasher.finalize().into()
+        })
+        .collect();
+
+    let mut hasher = sha2::Sha256::new();
+    buckets.iter().for_each(|d| hasher.update(d));
+
+    SyncSummary {
+        root: hasher.finalize().into(),
+        buckets,
+    }
+}
+
+/// Digests of relations in the given buckets
+pub(crate) fn bucket_digests(relations: &[TrustRelation], buckets: &[u8]) -> Vec<RelationDigest> {
+    let buckets: HashSet<u8> = buckets.iter().copied().collect();
+    relations
+        .iter()
+        .filter(|r| buckets.contains(&bucket(r)))
+        .map(|r| r.digest())
+        .collect()
+}
+
+/// Buckets with different digests, a malformed summary differs in every bucket
+pub(crate) fn diff_buckets(local: &SyncSummary, remote: &SyncSummary) -> Vec<u8> {
+    if local.root == remote.root {
+        return vec![];
+    }
+
+    (0..SYNC_BUCKETS)
+        .filter(|i| local.buckets.get(*i) != remote.buckets.get(*i))
+        .map(|i| i as u8)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_memory_storage::InMemoryStorage;
+    use crate::revoke::Revocation;
+    use crate::trust::Trust;
+    use fluence_keypair::key_pair::KeyPair;
+    use fluence_keypair::public_key::PublicKey;
+    use std::time::Duration;
+
+    fn graph(root: &KeyPair) -> TrustGraph<InMemoryStorage> {
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_root(root.public(), 10).unwrap();
+        trust(&mut graph, root, root, 1);
+        graph
+    }
+
+    fn trust(
+        graph: &mut TrustGraph<InMemoryStorage>,
+        issuer: &KeyPair,
+        issued_for: &KeyPair,
+        issued_at: u64,
+    ) {
+        let trust = Trust::create(
+            issuer,
+            issued_for.public(),
+            Duration::from_secs(1000),
+            Duration::from_secs(issued_at),
+        );
+        graph
+            .add_trust(trust, issuer.public(), Duration::from_secs(issued_at))
+            .unwrap();
+    }
+
+    /// Replays relations with a forged expiration time
+    struct TamperingTransport<'a>(LocalTransport<'a, InMemoryStorage>);
+
+    impl<'a> SyncTransport for TamperingTransport<'a> {
+        type Error = TrustGraphError;
+
+        fn summary(&mut self) -> Result<SyncSummary, Self::Error> {
+            self.0.summary()
+        }
+
+        fn digests(&mut self, buckets: &[u8]) -> Result<Vec<RelationDigest>, Self::Error> {
+            self.0.digests(buckets)
+        }
+
+        fn relations(
+            &mut self,
+            digests: &[RelationDigest],
+        ) -> Result<Vec<TrustRelation>, Self::Error> {
+            let mut relations = self.0.relations(digests)?;
+            for relation in relations.iter_mut() {
+                if let TrustRelation::Auth(auth) = relation {
+                    auth.trust.expires_at += Duration::from_secs(1000);
+                }
+            }
+            Ok(relations)
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let keys: Vec<KeyPair> = (0..10).map(|_| KeyPair::generate_ed25519()).collect();
+        let relations: Vec<TrustRelation> = keys
+            .windows(2)
+            .map(|pair| {
+                TrustRelation::Revocation(Revocation::create(
+                    &pair[0],
+                    pair[1].public(),
+                    Duration::from_secs(100),
+                ))
+            })
+            .collect();
+
+        let summary = summarize(&relations);
+        let mut reversed = relations.clone();
+        reversed.reverse();
+        assert_eq!(summarize(&reversed), summary);
+
+        let changed = summarize(&relations[1..]);
+        let first_bucket = bucket(&relations[0]);
+        assert_eq!(diff_buckets(&summary, &changed), vec![first_bucket]);
+
+        // other relations may fall into the same bucket
+        let digests = bucket_digests(&relations, &[first_bucket]);
+        let expected: Vec<RelationDigest> = relations
+            .iter()
+            .filter(|r| bucket(r) == first_bucket)
+            .map(|r| r.digest())
+            .collect();
+        assert!(digests.contains(&relations[0].digest()));
+        assert_eq!(digests.len(), expected.len());
+    }
+
+    #[test]
+    fn test_sync_replicas() {
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let c_kp = KeyPair::generate_ed25519();
+        let x_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::from_secs(100);
+
+        let mut first = graph(&root_kp);
+        trust(&mut first, &root_kp, &a_kp, 10);
+        trust(&mut first, &a_kp, &b_kp, 11);
+        first
+            .revoke(Revocation::create(
+                &root_kp,
+                c_kp.public(),
+                Duration::from_secs(13),
+            ))
+            .unwrap();
+
+        let mut second = graph(&root_kp);
+        trust(&mut second, &root_kp, &c_kp, 12);
+        trust(&mut second, &root_kp, &x_kp, 12);
+
+        let report = second
+            .sync_from(&mut LocalTransport::new(&first), cur_time)
+            .unwrap();
+        assert_eq!(report.fetched, 3);
+        assert_eq!(report.applied, 3);
+        assert_eq!(report.rejected, 0);
+        assert_eq!(report.unanchored, 0);
+
+        let weight = |graph: &mut TrustGraph<InMemoryStorage>, pk: PublicKey| {
+            graph.weight(pk, cur_time).unwrap()
+        };
+        assert_ne!(weight(&mut second, b_kp.public()), 0);
+        assert_eq!(
+            weight(&mut second, b_kp.public()),
+            weight(&mut first, b_kp.public())
+        );
+        // the revocation is newer than the trust
+        assert_eq!(weight(&mut second, c_kp.public()), 0);
+
+        let report = first
+            .sync_from(&mut LocalTransport::new(&second), cur_time)
+            .unwrap();
+        assert_eq!(report.fetched, 1);
+        assert_eq!(report.applied, 1);
+        assert_ne!(weight(&mut first, x_kp.public()), 0);
+        assert_eq!(
+            first.sync_summary().unwrap(),
+            second.sync_summary().unwrap()
+        );
+
+        let report = second
+            .sync_from(&mut LocalTransport::new(&first), cur_time)
+            .unwrap();
+        assert_eq!(report, SyncReport::default());
+    }
+
+    #[test]
+    fn test_sync_verifies_relations() {
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let x_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::from_secs(100);
+
+        let mut first = graph(&root_kp);
+        trust(&mut first, &root_kp, &a_kp, 10);
+
+        let mut second = graph(&root_kp);
+        let report = second
+            .sync_from(
+                &mut TamperingTransport(LocalTransport::new(&first)),
+                cur_time,
+            )
+            .unwrap();
+        assert_eq!(report.fetched, 1);
+        assert_eq!(report.rejected, 1);
+        assert_eq!(second.weight(a_kp.public(), cur_time).unwrap(), 0);
+
+        // x is not a root on the second replica, so neither its self-signed trust
+        // nor the trust it issued gives any weight there
+        let mut third = graph(&x_kp);
+        trust(&mut third, &x_kp, &b_kp, 10);
+        let report = second
+            .sync_from(&mut LocalTransport::new(&third), cur_time)
+            .unwrap();
+        assert_eq!(report.unanchored, 2);
+        assert_eq!(second.weight(b_kp.public(), cur_time).unwrap(), 0);
+    }
+}
@@ -0,0 +1,533 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::signing::{push_prefixed, v2_signature_bytes, PayloadType};
+use crate::threshold::ThresholdError::{
+    DecodeErrorInvalidSize, Expired, InvalidThreshold, NonCanonicalKeys, NotEnoughSignatures,
+};
+use fluence_keypair::key_pair::KeyPair;
+use fluence_keypair::public_key::PublicKey;
+use fluence_keypair::signature::Signature;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum ThresholdError {
+    #[error("Threshold {0} is invalid for an anchor with {1} distinct keys")]
+    InvalidThreshold(u32, usize),
+    #[error("Anchor keys must be distinct and sorted by their encoding")]
+    NonCanonicalKeys,
+    #[error("Threshold trust is expired at: '{0:?}', current time: '{1:?}'")]
+    Expired(Duration, Duration),
+    #[error("Threshold trust has {1} valid signatures of anchor keys, {0} required")]
+    NotEnoughSignatures(u32, usize),
+    #[error("Cannot decode `{0}` field in the threshold trust: invalid size")]
+    DecodeErrorInvalidSize(String),
+    #[error("{0}")]
+    DecodeError(
+        #[from]
+        #[source]
+        fluence_keypair::error::DecodingError,
+    ),
+}
+
+/// Takes `len` bytes of the `field` at `offset` and moves the offset past them
+fn take<'a>(
+    arr: &'a [u8],
+    offset: &mut usize,
+    len: usize,
+    field: &str,
+) -> Result<&'a [u8], ThresholdError> {
+    let bytes = arr
+        .get(*offset..*offset + len)
+        .ok_or_else(|| DecodeErrorInvalidSize(field.to_string()))?;
+    *offset += len;
+    Ok(bytes)
+}
+
+fn take_u32(arr: &[u8], offset: &mut usize, field: &str) -> Result<u32, ThresholdError> {
+    let bytes = take(arr, offset, 4, field)?;
+    Ok(u32::from_le_bytes(
+        bytes.try_into().expect("slice is 4 bytes"),
+    ))
+}
+
+fn take_u64(arr: &[u8], offset: &mut usize, field: &str) -> Result<u64, ThresholdError> {
+    let bytes = take(arr, offset, 8, field)?;
+    Ok(u64::from_le_bytes(
+        bytes.try_into().expect("slice is 8 bytes"),
+    ))
+}
+
+/// Takes a field prefixed by its 2 bytes length
+fn take_prefixed<'a>(
+    arr: &'a [u8],
+    offset: &mut usize,
+    field: &str,
+) -> Result<&'a [u8], ThresholdError> {
+    let len = u16::from_le_bytes(
+        take(arr, offset, 2, field)?
+            .try_into()
+            .expect("slice is 2 bytes"),
+    );
+    take(arr, offset, len as usize, field)
+}
+
+/// Multi-key root: `threshold` of `keys` holders have to sign a root certification together.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawThresholdAnchor")]
+pub struct ThresholdAnchor {
+    /// distinct keys sorted by their encoding, so equal anchors are always equal structurally
+    keys: Vec<PublicKey>,
+    threshold: u32,
+}
+
+/// Deserialized anchor before the checks of `ThresholdAnchor::new`
+#[derive(Serialize, Deserialize)]
+struct RawThresholdAnchor {
+    keys: Vec<PublicKey>,
+    threshold: u32,
+}
+
+impl TryFrom<RawThresholdAnchor> for ThresholdAnchor {
+    type Error = ThresholdError;
+
+    fn try_from(raw: RawThresholdAnchor) -> Result<Self, Self::Error> {
+        let anchor = Self::new(raw.keys.clone(), raw.threshold)?;
+        // serialized anchors are always canonical
+        if anchor.keys != raw.keys {
+            return Err(NonCanonicalKeys);
+        }
+
+        Ok(anchor)
+    }
+}
+
+impl ThresholdAnchor {
+    pub fn new(mut keys: Vec<PublicKey>, threshold: u32) -> Result<Self, ThresholdError> {
+        keys.sort_by_key(|k| k.encode());
+        keys.dedup();
+
+        if threshold == 0 || threshold as usize > keys.len() {
+            return Err(InvalidThreshold(threshold, keys.len()));
+        }
+
+        Ok(Self { keys, threshold })
+    }
+
+    pub fn keys(&self) -> &[PublicKey] {
+        &self.keys
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Encoding of the anchor, part of every threshold trust payload
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(&self.threshold.to_le_bytes());
+        encoded.extend_from_slice(&(self.keys.len() as u32).to_le_bytes());
+        for key in self.keys.iter() {
+            push_prefixed(&mut encoded, &key.encode());
+        }
+
+        encoded
+    }
+
+    fn decode_from(arr: &[u8], offset: &mut usize) -> Result<Self, ThresholdError> {
+        let threshold = take_u32(arr, offset, "threshold")?;
+        let keys_number = take_u32(arr, offset, "keys_number")?;
+        let keys = (0..keys_number)
+            .map(|_| Ok(PublicKey::decode(take_prefixed(arr, offset, "key")?)?))
+            .collect::<Result<_, ThresholdError>>()?;
+
+        Self::new(keys, threshold)
+    }
+
+    /// Stable identifier of the anchor, base58
+    pub fn id(&self) -> String {
+        bs58::encode(sha2::Sha256::digest(self.encode())).into_string()
+    }
+}
+
+/// Trust issued by a threshold anchor for a key, which then acts as a root.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdTrust {
+    /// the issuer of this trust
+    pub anchor: ThresholdAnchor,
+    /// For whom this trust is issued
+    pub issued_for: PublicKey,
+    /// Expiration date of a trust
+    pub expires_at: Duration,
+    /// Creation time of a trust
+    pub issued_at: Duration,
+    /// Signatures of anchor key holders and their keys
+    pub signatures: Vec<(PublicKey, Signature)>,
+}
+
+impl ThresholdTrust {
+    pub fn new(
+        anchor: ThresholdAnchor,
+        issued_for: PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+        signatures: Vec<(PublicKey, Signature)>,
+    ) -> Self {
+        Self {
+            anchor,
+            issued_for,
+            expires_at,
+            issued_at,
+            signatures,
+        }
+    }
+
+    /// Creates new threshold trust signed by all `signers`.
+    pub fn create(
+        anchor: ThresholdAnchor,
+        signers: &[&KeyPair],
+        issued_for: PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+    ) -> Self {
+        let msg = Self::signature_bytes(&anchor, &issued_for, expires_at, issued_at);
+        let signatures = signers
+            .iter()
+            .map(|kp| (kp.public(), kp.sign(&msg).unwrap()))
+            .collect();
+
+        Self::new(anchor, issued_for, expires_at, issued_at, signatures)
+    }
+
+    pub fn signature_bytes(
+        anchor: &ThresholdAnchor,
+        issued_for: &PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+    ) -> Vec<u8> {
+        let mut fields = anchor.encode();
+        push_prefixed(&mut fields, &issued_for.encode());
+        fields.extend_from_slice(&expires_at.as_secs().to_le_bytes());
+        fields.extend_from_slice(&issued_at.as_secs().to_le_bytes());
+
+        v2_signature_bytes(PayloadType::ThresholdTrust, &fields)
+    }
+
+    /// Encode the trust into a byte array:
+    /// anchor, then issued_for, expires_at and issued_at as in the signed payload,
+    /// then 4 bytes number of signatures and (key, signature) pairs prefixed by their lengths
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = self.anchor.encode();
+        push_prefixed(&mut encoded, &self.issued_for.encode());
+        encoded.extend_from_slice(&self.expires_at.as_secs().to_le_bytes());
+        encoded.extend_from_slice(&self.issued_at.as_secs().to_le_bytes());
+        encoded.extend_from_slice(&(self.signatures.len() as u32).to_le_bytes());
+        for (pk, signature) in self.signatures.iter() {
+            push_prefixed(&mut encoded, &pk.encode());
+            push_prefixed(&mut encoded, &signature.encode());
+        }
+
+        encoded
+    }
+
+    /// Decode a trust from a byte array as produced by `encode`.
+    pub fn decode(arr: &[u8]) -> Result<Self, ThresholdError> {
+        let mut offset = 0;
+        let anchor = ThresholdAnchor::decode_from(arr, &mut offset)?;
+        let issued_for = PublicKey::decode(take_prefixed(arr, &mut offset, "issued_for")?)?;
+        let expires_at = Duration::from_secs(take_u64(arr, &mut offset, "expires_at")?);
+        let issued_at = Duration::from_secs(take_u64(arr, &mut offset, "issued_at")?);
+
+        let signatures_number = take_u32(arr, &mut offset, "signatures_number")?;
+        let signatures = (0..signatures_number)
+            .map(|_| {
+                let pk = PublicKey::decode(take_prefixed(arr, &mut offset, "signer")?)?;
+                let signature =
+                    Signature::decode(take_prefixed(arr, &mut offset, "signature")?.to_vec())?;
+                Ok((pk, signature))
+            })
+            .collect::<Result<_, ThresholdError>>()?;
+
+        if offset != arr.len() {
+            return Err(DecodeErrorInvalidSize("signatures".to_string()));
+        }
+
+        Ok(Self::new(
+            anchor, issued_for, expires_at, issued_at, signatures,
+        ))
+    }
+
+    /// Verifies that the trust is not expired
+    /// and carries at least `threshold` valid signatures of distinct anchor keys.
+    pub fn verify(trust: &ThresholdTrust, cur_time: Duration) -> Result<(), ThresholdError> {
+        if trust.expires_at < cur_time {
+            return Err(Expired(trust.expires_at, cur_time));
+        }
+
+        let msg = Self::signature_bytes(
+            &trust.anchor,
+            &trust.issued_for,
+            trust.expires_at,
+            trust.issued_at,
+        );
+
+        let mut signed: Vec<&PublicKey> = vec![];
+        for (pk, signature) in trust.signatures.iter() {
+            if trust.anchor.keys.contains(pk)
+                && !signed.contains(&pk)
+                && pk.verify(&msg, signature).is_ok()
+            {
+                signed.push(pk);
+            }
+        }
+
+        if signed.len() < trust.anchor.threshold as usize {
+            return Err(NotEnoughSignatures(trust.anchor.threshold, signed.len()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_memory_storage::InMemoryStorage;
+    use crate::trust::Trust;
+    use crate::trust_graph::TrustGraph;
+
+    fn anchor(holders: &[KeyPair], threshold: u32) -> ThresholdAnchor {
+        ThresholdAnchor::new(holders.iter().map(|kp| kp.public()).collect(), threshold).unwrap()
+    }
+
+    #[test]
+    fn test_threshold_trust_ed25519() {
+        let holders: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate_ed25519()).collect();
+        let anchor = anchor(&holders, 2);
+        let root = KeyPair::generate_ed25519();
+        let cur_time = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+
+        let trust = ThresholdTrust::create(
+            anchor.clone(),
+            &[&holders[0], &holders[2]],
+            root.public(),
+            expires_at,
+            cur_time,
+        );
+        assert!(ThresholdTrust::verify(&trust, cur_time).is_ok());
+        assert!(ThresholdTrust::verify(&trust, expires_at + Duration::new(1, 0)).is_err());
+
+        let trust =
+            ThresholdTrust::create(anchor, &[&holders[1]], root.public(), expires_at, cur_time);
+        assert!(matches!(
+            ThresholdTrust::verify(&trust, cur_time),
+            Err(NotEnoughSignatures(2, 1))
+        ));
+    }
+
+    #[test]
+    fn test_threshold_trust_encoding() {
+        let holders = vec![
+            KeyPair::generate_ed25519(),
+            KeyPair::generate_secp256k1(),
+            KeyPair::generate_p256(),
+        ];
+        let anchor = anchor(&holders, 2);
+        let root = KeyPair::generate_ed25519();
+
+        let trust = ThresholdTrust::create(
+            anchor,
+            &[&holders[1], &holders[2]],
+            root.public(),
+            Duration::new(1000, 0),
+            Duration::new(100, 0),
+        );
+        let encoded = trust.encode();
+        assert_eq!(ThresholdTrust::decode(&encoded).unwrap(), trust);
+
+        assert!(matches!(
+            ThresholdTrust::decode(&encoded[..encoded.len() - 1]),
+            Err(DecodeErrorInvalidSize(_))
+        ));
+        let mut extended = encoded;
+        extended.push(0);
+        assert!(matches!(
+            ThresholdTrust::decode(&extended),
+            Err(DecodeErrorInvalidSize(_))
+        ));
+    }
+
+    #[test]
+    fn test_threshold_trust_encoding_rsa() {
+        let holders = vec![
+            KeyPair::from_pkcs8_der(include_bytes!("../../keypair/src/test/rsa-2048.pk8")).unwrap(),
+            KeyPair::from_pkcs8_der(include_bytes!("../../keypair/src/test/rsa-3072.pk8")).unwrap(),
+        ];
+        let anchor = anchor(&holders, 2);
+        let root =
+            KeyPair::from_pkcs8_der(include_bytes!("../../keypair/src/test/rsa-4096.pk8")).unwrap();
+        let cur_time = Duration::new(100, 0);
+
+        let trust = ThresholdTrust::create(
+            anchor.clone(),
+            &[&holders[0], &holders[1]],
+            root.public(),
+            Duration::new(1000, 0),
+            cur_time,
+        );
+        assert!(ThresholdTrust::verify(&trust, cur_time).is_ok());
+
+        let encoded = trust.encode();
+        assert_eq!(ThresholdTrust::decode(&encoded).unwrap(), trust);
+        assert_eq!(
+            ThresholdAnchor::decode_from(&anchor.encode(), &mut 0).unwrap(),
+            anchor
+        );
+    }
+
+    #[test]
+    fn test_duplicate_and_foreign_signatures_ed25519() {
+        let holders: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate_ed25519()).collect();
+        let anchor = anchor(&holders, 2);
+        let outsider = KeyPair::generate_ed25519();
+        let root = KeyPair::generate_ed25519();
+        let cur_time = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+
+        let trust = ThresholdTrust::create(
+            anchor,
+            &[&holders[0], &holders[0], &outsider],
+            root.public(),
+            expires_at,
+            cur_time,
+        );
+        assert!(matches!(
+            ThresholdTrust::verify(&trust, cur_time),
+            Err(NotEnoughSignatures(2, 1))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_threshold_ed25519() {
+        let holders: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate_ed25519()).collect();
+        let keys: Vec<PublicKey> = holders.iter().map(|kp| kp.public()).collect();
+
+        assert!(ThresholdAnchor::new(keys.clone(), 0).is_err());
+        assert!(ThresholdAnchor::new(keys.clone(), 3).is_err());
+        // duplicates are not counted as distinct keys
+        let mut duplicated = keys.clone();
+        duplicated.push(keys[0].clone());
+        assert!(ThresholdAnchor::new(duplicated, 3).is_err());
+        assert_eq!(
+            ThresholdAnchor::new(keys.clone(), 2).unwrap().id(),
+            ThresholdAnchor::new(keys.into_iter().rev().collect(), 2)
+                .unwrap()
+                .id()
+        );
+    }
+
+    #[test]
+    fn test_expired_anchor_trust() {
+        let holders: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate_ed25519()).collect();
+        let anchor = anchor(&holders, 2);
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::new(10, 0);
+        let expired_time = Duration::new(200, 0);
+
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_threshold_root(anchor.clone(), 4).unwrap();
+        let trust = ThresholdTrust::create(
+            anchor,
+            &[&holders[0], &holders[1]],
+            root_kp.public(),
+            Duration::new(100, 0),
+            Duration::new(1, 0),
+        );
+        graph.add_threshold_trust(trust, cur_time).unwrap();
+        for issued_for in [&root_kp, &a_kp] {
+            let trust = Trust::create(
+                &root_kp,
+                issued_for.public(),
+                Duration::new(1000, 0),
+                Duration::new(1, 0),
+            );
+            graph.add_trust(trust, root_kp.public(), cur_time).unwrap();
+        }
+
+        let certs = graph.get_all_certs(a_kp.public(), cur_time).unwrap();
+        assert_eq!(certs.len(), 1);
+        assert!(certs[0].anchor_trust.is_some());
+        assert!(graph
+            .certificates_weight(&certs, cur_time)
+            .unwrap()
+            .is_some());
+
+        // the chain itself is valid longer than the anchor trust
+        assert!(graph
+            .certificates_weight(&certs, expired_time)
+            .unwrap()
+            .is_none());
+        assert_eq!(graph.weight(a_kp.public(), expired_time).unwrap(), 0);
+
+        // anchor trust issued for another key
+        let mut unbound = certs[0].clone();
+        if let Some(anchor_trust) = unbound.anchor_trust.as_mut() {
+            anchor_trust.issued_for = a_kp.public();
+        }
+        assert!(graph
+            .certificates_weight_factor([unbound], cur_time)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_deserialize_invalid_anchor() {
+        let holders: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate_ed25519()).collect();
+        let valid = anchor(&holders, 2);
+        let deserialize = |keys: Vec<PublicKey>, threshold: u32| {
+            let raw = serde_json::to_string(&RawThresholdAnchor { keys, threshold }).unwrap();
+            serde_json::from_str::<ThresholdAnchor>(&raw)
+        };
+
+        assert_eq!(
+            deserialize(valid.keys().to_vec(), 2).unwrap(),
+            valid.clone()
+        );
+        assert!(deserialize(valid.keys().to_vec(), 0).is_err());
+        assert!(deserialize(valid.keys().to_vec(), 3).is_err());
+        let duplicated = vec![valid.keys()[0].clone(), valid.keys()[0].clone()];
+        assert!(deserialize(duplicated, 1).is_err());
+        let unsorted = valid.keys().iter().rev().cloned().collect();
+        assert!(deserialize(unsorted, 1).is_err());
+
+        // anchors inside threshold trusts are checked as well
+        let trust = ThresholdTrust::create(
+            valid,
+            &[&holders[0], &holders[1]],
+            KeyPair::generate_ed25519().public(),
+            Duration::new(200, 0),
+            Duration::new(100, 0),
+        );
+        let mut json: serde_json::Value = serde_json::to_value(&trust).unwrap();
+        json["anchor"]["threshold"] = 0.into();
+        assert!(serde_json::from_value::<ThresholdTrust>(json).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::merkle::{leaf_hash, root_from_path, verify_consistency, Hash};
+use crate::signing::{push_prefixed, v2_signature_bytes, PayloadType};
+use crate::transparency_log::TreeHeadError::IncorrectSignature;
+use crate::trust_relation::TrustRelation;
+use fluence_keypair::key_pair::KeyPair;
+use fluence_keypair::public_key::PublicKey;
+use fluence_keypair::signature::Signature;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum TreeHeadError {
+    #[error("Signature is incorrect: {0}")]
+    IncorrectSignature(
+        #[from]
+        #[source]
+        fluence_keypair::error::VerificationError,
+    ),
+}
+
+/// Relation accepted by the graph, in order of acceptance
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// position of the entry in the log, starting from 0
+    pub sequence: u64,
+    pub relation: TrustRelation,
+}
+
+impl LogEntry {
+    /// Leaf of the log tree, binds the relation to its position
+    pub fn leaf_hash(&self) -> Hash {
+        let mut data = self.sequence.to_le_bytes().to_vec();
+        data.extend(self.relation.digest());
+        leaf_hash(&data)
+    }
+}
+
+/// Statement of the log owner that the log had `size` entries with tree `root` at `timestamp`.
+/// Peers compare tree heads they got and check consistency proofs between them,
+/// so the log can't show different histories to different peers unnoticed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTreeHead {
+    /// the log owner, also the signer of this statement
+    pub log_key: PublicKey,
+    pub size: u64,
+    pub root: Hash,
+    pub timestamp: Duration,
+    /// proof of this statement
+    pub signature: Signature,
+}
+
+impl SignedTreeHead {
+    pub fn new(
+        log_key: PublicKey,
+        size: u64,
+        root: Hash,
+        timestamp: Duration,
+        signature: Signature,
+    ) -> Self {
+        Self {
+            log_key,
+            size,
+            root,
+            timestamp,
+            signature,
+        }
+    }
+
+    /// Creates new tree head signed by the log owner.
+    pub fn create(log_key: &KeyPair, size: u64, root: Hash, timestamp: Duration) -> Self {
+        let msg = SignedTreeHead::signature_bytes(&log_key.public(), size, &root, timestamp);
+        let signature = log_key.sign(&msg).unwrap();
+
+        SignedTreeHead::new(log_key.public(), size, root, timestamp, signature)
+    }
+
+    pub fn signature_bytes(
+        log_key: &PublicKey,
+        size: u64,
+        root: &Hash,
+        timestamp: Duration,
+    ) -> Vec<u8> {
+        let mut fields = Vec::new();
+        push_prefixed(&mut fields, &log_key.encode());
+        fields.extend_from_slice(&size.to_le_bytes());
+        fields.extend_from_slice(root);
+        fields.extend_from_slice(&timestamp.as_secs().to_le_bytes());
+
+        v2_signature_bytes(PayloadType::TreeHead, &fields)
+    }
+
+    /// Verifies that the tree head is signed by the log owner.
+    pub fn verify(head: &SignedTreeHead) -> Result<(), TreeHeadError> {
+        let msg =
+            SignedTreeHead::signature_bytes(&head.log_key, head.size, &head.root, head.timestamp);
+
+        head.log_key
+            .verify(msg.as_slice(), &head.signature)
+            .map_err(IncorrectSignature)
+    }
+}
+
+/// Check that `entry` is in the log tree with `head`
+pub fn verify_log_inclusion(entry: &LogEntry, head: &SignedTreeHead, path: &[Hash]) -> bool {
+    root_from_path(&entry.leaf_hash(), entry.sequence, head.size, path)
+        .is_some_and(|root| root == head.root)
+}
+
+/// Check that the log with `second` head only appended entries to the log with `first` head
+pub fn verify_log_consistency(
+    first: &SignedTreeHead,
+    second: &SignedTreeHead,
+    path: &[Hash],
+) -> bool {
+    first.log_key == second.log_key
+        && verify_consistency(first.size, second.size, &first.root, &second.root, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_memory_storage::InMemoryStorage;
+    use crate::revoke::Revocation;
+    use crate::trust::Trust;
+    use crate::trust_graph::TrustGraph;
+
+    fn trust(
+        graph: &mut TrustGraph<InMemoryStorage>,
+        issuer: &KeyPair,
+        issued_for: &KeyPair,
+        issued_at: u64,
+    ) {
+        let trust = Trust::create(
+            issuer,
+            issued_for.public(),
+            Duration::from_secs(1000),
+            Duration::from_secs(issued_at),
+        );
+        graph
+            .add_trust(trust, issuer.public(), Duration::from_secs(issued_at))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_log_proofs() {
+        let log_kp = KeyPair::generate_ed25519();
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_root(root_kp.public(), 10).unwrap();
+        trust(&mut graph, &root_kp, &root_kp, 1);
+        trust(&mut graph, &root_kp, &a_kp, 2);
+        let first = graph
+            .signed_tree_head(&log_kp, Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(first.size, 2);
+        SignedTreeHead::verify(&first).unwrap();
+
+        // the same trust again is not a new entry
+        trust(&mut graph, &root_kp, &a_kp, 2);
+        trust(&mut graph, &a_kp, &b_kp, 3);
+        let revocation = Revocation::create(&root_kp, a_kp.public(), Duration::from_secs(4));
+        graph.revoke(revocation).unwrap();
+        let second = graph
+            .signed_tree_head(&log_kp, Duration::from_secs(4))
+            .unwrap();
+        assert_eq!(second.size, 4);
+
+        let entries = graph.log_entries(0, second.size).unwrap();
+        assert!(entries
+            .iter()
+            .enumerate()
+            .all(|(i, e)| e.sequence == i as u64));
+        assert!(matches!(entries[3].relation, TrustRelation::Revocation(_)));
+
+        for entry in entries.iter() {
+            let path = graph
+                .log_inclusion_proof(entry.sequence, second.size)
+                .unwrap();
+            assert!(verify_log_inclusion(entry, &second, &path));
+        }
+        let path = graph.log_inclusion_proof(1, first.size).unwrap();
+        assert!(verify_log_inclusion(&entries[1], &first, &path));
+        assert!(!verify_log_inclusion(&entries[2], &first, &path));
+
+        let path = graph
+            .log_consistency_proof(first.size, second.size)
+            .unwrap();
+        assert!(verify_log_consistency(&first, &second, &path));
+        let mut forked = second.clone();
+        forked.root = [0; 32];
+        assert!(!verify_log_consistency(&first, &forked, &path));
+
+        assert!(graph.log_inclusion_proof(4, 4).is_err());
+        assert!(graph.log_consistency_proof(3, 5).is_err());
+    }
+
+    #[test]
+    fn test_tampered_tree_head() {
+        let log_kp = KeyPair::generate_ed25519();
+        let head = SignedTreeHead::create(&log_kp, 10, [1; 32], Duration::from_secs(100));
+        SignedTreeHead::verify(&head).unwrap();
+
+        let mut tampered = head.clone();
+        tampered.size = 11;
+        assert!(SignedTreeHead::verify(&tampered).is_err());
+
+        let mut tampered = head;
+        tampered.root = [2; 32];
+        assert!(SignedTreeHead::verify(&tampered).is_err());
+    }
+}
@@ -14,8 +14,13 @@
  * limitations under the License.
  */
 
+use crate::signing::{
+    prefixed_len, push_prefixed, v2_signature_bytes, v3_signature_bytes, PayloadType,
+    SigningVersion,
+};
 use crate::trust::TrustError::{
     Base58DecodeError, DecodeErrorInvalidSize, DecodePublicKeyError, ParseError, SignatureError,
+    UnknownSigningVersion, UnsignedConfidence,
 };
 use derivative::Derivative;
 use fluence_keypair::key_pair::KeyPair;
@@ -30,6 +35,14 @@ use thiserror::Error as ThisError;
 
 pub const EXPIRATION_LEN: usize = 8;
 pub const ISSUED_LEN: usize = 8;
+/// First byte of trusts encoded in the versioned layout, never a length of an encoded public key
+const VERSIONED_LAYOUT: u8 = 0;
+/// Confidence of a trust without an explicit one
+pub const MAX_CONFIDENCE: u8 = u8::MAX;
+
+fn default_confidence() -> u8 {
+    MAX_CONFIDENCE
+}
 
 /// One element in chain of trust in a certificate.
 /// TODO delete pk from Trust (it is already in a trust node)
@@ -47,6 +60,13 @@ pub struct Trust {
     pub signature: Signature,
     /// Creation time of a trust
     pub issued_at: Duration,
+    /// Format of the payload the signature is made over
+    #[serde(default)]
+    pub version: SigningVersion,
+    /// How much the issuer trusts the key, from 0 to `MAX_CONFIDENCE`.
+    /// Signed only since v3, so earlier trusts always have `MAX_CONFIDENCE`
+    #[serde(default = "default_confidence")]
+    pub confidence: u8,
 }
 
 fn show_pubkey(key: &PublicKey, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -90,21 +110,64 @@ pub enum TrustError {
 
     #[error("Cannot decode `{0}` field in the trust: invalid size")]
     DecodeErrorInvalidSize(String),
+
+    #[error("Unknown signing version {0} in the trust")]
+    UnknownSigningVersion(u8),
+
+    #[error("Confidence {0} is not signed by a trust with signing version {1}")]
+    UnsignedConfidence(u8, u8),
 }
 
 impl Trust {
+    /// Creates a trust with a signature over v1 payload.
     #[allow(dead_code)]
     pub fn new(
         issued_for: PublicKey,
         expires_at: Duration,
         issued_at: Duration,
         signature: Signature,
+    ) -> Self {
+        Self::new_with_version(
+            SigningVersion::V1,
+            issued_for,
+            expires_at,
+            issued_at,
+            signature,
+        )
+    }
+
+    pub fn new_with_version(
+        version: SigningVersion,
+        issued_for: PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+        signature: Signature,
+    ) -> Self {
+        Self {
+            issued_for,
+            expires_at,
+            issued_at,
+            signature,
+            version,
+            confidence: MAX_CONFIDENCE,
+        }
+    }
+
+    /// Creates a trust with a signature over v3 payload.
+    pub fn new_with_confidence(
+        issued_for: PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+        confidence: u8,
+        signature: Signature,
     ) -> Self {
         Self {
             issued_for,
             expires_at,
             issued_at,
             signature,
+            version: SigningVersion::V3,
+            confidence,
         }
     }
 
@@ -114,7 +177,7 @@ impl Trust {
         expires_at: Duration,
         issued_at: Duration,
     ) -> Self {
-        let msg = Self::signature_bytes(&issued_for, expires_at, issued_at);
+        let msg = Self::signature_bytes_v2(&issued_for, expires_at, issued_at);
 
         let signature = issued_by.sign(msg.as_slice()).unwrap();
 
@@ -123,9 +186,26 @@ impl Trust {
             expires_at,
             signature,
             issued_at,
+            version: SigningVersion::V2,
+            confidence: MAX_CONFIDENCE,
         }
     }
 
+    /// Creates a trust with partial confidence of the issuer.
+    pub fn create_with_confidence(
+        issued_by: &KeyPair,
+        issued_for: PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+        confidence: u8,
+    ) -> Self {
+        let msg = Self::signature_bytes_v3(&issued_for, expires_at, issued_at, confidence);
+
+        let signature = issued_by.sign(msg.as_slice()).unwrap();
+
+        Self::new_with_confidence(issued_for, expires_at, issued_at, confidence, signature)
+    }
+
     /// Verifies that authorization is cryptographically correct.
     pub fn verify(
         trust: &Trust,
@@ -136,12 +216,37 @@ impl Trust {
             return Err(TrustError::Expired(trust.expires_at, cur_time));
         }
 
-        let msg: &[u8] =
-            &Self::signature_bytes(&trust.issued_for, trust.expires_at, trust.issued_at);
+        if trust.version < SigningVersion::V3 && trust.confidence != MAX_CONFIDENCE {
+            return Err(UnsignedConfidence(trust.confidence, trust.version.to_u8()));
+        }
+
+        let msg: &[u8] = &Self::versioned_signature_bytes(
+            trust.version,
+            &trust.issued_for,
+            trust.expires_at,
+            trust.issued_at,
+            trust.confidence,
+        );
 
         KeyPair::verify(issued_by, msg, &trust.signature).map_err(SignatureError)
     }
 
+    /// `confidence` is a part of the payload only since v3
+    pub fn versioned_signature_bytes(
+        version: SigningVersion,
+        pk: &PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+        confidence: u8,
+    ) -> Vec<u8> {
+        match version {
+            SigningVersion::V1 => Self::signature_bytes(pk, expires_at, issued_at),
+            SigningVersion::V2 => Self::signature_bytes_v2(pk, expires_at, issued_at),
+            SigningVersion::V3 => Self::signature_bytes_v3(pk, expires_at, issued_at, confidence),
+        }
+    }
+
+    /// Legacy v1 payload, kept to verify trusts signed before v2
     pub fn signature_bytes(pk: &PublicKey, expires_at: Duration, issued_at: Duration) -> Vec<u8> {
         let pk_encoded = pk.encode();
         let expires_at_encoded: [u8; EXPIRATION_LEN] = expires_at.as_secs().to_le_bytes();
@@ -155,18 +260,62 @@ impl Trust {
         sha2::Sha256::digest(&metadata).to_vec()
     }
 
-    /// Encode the trust into a byte array
+    pub fn signature_bytes_v2(
+        pk: &PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+    ) -> Vec<u8> {
+        let mut fields = Vec::new();
+
+        push_prefixed(&mut fields, &pk.encode());
+        fields.extend_from_slice(&expires_at.as_secs().to_le_bytes());
+        fields.extend_from_slice(&issued_at.as_secs().to_le_bytes());
+
+        v2_signature_bytes(PayloadType::Trust, &fields)
+    }
+
+    pub fn signature_bytes_v3(
+        pk: &PublicKey,
+        expires_at: Duration,
+        issued_at: Duration,
+        confidence: u8,
+    ) -> Vec<u8> {
+        let mut fields = Vec::new();
+
+        push_prefixed(&mut fields, &pk.encode());
+        fields.extend_from_slice(&expires_at.as_secs().to_le_bytes());
+        fields.extend_from_slice(&issued_at.as_secs().to_le_bytes());
+        fields.push(confidence);
+
+        v3_signature_bytes(PayloadType::Trust, &fields)
+    }
+
+    /// Encode the trust into a byte array.
+    /// v1 trusts keep the legacy layout with 1 byte lengths of the key and the signature.
+    /// Later versions start with `VERSIONED_LAYOUT` and the signing version,
+    /// and prefix the key and the signature with 2 bytes lengths, so RSA keys fit.
+    /// Confidence follows the issue date since v3
     #[allow(dead_code)]
     pub fn encode(&self) -> Vec<u8> {
         let mut vec = Vec::new();
-        let mut issued_for = self.issued_for.encode();
-        let mut signature = self.signature.encode();
-        vec.push(issued_for.len() as u8);
-        vec.append(&mut issued_for);
-        vec.push(signature.len() as u8);
-        vec.append(&mut signature);
+        let issued_for = self.issued_for.encode();
+        let signature = self.signature.encode();
+        if self.version == SigningVersion::V1 {
+            vec.push(issued_for.len() as u8);
+            vec.extend(issued_for);
+            vec.push(signature.len() as u8);
+            vec.extend(signature);
+        } else {
+            vec.push(VERSIONED_LAYOUT);
+            vec.push(self.version.to_u8());
+            push_prefixed(&mut vec, &issued_for);
+            push_prefixed(&mut vec, &signature);
+        }
         vec.extend_from_slice(&self.expires_at.as_secs().to_le_bytes());
         vec.extend_from_slice(&self.issued_at.as_secs().to_le_bytes());
+        if self.version >= SigningVersion::V3 {
+            vec.push(self.confidence);
+        }
 
         vec
     }
@@ -179,25 +328,44 @@ impl Trust {
         }
     }
 
+    /// Takes a field prefixed by its little-endian length of `len_size` bytes
+    fn take_prefixed<'a>(
+        arr: &'a [u8],
+        offset: &mut usize,
+        len_size: usize,
+        field_name: &str,
+    ) -> Result<&'a [u8], TrustError> {
+        Self::check_arr_len(arr, &format!("{field_name}_len"), *offset + len_size)?;
+        let len = prefixed_len(&arr[*offset..*offset + len_size]);
+        *offset += len_size;
+
+        Self::check_arr_len(arr, field_name, *offset + len)?;
+        let field = &arr[*offset..*offset + len];
+        *offset += len;
+
+        Ok(field)
+    }
+
     /// Decode a trust from a byte array as produced by `encode`.
     #[allow(dead_code)]
     pub fn decode(arr: &[u8]) -> Result<Self, TrustError> {
         Self::check_arr_len(arr, "public_key_len", 1)?;
-        let pk_len = arr[0] as usize;
-        let mut offset = 1;
-
-        Self::check_arr_len(arr, "public_key", offset + pk_len)?;
-        let pk = PublicKey::decode(&arr[offset..offset + pk_len])?;
-        offset += pk_len;
-
-        Self::check_arr_len(arr, "signature_size", offset + 1)?;
-        let signature_len = arr[offset] as usize;
-        offset += 1;
-
-        Self::check_arr_len(arr, "signature", offset + signature_len)?;
-        let signature = &arr[offset..offset + signature_len];
+        let (version, len_size, mut offset) = if arr[0] == VERSIONED_LAYOUT {
+            Self::check_arr_len(arr, "signing_version", 2)?;
+            let version = SigningVersion::from_u8(arr[1]).ok_or(UnknownSigningVersion(arr[1]))?;
+            (version, 2, 2)
+        } else {
+            (SigningVersion::V1, 1, 0)
+        };
+
+        let pk = PublicKey::decode(Self::take_prefixed(
+            arr,
+            &mut offset,
+            len_size,
+            "public_key",
+        )?)?;
+        let signature = Self::take_prefixed(arr, &mut offset, len_size, "signature")?;
         let signature = Signature::decode(signature.to_vec())?;
-        offset += signature_len;
 
         Self::check_arr_len(arr, "expiration", offset + EXPIRATION_LEN)?;
         let expiration_bytes = &arr[offset..offset + EXPIRATION_LEN];
@@ -206,15 +374,25 @@ impl Trust {
         offset += EXPIRATION_LEN;
 
         Self::check_arr_len(arr, "issued", offset + ISSUED_LEN)?;
-        let issued_bytes = &arr[offset..];
+        let issued_bytes = &arr[offset..offset + ISSUED_LEN];
         let issued_date = u64::from_le_bytes(issued_bytes.try_into().unwrap());
         let issued_date = Duration::from_secs(issued_date);
+        offset += ISSUED_LEN;
+
+        let confidence = match (version >= SigningVersion::V3, &arr[offset..]) {
+            (false, []) => MAX_CONFIDENCE,
+            (true, [confidence]) => *confidence,
+            (false, _) => return Err(DecodeErrorInvalidSize("issued".to_string())),
+            (true, _) => return Err(DecodeErrorInvalidSize("confidence".to_string())),
+        };
 
         Ok(Self {
             issued_for: pk,
             signature,
             expires_at: expiration_date,
             issued_at: issued_date,
+            version,
+            confidence,
         })
     }
 
@@ -270,6 +448,7 @@ impl ToString for Trust {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fluence_keypair::KeyFormat;
 
     #[test]
     fn test_gen_revoke_and_validate_ed25519() {
@@ -324,4 +503,140 @@ mod tests {
 
         assert_eq!(trust, decoded);
     }
+
+    #[test]
+    fn test_verify_v1_trust_ed25519() {
+        let truster = KeyPair::generate_ed25519();
+        let trusted = KeyPair::generate_ed25519();
+
+        let current = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+        let issued_at = Duration::new(10, 0);
+
+        let msg = Trust::signature_bytes(&trusted.public(), expires_at, issued_at);
+        let signature = truster.sign(&msg).unwrap();
+        let trust = Trust::new(trusted.public(), expires_at, issued_at, signature.clone());
+
+        assert!(Trust::verify(&trust, &truster.public(), current).is_ok());
+
+        // v1 signature is not valid as v2 one
+        let trust = Trust::new_with_version(
+            SigningVersion::V2,
+            trusted.public(),
+            expires_at,
+            issued_at,
+            signature,
+        );
+        assert!(Trust::verify(&trust, &truster.public(), current).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_v1_ed25519() {
+        let truster = KeyPair::generate_ed25519();
+        let trusted = KeyPair::generate_ed25519();
+
+        let expires_at = Duration::new(1000, 0);
+        let issued_at = Duration::new(10, 0);
+
+        let msg = Trust::signature_bytes(&trusted.public(), expires_at, issued_at);
+        let signature = truster.sign(&msg).unwrap();
+        let trust = Trust::new(trusted.public(), expires_at, issued_at, signature);
+
+        let encoded = trust.encode();
+        let v2_encoded = Trust::create(&truster, trusted.public(), expires_at, issued_at).encode();
+        assert_ne!(encoded[0], VERSIONED_LAYOUT);
+        assert_eq!(
+            v2_encoded[..2],
+            [VERSIONED_LAYOUT, SigningVersion::V2.to_u8()]
+        );
+
+        let decoded = Trust::decode(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.version, SigningVersion::V1);
+        assert_eq!(trust, decoded);
+    }
+
+    #[test]
+    fn test_trust_with_confidence_ed25519() {
+        let truster = KeyPair::generate_ed25519();
+        let trusted = KeyPair::generate_ed25519();
+
+        let current = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+        let issued_at = Duration::new(10, 0);
+
+        let trust =
+            Trust::create_with_confidence(&truster, trusted.public(), expires_at, issued_at, 128);
+        assert_eq!(trust.version, SigningVersion::V3);
+        assert!(Trust::verify(&trust, &truster.public(), current).is_ok());
+
+        let decoded = Trust::decode(trust.encode().as_slice()).unwrap();
+        assert_eq!(trust, decoded);
+
+        // confidence is signed
+        let mut corrupted = trust.clone();
+        corrupted.confidence = MAX_CONFIDENCE;
+        assert!(Trust::verify(&corrupted, &truster.public(), current).is_err());
+
+        // and can't be attached to a trust signed before v3
+        let mut corrupted = Trust::create(&truster, trusted.public(), expires_at, issued_at);
+        corrupted.confidence = 128;
+        assert!(matches!(
+            Trust::verify(&corrupted, &truster.public(), current),
+            Err(UnsignedConfidence(128, 2))
+        ));
+    }
+
+    #[test]
+    fn test_encode_decode_rsa() {
+        let rsa_key = include_bytes!("../../keypair/src/test/rsa-2048.pk8");
+        let truster = KeyPair::from_pkcs8_der(rsa_key).unwrap();
+        let trusted = KeyPair::from_pkcs8_der(rsa_key).unwrap();
+
+        let current = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+        let issued_at = Duration::new(10, 0);
+
+        // RSA keys and signatures are longer than 255 bytes
+        assert!(trusted.public().encode().len() > u8::MAX as usize);
+        for trust in [
+            Trust::create(&truster, trusted.public(), expires_at, issued_at),
+            Trust::create_with_confidence(&truster, trusted.public(), expires_at, issued_at, 128),
+        ] {
+            assert!(Trust::verify(&trust, &truster.public(), current).is_ok());
+            let decoded = Trust::decode(trust.encode().as_slice()).unwrap();
+            assert_eq!(trust, decoded);
+        }
+    }
+
+    #[test]
+    fn test_eip191_trust_secp256k1() {
+        let wallet = KeyPair::generate_secp256k1();
+        let trusted = KeyPair::generate_ed25519();
+
+        let current = Duration::new(100, 0);
+        let expires_at = Duration::new(1000, 0);
+        let issued_at = Duration::new(10, 0);
+
+        // a wallet signs the payload with personal_sign
+        let msg = Trust::signature_bytes_v2(&trusted.public(), expires_at, issued_at);
+        let signature = wallet.sign_eip191(&msg).unwrap();
+        let trust = Trust::new_with_version(
+            SigningVersion::V2,
+            trusted.public(),
+            expires_at,
+            issued_at,
+            signature,
+        );
+        assert!(Trust::verify(&trust, &wallet.public(), current).is_ok());
+        assert!(Trust::verify(&trust, &KeyPair::generate_secp256k1().public(), current).is_err());
+
+        let decoded = Trust::decode(trust.encode().as_slice()).unwrap();
+        assert_eq!(trust, decoded);
+
+        // EIP-191 signature isn't a valid plain secp256k1 one
+        let mut corrupted = trust.clone();
+        corrupted.signature =
+            Signature::from_bytes(KeyFormat::Secp256k1, trust.signature.to_vec().to_vec());
+        assert!(Trust::verify(&corrupted, &wallet.public(), current).is_err());
+    }
 }
@@ -14,23 +14,43 @@
  * limitations under the License.
  */
 
+use crate::attestation::WeightAttestation;
 use crate::certificate::CertificateError::CertificateLengthError;
 use crate::certificate::{Certificate, CertificateError};
 use crate::chain::Chain;
+use crate::distrust::{Distrust, DistrustError, DistrustPolicy};
+use crate::export::{render, GraphFormat};
+use crate::gc::{GcPolicy, GcReport, GcSchedule};
+use crate::key_compromise::{KeyCompromise, KeyCompromiseError};
+use crate::merkle::{consistency_path, inclusion_path, root, Hash};
+use crate::pending::{PendingPoolConfig, PendingTrust};
 use crate::public_key_hashable::PublicKeyHashable as PK;
+use crate::quota::{EvictionPolicy, QuotaKind, Quotas};
+use crate::reputation::{compute, Reputation, ReputationConfig};
+use crate::revocation_list::{RevocationList, RevocationListError};
 use crate::revoke::Revocation;
 use crate::revoke::RevokeError;
-use crate::trust::Trust;
+use crate::state::{self, AbsenceProof, LeafId, NonRevocationProof, StateLeaf, StateProof};
+use crate::stats::GraphStats;
+use crate::sync::{
+    bucket_digests, diff_buckets, summarize, RelationDigest, SyncReport, SyncSummary, SyncTransport,
+};
+use crate::threshold::{ThresholdAnchor, ThresholdError, ThresholdTrust};
+use crate::transparency_log::{LogEntry, SignedTreeHead};
+use crate::trust::{Trust, MAX_CONFIDENCE};
 use crate::trust_graph::TrustGraphError::{
-    CertificateCheckError, EmptyChain, InternalStorageError, NoRoot,
+    CertificateCheckError, EmptyChain, InternalStorageError, InvalidLogRange, NoRoot,
+    OutdatedRevocationList, QuotaExceeded, SyncTransportError, UnknownThresholdAnchor,
 };
 use crate::trust_graph_storage::Storage;
-use crate::trust_relation::Auth;
+use crate::trust_relation::{Auth, TrustRelation};
+use crate::weight_proof::{non_adjacent_pairs, WeightProof};
 use crate::{StorageError, TrustError};
+use fluence_keypair::key_pair::KeyPair;
 use fluence_keypair::public_key::PublicKey;
 use nonempty::NonEmpty;
 use std::borrow::Borrow;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::{From, Into};
 use std::result::Result;
 use std::time::Duration;
@@ -41,6 +61,18 @@ pub type WeightFactor = u32;
 
 pub static MAX_WEIGHT_FACTOR: u32 = 16;
 
+/// Inclusion proofs of the certificate trusts and non-revocation proofs of its keys
+type CertificateProofs = (Vec<StateProof>, Vec<NonRevocationProof>);
+
+/// Key reachable from the roots with its best weight
+#[derive(Clone, Debug)]
+pub struct TrustedKey {
+    pub pk: PublicKey,
+    pub weight: u32,
+    /// certificate that gives the weight, None if the key is a root itself
+    pub certificate: Option<Certificate>,
+}
+
 /// Graph to efficiently calculate weights of certificates and get chains of certificates.
 /// TODO serialization/deserialization
 /// TODO export a certificate from graph
@@ -50,6 +82,16 @@ where
     S: Storage,
 {
     storage: S,
+    distrust_policy: DistrustPolicy,
+    distrust_radius: u32,
+    gc_schedule: GcSchedule,
+    last_gc: Option<Duration>,
+    quotas: Quotas,
+    pending_pool: Option<PendingPoolConfig>,
+    /// whether the state tree is stored, it's built on the first request of the state root
+    state_stored: bool,
+    /// leaf hashes of the log, loaded on the first request of a tree head or a proof
+    log_leaves: Option<Vec<Hash>>,
 }
 
 #[derive(ThisError, Debug)]
@@ -80,6 +122,40 @@ pub enum TrustGraphError {
         #[source]
         TrustError,
     ),
+    #[error("Revocation list check error: {0}")]
+    RevocationListCheckError(
+        #[from]
+        #[source]
+        RevocationListError,
+    ),
+    #[error("Revocation list with sequence {0} is outdated, current sequence is {1}")]
+    OutdatedRevocationList(u64, u64),
+    #[error("Key compromise check error: {0}")]
+    KeyCompromiseCheckError(
+        #[from]
+        #[source]
+        KeyCompromiseError,
+    ),
+    #[error("Threshold trust check error: {0}")]
+    ThresholdCheckError(
+        #[from]
+        #[source]
+        ThresholdError,
+    ),
+    #[error("Threshold anchor {0} is not set as a root")]
+    UnknownThresholdAnchor(String),
+    #[error("Error on distrusting a key: {0}")]
+    DistrustCheckError(
+        #[from]
+        #[source]
+        DistrustError,
+    ),
+    #[error("Key {1} has reached the quota of {2} {0} trusts")]
+    QuotaExceeded(QuotaKind, String, u32),
+    #[error("Sync transport error: {0}")]
+    SyncTransportError(Box<dyn std::error::Error>),
+    #[error("Invalid log range {0}..{1}, log size is {2}")]
+    InvalidLogRange(u64, u64, u64),
 }
 
 impl<T: StorageError + 'static> From<T> for TrustGraphError {
@@ -102,21 +178,161 @@ pub fn get_weight_from_factor(wf: WeightFactor) -> u32 {
     2u32.pow(MAX_WEIGHT_FACTOR.saturating_sub(wf))
 }
 
+/// Auths are removed by gc once they expire
+fn is_expired(expires_at: Duration, cur_time: Duration) -> bool {
+    expires_at <= cur_time
+}
+
+/// Multiply weight by confidence of every trust in the chain
+pub(crate) fn apply_confidence(weight: u32, chain: &[Trust]) -> u32 {
+    chain.iter().fold(weight, |weight, trust| {
+        (u64::from(weight) * u64::from(trust.confidence) / u64::from(MAX_CONFIDENCE)) as u32
+    })
+}
+
 impl<S> TrustGraph<S>
 where
     S: Storage,
 {
     pub fn new(storage: S) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            distrust_policy: DistrustPolicy::default(),
+            distrust_radius: 0,
+            gc_schedule: GcSchedule::default(),
+            last_gc: None,
+            quotas: Quotas::default(),
+            pending_pool: None,
+            state_stored: false,
+            log_leaves: None,
+        }
+    }
+
+    /// Storage of the graph, e.g. to keep data of the embedding service next to it
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Enable or disable the pool of trusts from issuers without weight.
+    /// Pending trusts are added to the graph once their issuer gains weight.
+    pub fn set_pending_pool(&mut self, config: Option<PendingPoolConfig>) {
+        self.pending_pool = config;
+    }
+
+    /// Set limits on the number of stored trusts per key
+    pub fn set_quotas(&mut self, quotas: Quotas) {
+        self.quotas = quotas;
+    }
+
+    /// Set when garbage collection runs besides explicit `gc` calls
+    pub fn set_gc_schedule(&mut self, schedule: GcSchedule) {
+        self.gc_schedule = schedule;
+    }
+
+    /// Time of the last garbage collection, `Every` schedule counts its interval from it
+    pub fn last_gc(&self) -> Option<Duration> {
+        self.last_gc
+    }
+
+    /// Restore the time of the last garbage collection, e.g. after a restart
+    pub fn set_last_gc(&mut self, last_gc: Option<Duration>) {
+        self.last_gc = last_gc;
+    }
+
+    /// Set how distrusts affect weights of certificates.
+    /// A distrust of a key in a certificate applies if its issuer is in the certificate
+    /// or trusts one of the certificate keys through at most `radius` trusts.
+    pub fn set_distrust_policy(&mut self, policy: DistrustPolicy, radius: u32) {
+        self.distrust_policy = policy;
+        self.distrust_radius = radius;
     }
 
     /// Insert new root weight
     pub fn set_root(&mut self, pk: PublicKey, max_chain_len: u32) -> Result<(), TrustGraphError> {
+        let pk: PK = pk.into();
+        let weight_factor = get_weight_factor(max_chain_len);
+        self.storage
+            .set_root_weight_factor(pk.clone(), weight_factor)?;
+        if self.is_state_stored()? {
+            state::set_root(&mut self.storage, &pk, weight_factor)?;
+        }
+
+        Ok(())
+    }
+
+    /// Insert new threshold anchor weight.
+    /// Keys certified by the anchor get one more weight factor than the anchor itself
+    pub fn set_threshold_root(
+        &mut self,
+        anchor: ThresholdAnchor,
+        max_chain_len: u32,
+    ) -> Result<(), TrustGraphError> {
+        Ok(self
+            .storage
+            .set_threshold_anchor_weight_factor(anchor, get_weight_factor(max_chain_len))?)
+    }
+
+    /// Add a trust signed by a threshold anchor, its subject acts as a root from now on.
+    /// Self-signed trust of the subject should be added in next call, same as for usual roots
+    pub fn add_threshold_trust(
+        &mut self,
+        trust: ThresholdTrust,
+        cur_time: Duration,
+    ) -> Result<u32, TrustGraphError> {
+        ThresholdTrust::verify(&trust, cur_time)?;
+        let weight_factor = self
+            .anchored_root_weight_factor(&trust, cur_time)?
+            .ok_or_else(|| UnknownThresholdAnchor(trust.anchor.id()))?;
+
+        // keep only the latest threshold trust, same as for usual trusts
+        let current = self
+            .storage
+            .get_threshold_trust(trust.issued_for.as_ref())?;
+        match current {
+            Some(c) if c.issued_at >= trust.issued_at => {}
+            _ => self.storage.set_threshold_trust(trust)?,
+        }
+
+        Ok(get_weight_from_factor(weight_factor))
+    }
+
+    /// Weight factor of a key certified by the threshold trust,
+    /// None if the trust is expired or its anchor is not a root
+    fn anchored_root_weight_factor(
+        &self,
+        trust: &ThresholdTrust,
+        cur_time: Duration,
+    ) -> Result<Option<WeightFactor>, TrustGraphError> {
+        if trust.expires_at < cur_time {
+            return Ok(None);
+        }
+
         Ok(self
             .storage
-            .set_root_weight_factor(pk.into(), get_weight_factor(max_chain_len))?)
+            .get_threshold_anchor_weight_factor(&trust.anchor)?
+            .map(|wf| wf + 1))
+    }
+
+    /// Threshold trusts that currently make their subjects roots
+    fn valid_threshold_trusts(
+        &self,
+        cur_time: Duration,
+    ) -> Result<Vec<ThresholdTrust>, TrustGraphError> {
+        let mut trusts = vec![];
+        for trust in self.storage.threshold_trusts()? {
+            if self
+                .anchored_root_weight_factor(&trust, cur_time)?
+                .is_some()
+            {
+                trusts.push(trust);
+            }
+        }
+
+        Ok(trusts)
     }
 
+    /// Add trust to the graph, returns weight it gives to `issued_for`.
+    /// Trust from an issuer without weight gives 0, it's put into the pending pool if the pool is enabled.
     pub fn add_trust<T, P>(
         &mut self,
         trust: T,
@@ -127,27 +343,129 @@ where
         T: Borrow<Trust>,
         P: Borrow<PublicKey>,
     {
-        Trust::verify(trust.borrow(), issued_by.borrow(), cur_time)?;
-        let next_weight = self.get_next_weight(
-            issued_by.borrow().as_ref(),
-            trust.borrow().issued_for.as_ref(),
-            cur_time,
-        )?;
+        let weight = self.add_trust_unpromoted(trust.borrow(), issued_by.borrow(), cur_time)?;
+        if weight > 0 {
+            self.promote_pending(trust.borrow().issued_for.as_ref(), cur_time)?;
+        }
 
-        if next_weight == 0u32 {
+        Ok(weight)
+    }
+
+    fn add_trust_unpromoted(
+        &mut self,
+        trust: &Trust,
+        issued_by: &PublicKey,
+        cur_time: Duration,
+    ) -> Result<u32, TrustGraphError> {
+        Trust::verify(trust, issued_by, cur_time)?;
+
+        // trusts signed by a compromised key after the compromise are ignored anyway
+        if self.is_void(issued_by.as_ref(), trust.issued_at)? {
             return Ok(0u32);
         }
 
+        let next_weight =
+            self.get_next_weight(issued_by.as_ref(), trust.issued_for.as_ref(), cur_time)?;
+
         let auth = Auth {
-            trust: trust.borrow().clone(),
-            issued_by: issued_by.borrow().clone(),
+            trust: trust.clone(),
+            issued_by: issued_by.clone(),
         };
 
+        if next_weight == 0u32 {
+            self.add_pending(auth, cur_time)?;
+            return Ok(0u32);
+        }
+
+        self.enforce_quotas(&auth)?;
+        let relation = TrustRelation::Auth(auth.clone());
+        let needs_logging = self.needs_logging(&relation)?;
         self.storage.update_auth(auth, cur_time)?;
+        self.relation_written(relation, needs_logging)?;
 
         Ok(next_weight)
     }
 
+    /// Put auth into the pending pool, evicting stale and the earliest added trusts
+    fn add_pending(&mut self, auth: Auth, cur_time: Duration) -> Result<(), TrustGraphError> {
+        let config = match self.pending_pool {
+            Some(config) if auth.issued_by != auth.trust.issued_for => config,
+            // self-signed trust gets weight only with the key becoming a root
+            _ => return Ok(()),
+        };
+
+        let mut pool = vec![];
+        for pending in self.storage.get_all_pending()? {
+            let same_keys = pending.auth.issued_by == auth.issued_by
+                && pending.auth.trust.issued_for == auth.trust.issued_for;
+            if same_keys && pending.auth.trust.issued_at >= auth.trust.issued_at {
+                return Ok(());
+            }
+
+            if same_keys || pending.is_stale(config.ttl, cur_time) {
+                self.remove_pending(&pending)?;
+            } else {
+                pool.push(pending);
+            }
+        }
+
+        if config.max_size == 0 {
+            return Ok(());
+        }
+
+        pool.sort_by_key(|p| p.added_at);
+        let excess = (pool.len() + 1).saturating_sub(config.max_size as usize);
+        for pending in pool.iter().take(excess) {
+            self.remove_pending(pending)?;
+        }
+
+        Ok(self.storage.insert_pending(PendingTrust {
+            auth,
+            added_at: cur_time,
+        })?)
+    }
+
+    fn remove_pending(&mut self, pending: &PendingTrust) -> Result<(), TrustGraphError> {
+        Ok(self.storage.remove_pending(
+            pending.auth.trust.issued_for.as_ref(),
+            pending.auth.issued_by.as_ref(),
+        )?)
+    }
+
+    /// Add pending trusts issued by `pk` that has gained weight,
+    /// and then pending trusts issued by keys that have gained weight from them
+    fn promote_pending(&mut self, pk: &PK, cur_time: Duration) -> Result<(), TrustGraphError> {
+        let ttl = match self.pending_pool {
+            Some(config) => config.ttl,
+            None => return Ok(()),
+        };
+
+        let mut queue = VecDeque::from([pk.clone()]);
+        while let Some(pk) = queue.pop_front() {
+            for pending in self.storage.get_pending(&pk)? {
+                self.remove_pending(&pending)?;
+                if pending.is_stale(ttl, cur_time) {
+                    continue;
+                }
+
+                let issued_for = pending.auth.trust.issued_for.clone();
+                match self.add_trust_unpromoted(
+                    &pending.auth.trust,
+                    &pending.auth.issued_by,
+                    cur_time,
+                ) {
+                    Ok(weight) if weight > 0 => queue.push_back(issued_for.into()),
+                    Ok(_) => {}
+                    Err(e @ InternalStorageError(_)) => return Err(e),
+                    // trust that has become invalid while pending is dropped
+                    Err(_) => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Certificate is a chain of trusts, add this chain to graph
     pub fn add<C>(&mut self, cert: C, cur_time: Duration) -> Result<(), TrustGraphError>
     where
@@ -165,6 +483,83 @@ where
         Ok(())
     }
 
+    /// Make room for the new auth or reject it if its issuer or subject has too many auths.
+    /// Auth that replaces an existing one between the same keys doesn't count.
+    fn enforce_quotas(&mut self, auth: &Auth) -> Result<(), TrustGraphError> {
+        let issued_by: &PK = auth.issued_by.as_ref();
+        let issued_for: &PK = auth.trust.issued_for.as_ref();
+        if issued_by == issued_for {
+            return Ok(());
+        }
+
+        let mut evicted = vec![];
+        if let Some(max_issued) = self.quotas.max_issued {
+            let issued: Vec<Auth> = self
+                .storage
+                .get_issued_by(issued_by)?
+                .into_iter()
+                .filter_map(|r| match r {
+                    TrustRelation::Auth(a) => Some(a),
+                    _ => None,
+                })
+                .filter(|a| {
+                    a.trust.issued_for != a.issued_by && a.trust.issued_for != auth.trust.issued_for
+                })
+                .collect();
+            evicted.extend(self.check_quota(QuotaKind::Issued, issued_by, issued, max_issued)?);
+        }
+
+        if let Some(max_received) = self.quotas.max_received {
+            let received: Vec<Auth> = self
+                .storage
+                .get_authorizations(issued_for)?
+                .into_iter()
+                .filter(|a| a.issued_by != auth.trust.issued_for && a.issued_by != auth.issued_by)
+                .collect();
+            evicted.extend(self.check_quota(
+                QuotaKind::Received,
+                issued_for,
+                received,
+                max_received,
+            )?);
+        }
+
+        // nothing is removed if any of quotas rejects the auth
+        for a in evicted {
+            self.storage
+                .remove_relation(a.trust.issued_for.as_ref(), a.issued_by.as_ref())?;
+            self.refresh_state(a.trust.issued_for.as_ref(), a.issued_by.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns auths to evict to fit one more auth into the quota
+    fn check_quota(
+        &self,
+        kind: QuotaKind,
+        pk: &PK,
+        mut auths: Vec<Auth>,
+        max: u32,
+    ) -> Result<Vec<Auth>, TrustGraphError> {
+        if auths.len() < max as usize {
+            return Ok(vec![]);
+        }
+
+        // there is nothing to evict if no trusts are allowed at all
+        if self.quotas.eviction == EvictionPolicy::Reject || max == 0 {
+            return Err(QuotaExceeded(
+                kind,
+                pk.as_ref().to_peer_id().to_base58(),
+                max,
+            ));
+        }
+
+        auths.sort_by_key(|a| a.trust.issued_at);
+        auths.truncate(auths.len() + 1 - max as usize);
+        Ok(auths)
+    }
+
     fn get_next_weight(
         &mut self,
         issued_by: &PK,
@@ -191,15 +586,228 @@ where
             max_weight = get_weight_from_factor(weight_factor);
         }
 
+        if let Some(trust) = self.storage.get_threshold_trust(pk.borrow().as_ref())? {
+            if let Some(weight_factor) = self.anchored_root_weight_factor(&trust, cur_time)? {
+                max_weight = std::cmp::max(max_weight, get_weight_from_factor(weight_factor));
+            }
+        }
+
         // get all possible certificates from the given public key to all roots in the graph
         let certs = self.get_all_certs(pk, cur_time)?;
-        if let Some(weight_factor) = self.certificates_weight_factor(certs)? {
-            max_weight = std::cmp::max(max_weight, get_weight_from_factor(weight_factor))
+        if let Some(weight) = self.certificates_weight(certs, cur_time)? {
+            max_weight = std::cmp::max(max_weight, weight)
         }
 
         Ok(max_weight)
     }
 
+    /// Proof of the weight of the key that can be checked with `verify_weight_proof`
+    /// without access to the graph. None if the key has no weight from roots or certificates.
+    pub fn prove_weight<P>(
+        &mut self,
+        pk: P,
+        cur_time: Duration,
+    ) -> Result<Option<WeightProof>, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        let pk = pk.borrow();
+        let root_weight = self
+            .storage
+            .get_root_weight_factor(pk.as_ref())?
+            .map(get_weight_from_factor);
+        let anchored = match self.storage.get_threshold_trust(pk.as_ref())? {
+            Some(trust) => self
+                .anchored_root_weight_factor(&trust, cur_time)?
+                .map(|wf| (get_weight_from_factor(wf), trust)),
+            None => None,
+        };
+        let mut best: Option<(u32, Option<Certificate>)> =
+            std::cmp::max(root_weight, anchored.as_ref().map(|(w, _)| *w)).map(|w| (w, None));
+
+        let mut hops = vec![];
+        let mut non_revocations = vec![];
+        for cert in self.get_all_certs(pk, cur_time)? {
+            if let Some(weight_factor) = self.certificate_weight_factor(&cert, cur_time)? {
+                let weight = apply_confidence(get_weight_from_factor(weight_factor), &cert.chain);
+                if best.as_ref().is_some_and(|(b, _)| weight <= *b) {
+                    continue;
+                }
+                // the weight is proven only by a certificate the state proofs can be made for
+                if let Some((cert_hops, cert_non_revocations)) = self.prove_certificate(&cert)? {
+                    best = Some((weight, Some(cert)));
+                    hops = cert_hops;
+                    non_revocations = cert_non_revocations;
+                }
+            }
+        }
+
+        let (weight, certificate) = match best {
+            Some(best) => best,
+            None => return Ok(None),
+        };
+        // the anchor trust is needed only if the key itself is the source of the weight
+        let anchor_trust = match (&certificate, anchored) {
+            (None, Some((anchored_weight, trust))) if anchored_weight == weight => Some(trust),
+            _ => None,
+        };
+
+        let mut roots = vec![];
+        for root in self.storage.root_keys()? {
+            if let Some(weight_factor) = self.storage.get_root_weight_factor(&root)? {
+                roots.push((root.into(), weight_factor));
+            }
+        }
+
+        let mut anchors = vec![];
+        let anchor = certificate
+            .as_ref()
+            .and_then(|cert| cert.anchor_trust.as_ref())
+            .or(anchor_trust.as_ref())
+            .map(|trust| &trust.anchor);
+        if let Some(anchor) = anchor {
+            if let Some(weight_factor) = self.storage.get_threshold_anchor_weight_factor(anchor)? {
+                anchors.push((anchor.clone(), weight_factor));
+            }
+        }
+
+        Ok(Some(WeightProof {
+            pk: pk.clone(),
+            weight,
+            certificate,
+            anchor_trust,
+            roots,
+            anchors,
+            state_root: state::state_root(self.state()?)?,
+            hops,
+            non_revocations,
+        }))
+    }
+
+    /// Inclusion proofs of the certificate trusts and proofs that no key of the certificate
+    /// is revoked by an earlier one, None if some of them can't be made for the current state
+    fn prove_certificate(
+        &mut self,
+        cert: &Certificate,
+    ) -> Result<Option<CertificateProofs>, TrustGraphError> {
+        let mut hops = vec![];
+        for (i, trust) in cert.chain.iter().enumerate() {
+            let relation = TrustRelation::Auth(Auth {
+                trust: trust.clone(),
+                issued_by: cert.chain[i.saturating_sub(1)].issued_for.clone(),
+            });
+            match state::prove(self.state()?, &relation)? {
+                Some(hop) => hops.push(hop),
+                None => return Ok(None),
+            }
+        }
+
+        let mut non_revocations = vec![];
+        for (earlier, later) in non_adjacent_pairs(cert.chain.len()) {
+            let proof = self.prove_non_revocation(
+                &cert.chain[later].issued_for,
+                &cert.chain[earlier].issued_for,
+            )?;
+            match proof {
+                Some(proof) => non_revocations.push(proof),
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some((hops, non_revocations)))
+    }
+
+    /// Proof that there is no revocation of `issued_for` by `issued_by` in the current state,
+    /// None if there is one
+    pub fn prove_non_revocation(
+        &mut self,
+        issued_for: &PublicKey,
+        issued_by: &PublicKey,
+    ) -> Result<Option<NonRevocationProof>, TrustGraphError> {
+        let proof = match self
+            .storage
+            .get_relation(issued_for.as_ref(), issued_by.as_ref())?
+        {
+            Some(TrustRelation::Revocation(_)) => return Ok(None),
+            Some(relation) => match state::prove(self.state()?, &relation)? {
+                Some(proof) => NonRevocationProof::Relation(relation, proof),
+                None => return Ok(None),
+            },
+            None => {
+                let AbsenceProof {
+                    prev,
+                    next,
+                    bucket_path,
+                } = match state::prove_absence(
+                    self.state()?,
+                    issued_for.as_ref(),
+                    issued_by.as_ref(),
+                )? {
+                    Some(absence) => absence,
+                    None => return Ok(None),
+                };
+
+                NonRevocationProof::Absent {
+                    prev: self.state_leaf(prev)?,
+                    next: self.state_leaf(next)?,
+                    bucket_path,
+                }
+            }
+        };
+
+        // leaves are loaded from the storage, so check they are the ones of the state
+        let state_root = state::state_root(self.state()?)?;
+        Ok(proof
+            .verify(issued_for, issued_by, &state_root)
+            .then_some(proof))
+    }
+
+    fn state_leaf(
+        &self,
+        leaf: Option<(LeafId, StateProof)>,
+    ) -> Result<Option<(StateLeaf, StateProof)>, TrustGraphError> {
+        let (id, proof) = match leaf {
+            Some(leaf) => leaf,
+            None => return Ok(None),
+        };
+
+        let leaf = match id {
+            LeafId::Root(pk) => self
+                .storage
+                .get_root_weight_factor(&pk)?
+                .map(|wf| StateLeaf::Root(pk.into(), wf)),
+            LeafId::Relation {
+                issued_for,
+                issued_by,
+            } => self
+                .storage
+                .get_relation(&issued_for, &issued_by)?
+                .map(|relation| StateLeaf::Relation(Box::new(relation))),
+        };
+
+        Ok(leaf.map(|leaf| (leaf, proof)))
+    }
+
+    /// Sign the current weight of the key with the host key pair
+    pub fn attest_weight<P>(
+        &mut self,
+        host: &KeyPair,
+        pk: P,
+        cur_time: Duration,
+    ) -> Result<WeightAttestation, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        let weight = self.weight(pk.borrow(), cur_time)?;
+
+        Ok(WeightAttestation::create(
+            host,
+            pk.borrow().clone(),
+            weight,
+            cur_time,
+        ))
+    }
+
     /// Get the maximum weight of trust for one public key.
     /// for all chains which contain `issuer`
     pub fn weight_from<P>(
@@ -216,101 +824,248 @@ where
         // get all possible certificates from the given public key to all roots in the graph
         // which contain `issuer`
         let certs = self.get_all_certs_from(issued_for, issuer, cur_time)?;
-        if let Some(weight_factor) = self.certificates_weight_factor(certs)? {
-            max_weight = std::cmp::max(max_weight, get_weight_from_factor(weight_factor))
+        if let Some(weight) = self.certificates_weight(certs, cur_time)? {
+            max_weight = std::cmp::max(max_weight, weight)
         }
 
         Ok(max_weight)
     }
 
+    /// Calculate weight from given certificates,
+    /// weight of every certificate is scaled by confidences of its trusts.
+    /// Returns None if there is no such public key,
+    /// some trust between this key and a root key is revoked
+    /// or every certificate is vetoed by a distrust or has an expired anchor trust.
+    pub fn certificates_weight<C, I>(
+        &self,
+        certs: I,
+        cur_time: Duration,
+    ) -> Result<Option<u32>, TrustGraphError>
+    where
+        C: Borrow<Certificate>,
+        I: IntoIterator<Item = C>,
+    {
+        let mut weight = None;
+
+        for cert in certs {
+            let c = cert.borrow();
+            if let Some(weight_factor) = self.certificate_weight_factor(c, cur_time)? {
+                let cert_weight = apply_confidence(get_weight_from_factor(weight_factor), &c.chain);
+                weight = std::cmp::max(weight, Some(cert_weight));
+            }
+        }
+
+        Ok(weight)
+    }
+
     /// Calculate weight from given certificates
-    /// Returns None if there is no such public key
-    /// or some trust between this key and a root key is revoked.
+    /// Returns None if there is no such public key,
+    /// some trust between this key and a root key is revoked
+    /// or every certificate is vetoed by a distrust or has an expired anchor trust.
+    /// Confidences of trusts can't be expressed as a weight factor, see `certificates_weight`
     pub fn certificates_weight_factor<C, I>(
         &self,
         certs: I,
+        cur_time: Duration,
     ) -> Result<Option<WeightFactor>, TrustGraphError>
     where
         C: Borrow<Certificate>,
         I: IntoIterator<Item = C>,
     {
-        let mut certs = certs.into_iter().peekable();
         // if there are no certificates for the given public key, there is no info about this public key
         // or some elements of possible certificate chains was revoked
-        if certs.peek().is_none() {
-            return Ok(None);
-        }
-
-        let mut weight_factor = u32::MAX;
+        let mut weight_factor = None;
 
         for cert in certs {
-            let c = cert.borrow();
-
-            let first = c
-                .chain
-                .first()
-                .ok_or(CertificateCheckError(CertificateLengthError))?;
-
-            let root_weight_factor = self
-                .storage
-                .get_root_weight_factor(first.issued_for.as_ref())?
-                .ok_or(NoRoot)?;
-
-            // certificate weight_factor = root weight factor + 1 * every other element in the chain
-            // (except root, so the formula is `root weight factor + chain length - 1`)
-            weight_factor =
-                std::cmp::min(weight_factor, root_weight_factor + c.chain.len() as u32 - 1)
+            if let Some(cert_weight_factor) =
+                self.certificate_weight_factor(cert.borrow(), cur_time)?
+            {
+                weight_factor = Some(std::cmp::min(
+                    weight_factor.unwrap_or(u32::MAX),
+                    cert_weight_factor,
+                ));
+            }
         }
 
-        Ok(Some(weight_factor))
+        Ok(weight_factor)
     }
 
-    /// BF search for all converging paths (chains) in the graph
-    fn bf_search_paths(
+    /// Weight factor of one certificate, None if it's vetoed by a distrust
+    /// or its anchor trust doesn't certify its root at `cur_time`
+    fn certificate_weight_factor(
         &self,
-        pk: &PK,
-        roots: HashSet<&PK>,
-    ) -> Result<Vec<Vec<Auth>>, TrustGraphError> {
-        // queue to collect all chains in the trust graph (each chain is a path in the trust graph)
-        let mut chains_queue: VecDeque<Chain> = VecDeque::new();
-
-        let node_auths: Vec<Auth> = self.storage.get_authorizations(pk)?;
-        let node_revocations = self.storage.get_revocations(pk)?;
+        c: &Certificate,
+        cur_time: Duration,
+    ) -> Result<Option<WeightFactor>, TrustGraphError> {
+        let first = c
+            .chain
+            .first()
+            .ok_or(CertificateCheckError(CertificateLengthError))?;
 
-        // put all auth in the queue as the first possible paths through the graph
-        for auth in node_auths {
-            chains_queue.push_back(Chain::new(NonEmpty::new(auth), node_revocations.clone()));
+        let root_weight_factor = match &c.anchor_trust {
+            // anchor trust is one more element in the chain
+            Some(anchor_trust) => {
+                // like an expired root trust, an expired anchor trust gives no weight
+                if anchor_trust.issued_for != first.issued_for || anchor_trust.expires_at < cur_time
+                {
+                    return Ok(None);
+                }
+                self.storage
+                    .get_threshold_anchor_weight_factor(&anchor_trust.anchor)?
+                    .map(|wf| wf + 1)
+            }
+            None => self
+                .storage
+                .get_root_weight_factor(first.issued_for.as_ref())?,
         }
+        .ok_or(NoRoot)?;
 
-        // List of all chains that converge (terminate) to known roots
-        let mut terminated_chains: Vec<Vec<Auth>> = Vec::new();
+        // certificate weight_factor = root weight factor + 1 * every other element in the chain
+        // (except root, so the formula is `root weight factor + chain length - 1`)
+        let cert_weight_factor = root_weight_factor + c.chain.len() as u32 - 1;
 
-        while !chains_queue.is_empty() {
-            let cur_chain = chains_queue
-                .pop_front()
-                .expect("`chains_queue` always has at least one element");
+        let cert_weight_factor = match self.distrust_policy {
+            DistrustPolicy::Ignore => cert_weight_factor,
+            DistrustPolicy::Penalty(penalty) => cert_weight_factor
+                .saturating_add(penalty.saturating_mul(self.count_distrusts(&c.chain)?)),
+            DistrustPolicy::Veto => {
+                if self.count_distrusts(&c.chain)? > 0 {
+                    return Ok(None);
+                }
+                cert_weight_factor
+            }
+        };
 
-            let last = cur_chain.auths.last();
+        Ok(Some(cert_weight_factor))
+    }
 
-            let auths = self
-                .storage
-                .get_authorizations(&last.issued_by.clone().into())?;
+    /// Count distrusts of the chain keys issued by keys near the chain:
+    /// keys of the chain itself and keys trusting them through at most `distrust_radius` trusts
+    fn count_distrusts(&self, chain: &[Trust]) -> Result<u32, TrustGraphError> {
+        let chain_keys: HashSet<PK> = chain.iter().map(|t| t.issued_for.clone().into()).collect();
 
-            for auth in auths {
-                // if there is auth, that we not visited in the current chain and no revocations to any chain member --  copy chain and append this auth
-                if cur_chain.can_be_extended_by(&auth.issued_by) {
-                    let mut new_chain = cur_chain.clone();
-                    new_chain.add_revocations(
-                        self.storage
-                            .get_revocations(&auth.issued_by.clone().into())?,
-                    );
-                    new_chain.auths.push(auth);
-                    chains_queue.push_back(new_chain);
+        // collect issuers of trusts to the chain keys level by level
+        let mut near: HashSet<PK> = chain_keys.clone();
+        let mut frontier: Vec<PK> = chain_keys.iter().cloned().collect();
+        for _ in 0..self.distrust_radius {
+            let mut next = vec![];
+            for key in frontier.iter() {
+                for auth in self.get_unvoided_authorizations(key)? {
+                    let issued_by: PK = auth.issued_by.into();
+                    if near.insert(issued_by.clone()) {
+                        next.push(issued_by);
+                    }
                 }
             }
+            frontier = next;
+        }
 
-            // to be considered a valid chain, the chain must:
-            // - end with a self-signed trust
+        let mut count = 0u32;
+        for key in chain_keys.iter() {
+            for distrust in self.storage.get_distrusts(key)? {
+                let distrusted_by: &PK = distrust.distrusted_by.as_ref();
+                if distrusted_by != key
+                    && near.contains(distrusted_by)
+                    && !self.is_void(distrusted_by, distrust.distrusted_at)?
+                {
+                    count += 1;
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Returns true if the signature of `signed_by` made at `signed_at` is void
+    /// because `signed_by` declared its key compromised before that.
+    fn is_void(&self, signed_by: &PK, signed_at: Duration) -> Result<bool, TrustGraphError> {
+        Ok(self
+            .storage
+            .get_key_compromise(signed_by)?
+            .is_some_and(|c| c.voids(signed_at)))
+    }
+
+    /// Get auths for `issued_for`, skipping auths signed by a compromised key after the compromise
+    fn get_unvoided_authorizations(&self, issued_for: &PK) -> Result<Vec<Auth>, TrustGraphError> {
+        let mut auths = vec![];
+        for auth in self.storage.get_authorizations(issued_for)? {
+            if !self.is_void(auth.issued_by.as_ref(), auth.trust.issued_at)? {
+                auths.push(auth);
+            }
+        }
+
+        Ok(auths)
+    }
+
+    /// Same as `get_unvoided_authorizations`, also skipping expired auths that aren't collected yet
+    fn get_valid_authorizations(
+        &self,
+        issued_for: &PK,
+        cur_time: Duration,
+    ) -> Result<Vec<Auth>, TrustGraphError> {
+        Ok(self
+            .get_unvoided_authorizations(issued_for)?
+            .into_iter()
+            .filter(|auth| !is_expired(auth.trust.expires_at, cur_time))
+            .collect())
+    }
+
+    /// Get revocations for `issued_for`, skipping revocations signed by a compromised key after the compromise
+    fn get_valid_revocations(&self, issued_for: &PK) -> Result<Vec<Revocation>, TrustGraphError> {
+        let mut revocations = vec![];
+        for revocation in self.storage.get_revocations(issued_for)? {
+            if !self.is_void(revocation.revoked_by.as_ref(), revocation.revoked_at)? {
+                revocations.push(revocation);
+            }
+        }
+
+        Ok(revocations)
+    }
+
+    /// BF search for all converging paths (chains) in the graph
+    fn bf_search_paths(
+        &self,
+        pk: &PK,
+        roots: HashSet<&PK>,
+        cur_time: Duration,
+    ) -> Result<Vec<Vec<Auth>>, TrustGraphError> {
+        // queue to collect all chains in the trust graph (each chain is a path in the trust graph)
+        let mut chains_queue: VecDeque<Chain> = VecDeque::new();
+
+        let node_auths: Vec<Auth> = self.get_valid_authorizations(pk, cur_time)?;
+        let node_revocations = self.get_valid_revocations(pk)?;
+
+        // put all auth in the queue as the first possible paths through the graph
+        for auth in node_auths {
+            chains_queue.push_back(Chain::new(NonEmpty::new(auth), node_revocations.clone()));
+        }
+
+        // List of all chains that converge (terminate) to known roots
+        let mut terminated_chains: Vec<Vec<Auth>> = Vec::new();
+
+        while !chains_queue.is_empty() {
+            let cur_chain = chains_queue
+                .pop_front()
+                .expect("`chains_queue` always has at least one element");
+
+            let last = cur_chain.auths.last();
+
+            let auths = self.get_valid_authorizations(&last.issued_by.clone().into(), cur_time)?;
+
+            for auth in auths {
+                // if there is auth, that we not visited in the current chain and no revocations to any chain member --  copy chain and append this auth
+                if cur_chain.can_be_extended_by(&auth.issued_by) {
+                    let mut new_chain = cur_chain.clone();
+                    new_chain.add_revocations(
+                        self.get_valid_revocations(&auth.issued_by.clone().into())?,
+                    );
+                    new_chain.auths.push(auth);
+                    chains_queue.push_back(new_chain);
+                }
+            }
+
+            // to be considered a valid chain, the chain must:
+            // - end with a self-signed trust
             // - that trust must converge to one of the roots
             // - there should be more than 1 trust in the chain
             let self_signed = last.issued_by == last.trust.issued_for;
@@ -356,19 +1111,33 @@ where
     where
         P: Borrow<PublicKey>,
     {
-        // garbage collect
-        self.storage.remove_expired(cur_time)?;
+        self.scheduled_gc(cur_time)?;
 
         // maybe later we should retrieve root keys lazily
         let keys = self.storage.root_keys()?;
-        let roots = keys.iter().collect();
+        let threshold_trusts = self.valid_threshold_trusts(cur_time)?;
+        let anchored_keys: Vec<PK> = threshold_trusts
+            .iter()
+            .map(|t| t.issued_for.clone().into())
+            .collect();
+        let roots = keys.iter().chain(anchored_keys.iter()).collect();
 
         Ok(self
-            .bf_search_paths(issued_for.borrow().as_ref(), roots)?
+            .bf_search_paths(issued_for.borrow().as_ref(), roots, cur_time)?
             .into_iter()
             .map(|auths| {
                 let trusts: Vec<Trust> = auths.into_iter().map(|auth| auth.trust).rev().collect();
-                Certificate::new_unverified(trusts)
+                let root: &PK = trusts[0].issued_for.as_ref();
+                // usual root is preferred if the key is both
+                match threshold_trusts
+                    .iter()
+                    .find(|t| t.issued_for == trusts[0].issued_for)
+                {
+                    Some(anchor_trust) if !keys.contains(root) => {
+                        Certificate::new_anchored_unverified(trusts, anchor_trust.clone())
+                    }
+                    _ => Certificate::new_unverified(trusts),
+                }
             })
             .filter(|c| {
                 // Certificate with one trust means nothing, gotta be a bug. Checking for it here.
@@ -381,13 +1150,605 @@ where
             .collect())
     }
 
+    /// Get all keys reachable from the roots with weight at least `min_weight`,
+    /// every key with its best weight and the certificate that gives it.
+    /// Keys are sorted by weight in descending order.
+    pub fn trusted_keys(
+        &mut self,
+        min_weight: u32,
+        cur_time: Duration,
+    ) -> Result<Vec<TrustedKey>, TrustGraphError> {
+        self.collect_trusted_keys(None, min_weight, cur_time)
+    }
+
+    /// Same as `trusted_keys`, but only certificates starting from `root` are taken into account
+    pub fn trusted_keys_from<P>(
+        &mut self,
+        root: P,
+        min_weight: u32,
+        cur_time: Duration,
+    ) -> Result<Vec<TrustedKey>, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        self.collect_trusted_keys(Some(root.borrow().as_ref()), min_weight, cur_time)
+    }
+
+    fn collect_trusted_keys(
+        &mut self,
+        root: Option<&PK>,
+        min_weight: u32,
+        cur_time: Duration,
+    ) -> Result<Vec<TrustedKey>, TrustGraphError> {
+        self.scheduled_gc(cur_time)?;
+
+        let mut roots: HashMap<PK, u32> = HashMap::new();
+        for pk in self.storage.root_keys()? {
+            if let Some(weight_factor) = self.storage.get_root_weight_factor(&pk)? {
+                roots.insert(pk, get_weight_from_factor(weight_factor));
+            }
+        }
+        for trust in self.valid_threshold_trusts(cur_time)? {
+            if let Some(weight_factor) = self.anchored_root_weight_factor(&trust, cur_time)? {
+                let weight = roots.entry(trust.issued_for.into()).or_default();
+                *weight = std::cmp::max(*weight, get_weight_from_factor(weight_factor));
+            }
+        }
+        roots.retain(|pk, _| root.is_none() || root == Some(pk));
+
+        // traverse valid trusts forward from the roots to find candidates
+        let mut reachable: HashSet<PK> = roots.keys().cloned().collect();
+        let mut queue: VecDeque<PK> = roots.keys().cloned().collect();
+        while let Some(pk) = queue.pop_front() {
+            for next in self.get_valid_issued_keys(&pk, cur_time)? {
+                if reachable.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut trusted = vec![];
+        for pk in reachable {
+            let mut best = roots.get(&pk).map(|weight| TrustedKey {
+                pk: pk.clone().into(),
+                weight: *weight,
+                certificate: None,
+            });
+
+            for cert in self.get_all_certs(pk.as_ref(), cur_time)? {
+                let cert_root: &PK = cert.chain[0].issued_for.as_ref();
+                if root.is_some() && root != Some(cert_root) {
+                    continue;
+                }
+
+                if let Some(weight_factor) = self.certificate_weight_factor(&cert, cur_time)? {
+                    let weight =
+                        apply_confidence(get_weight_from_factor(weight_factor), &cert.chain);
+                    if best.as_ref().map_or(0, |b| b.weight) < weight {
+                        best = Some(TrustedKey {
+                            pk: pk.clone().into(),
+                            weight,
+                            certificate: Some(cert),
+                        });
+                    }
+                }
+            }
+
+            if let Some(key) = best.filter(|k| k.weight >= min_weight) {
+                trusted.push(key);
+            }
+        }
+
+        trusted.sort_by_key(|k| std::cmp::Reverse(k.weight));
+        Ok(trusted)
+    }
+
+    /// Collect statistics of the graph, expired relations are counted but not removed
+    pub fn stats(&self, cur_time: Duration) -> Result<GraphStats, TrustGraphError> {
+        let mut stats = GraphStats::default();
+        let mut keys: HashSet<PK> = HashSet::new();
+
+        for relation in self.storage.get_all_relations()? {
+            match &relation {
+                TrustRelation::Auth(auth) => {
+                    stats.auths += 1;
+                    if is_expired(auth.trust.expires_at, cur_time) {
+                        stats.expired += 1;
+                    }
+                }
+                TrustRelation::Revocation(_) => stats.revocations += 1,
+                TrustRelation::Distrust(_) => stats.distrusts += 1,
+            }
+
+            keys.insert(relation.issued_by().clone().into());
+            keys.insert(relation.issued_for().clone().into());
+        }
+
+        let mut roots = self.storage.root_keys()?;
+        stats.roots = roots.len() as u64;
+        for trust in self.valid_threshold_trusts(cur_time)? {
+            stats.anchored_roots += 1;
+            roots.push(trust.issued_for.into());
+        }
+        keys.extend(roots.iter().cloned());
+
+        stats.keys = keys.len() as u64;
+        for pk in keys {
+            let format = String::from(pk.as_ref().get_key_format());
+            *stats.key_formats.entry(format).or_default() += 1;
+        }
+
+        let mut depths: HashMap<PK, usize> = roots.iter().map(|pk| (pk.clone(), 0)).collect();
+        let mut queue: VecDeque<PK> = depths.keys().cloned().collect();
+        while let Some(pk) = queue.pop_front() {
+            let depth = depths[&pk] + 1;
+            for next in self.get_valid_issued_keys(&pk, cur_time)? {
+                if !depths.contains_key(&next) {
+                    depths.insert(next.clone(), depth);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        for depth in depths.into_values() {
+            if stats.depth_histogram.len() <= depth {
+                stats.depth_histogram.resize(depth + 1, 0);
+            }
+            stats.depth_histogram[depth] += 1;
+        }
+
+        Ok(stats)
+    }
+
+    /// Remove relations according to the policy, returns numbers of removed relations
+    pub fn gc(
+        &mut self,
+        cur_time: Duration,
+        policy: &GcPolicy,
+    ) -> Result<GcReport, TrustGraphError> {
+        let mut report = GcReport::default();
+
+        let reachable = if policy.remove_unreachable {
+            let mut roots = self.storage.root_keys()?;
+            for trust in self.valid_threshold_trusts(cur_time)? {
+                roots.push(trust.issued_for.into());
+            }
+
+            let mut reachable: HashSet<PK> = roots.iter().cloned().collect();
+            let mut queue: VecDeque<PK> = roots.into_iter().collect();
+            while let Some(pk) = queue.pop_front() {
+                for next in self.get_valid_issued_keys(&pk, cur_time)? {
+                    if reachable.insert(next.clone()) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            Some(reachable)
+        } else {
+            None
+        };
+
+        for relation in self.storage.get_all_relations()? {
+            let counter = match &relation {
+                TrustRelation::Auth(auth)
+                    if policy.remove_expired && is_expired(auth.trust.expires_at, cur_time) =>
+                {
+                    &mut report.expired
+                }
+                TrustRelation::Revocation(revocation)
+                    if policy
+                        .revocation_retention
+                        .is_some_and(|r| cur_time.saturating_sub(revocation.revoked_at) > r) =>
+                {
+                    &mut report.revocations
+                }
+                _ if reachable
+                    .as_ref()
+                    .is_some_and(|r| !r.contains(relation.issued_by().as_ref())) =>
+                {
+                    &mut report.unreachable
+                }
+                _ => continue,
+            };
+
+            self.storage.remove_relation(
+                relation.issued_for().as_ref(),
+                relation.issued_by().as_ref(),
+            )?;
+            // trusts issued before the revocation would be accepted again without it
+            if let TrustRelation::Revocation(revocation) = &relation {
+                self.storage.set_tombstone(
+                    relation.issued_for().as_ref(),
+                    relation.issued_by().as_ref(),
+                    revocation.revoked_at,
+                )?;
+            }
+            self.refresh_state(
+                relation.issued_for().as_ref(),
+                relation.issued_by().as_ref(),
+            )?;
+            *counter += 1;
+        }
+
+        self.last_gc = Some(cur_time);
+        Ok(report)
+    }
+
+    /// Garbage collection on the read path, depends on the schedule
+    fn scheduled_gc(&mut self, cur_time: Duration) -> Result<(), TrustGraphError> {
+        match self.gc_schedule {
+            GcSchedule::OnRead => {
+                self.storage.remove_expired(cur_time)?;
+                if self.is_state_stored()? {
+                    state::remove_expired(&mut self.storage, cur_time)?;
+                }
+                Ok(())
+            }
+            GcSchedule::Every { interval, policy } => {
+                let due = match self.last_gc {
+                    Some(last_gc) => last_gc + interval <= cur_time,
+                    None => true,
+                };
+                if due {
+                    self.gc(cur_time, &policy)?;
+                }
+                Ok(())
+            }
+            GcSchedule::Manual => Ok(()),
+        }
+    }
+
+    /// Export roots, trusts and revocations in `format` for visualization.
+    /// If `from` is given, only keys reachable from it by trusts and relations they issued are exported.
+    /// Expired relations which are not garbage collected yet are exported too.
+    pub fn export_graph(
+        &self,
+        format: GraphFormat,
+        from: Option<&PublicKey>,
+    ) -> Result<String, TrustGraphError> {
+        let mut roots = self.storage.root_keys()?;
+
+        let relations = match from {
+            None => self.storage.get_all_relations()?,
+            Some(from) => {
+                let mut relations = vec![];
+                let mut reachable: HashSet<PK> = HashSet::new();
+                let mut queue: VecDeque<PK> = VecDeque::new();
+                reachable.insert(from.clone().into());
+                queue.push_back(from.clone().into());
+
+                while let Some(pk) = queue.pop_front() {
+                    for relation in self.storage.get_issued_by(&pk)? {
+                        if let TrustRelation::Auth(auth) = &relation {
+                            let next: PK = auth.trust.issued_for.clone().into();
+                            if reachable.insert(next.clone()) {
+                                queue.push_back(next);
+                            }
+                        }
+                        relations.push(relation);
+                    }
+                }
+
+                roots.retain(|pk| reachable.contains(pk));
+                relations
+            }
+        };
+
+        Ok(render(format, &roots, &relations))
+    }
+
+    /// Merkle root over roots and relations, equal on replicas with the same contents.
+    /// Expired trusts are included until they are garbage collected.
+    /// The tree is built and stored on the first call and then updated along with the graph,
+    /// so it isn't rebuilt by other graphs over the same storage.
+    pub fn state_root(&mut self) -> Result<Hash, TrustGraphError> {
+        Ok(state::state_root(self.state()?)?)
+    }
+
+    /// Relation between the keys with the proof of its inclusion into the current state root
+    pub fn prove_relation<P>(
+        &mut self,
+        issued_for: P,
+        issued_by: P,
+    ) -> Result<Option<(TrustRelation, StateProof)>, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        let relation = self
+            .storage
+            .get_relation(issued_for.borrow().as_ref(), issued_by.borrow().as_ref())?;
+
+        Ok(match relation {
+            Some(relation) => {
+                state::prove(self.state()?, &relation)?.map(|proof| (relation, proof))
+            }
+            None => None,
+        })
+    }
+
+    /// Storage with the state tree, which is built on the first request
+    fn state(&mut self) -> Result<&S, TrustGraphError> {
+        if !self.is_state_stored()? {
+            let mut roots = vec![];
+            for pk in self.storage.root_keys()? {
+                if let Some(weight_factor) = self.storage.get_root_weight_factor(&pk)? {
+                    roots.push((pk, weight_factor));
+                }
+            }
+
+            let relations = self.storage.get_all_relations()?;
+            state::build(&mut self.storage, &roots, &relations)?;
+            self.state_stored = true;
+        }
+
+        Ok(&self.storage)
+    }
+
+    /// The tree is updated along with the graph only once it's stored
+    fn is_state_stored(&mut self) -> Result<bool, TrustGraphError> {
+        if !self.state_stored {
+            self.state_stored = state::is_stored(&self.storage)?;
+        }
+
+        Ok(self.state_stored)
+    }
+
+    /// Entries of the transparency log with sequence numbers in `from..to`
+    pub fn log_entries(&self, from: u64, to: u64) -> Result<Vec<LogEntry>, TrustGraphError> {
+        Ok(self.storage.get_log_entries(from, to)?)
+    }
+
+    /// Size and tree root of the transparency log
+    pub fn log_tree_head(&mut self) -> Result<(u64, Hash), TrustGraphError> {
+        let leaves = self.log_leaves()?;
+        Ok((leaves.len() as u64, root(leaves)))
+    }
+
+    /// Current tree head of the transparency log signed by the log owner
+    pub fn signed_tree_head(
+        &mut self,
+        log_key: &KeyPair,
+        cur_time: Duration,
+    ) -> Result<SignedTreeHead, TrustGraphError> {
+        let (size, root) = self.log_tree_head()?;
+        Ok(SignedTreeHead::create(log_key, size, root, cur_time))
+    }
+
+    /// Proof that the entry with `sequence` is in the log tree of `size` entries
+    pub fn log_inclusion_proof(
+        &mut self,
+        sequence: u64,
+        size: u64,
+    ) -> Result<Vec<Hash>, TrustGraphError> {
+        let leaves = self.log_leaves()?;
+        if sequence >= size || size > leaves.len() as u64 {
+            return Err(InvalidLogRange(sequence, size, leaves.len() as u64));
+        }
+
+        Ok(inclusion_path(&leaves[..size as usize], sequence as usize))
+    }
+
+    /// Proof that the log tree of `first` entries is a prefix of the log tree of `second` entries
+    pub fn log_consistency_proof(
+        &mut self,
+        first: u64,
+        second: u64,
+    ) -> Result<Vec<Hash>, TrustGraphError> {
+        let leaves = self.log_leaves()?;
+        if first > second || second > leaves.len() as u64 {
+            return Err(InvalidLogRange(first, second, leaves.len() as u64));
+        }
+
+        Ok(consistency_path(&leaves[..second as usize], first as usize))
+    }
+
+    fn log_leaves(&mut self) -> Result<&[Hash], TrustGraphError> {
+        let leaves = match self.log_leaves.take() {
+            Some(leaves) => leaves,
+            None => self
+                .storage
+                .get_log_entries(0, self.storage.log_size()?)?
+                .iter()
+                .map(|e| e.leaf_hash())
+                .collect(),
+        };
+
+        Ok(self.log_leaves.insert(leaves))
+    }
+
+    /// Relation has to be logged if it's not stored yet, the same relation may come again on sync
+    fn needs_logging(&self, relation: &TrustRelation) -> Result<bool, TrustGraphError> {
+        Ok(!self.is_stored(relation)?)
+    }
+
+    fn is_stored(&self, relation: &TrustRelation) -> Result<bool, TrustGraphError> {
+        let stored = self.storage.get_relation(
+            relation.issued_for().as_ref(),
+            relation.issued_by().as_ref(),
+        )?;
+
+        Ok(stored.is_some_and(|r| r.digest() == relation.digest()))
+    }
+
+    /// Update the state tree and the log after the relation was written to storage,
+    /// which ignores relations older than the stored one
+    fn relation_written(
+        &mut self,
+        relation: TrustRelation,
+        needs_logging: bool,
+    ) -> Result<(), TrustGraphError> {
+        self.refresh_state(
+            relation.issued_for().as_ref(),
+            relation.issued_by().as_ref(),
+        )?;
+
+        if needs_logging && self.is_stored(&relation)? {
+            let entry = LogEntry {
+                sequence: self.storage.log_size()?,
+                relation,
+            };
+            let leaf = entry.leaf_hash();
+            self.storage.append_log_entry(entry)?;
+            if let Some(leaves) = self.log_leaves.as_mut() {
+                leaves.push(leaf);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Update the state tree after the relation between the keys has changed in storage
+    fn refresh_state(&mut self, issued_for: &PK, issued_by: &PK) -> Result<(), TrustGraphError> {
+        if self.is_state_stored()? {
+            let relation = self.storage.get_relation(issued_for, issued_by)?;
+            state::set_relation(&mut self.storage, issued_for, issued_by, relation.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Digests of relation buckets to compare this replica with another one
+    pub fn sync_summary(&self) -> Result<SyncSummary, TrustGraphError> {
+        Ok(summarize(&self.storage.get_all_relations()?))
+    }
+
+    /// Digests of relations in `buckets`
+    pub fn sync_digests(&self, buckets: &[u8]) -> Result<Vec<RelationDigest>, TrustGraphError> {
+        Ok(bucket_digests(&self.storage.get_all_relations()?, buckets))
+    }
+
+    /// Relations with the given digests, unknown digests are skipped
+    pub fn sync_relations(
+        &self,
+        digests: &[RelationDigest],
+    ) -> Result<Vec<TrustRelation>, TrustGraphError> {
+        let digests: HashSet<&RelationDigest> = digests.iter().collect();
+        Ok(self
+            .storage
+            .get_all_relations()?
+            .into_iter()
+            .filter(|r| digests.contains(&r.digest()))
+            .collect())
+    }
+
+    /// Fetch relations this replica lacks from the remote one and apply them.
+    /// Only buckets with different digests are compared relation by relation,
+    /// and only missing relations are fetched.
+    /// Every relation is verified as if it was added by hand, so trusts from issuers
+    /// without weight on this replica are not stored. Roots are never synced.
+    pub fn sync_from<T>(
+        &mut self,
+        transport: &mut T,
+        cur_time: Duration,
+    ) -> Result<SyncReport, TrustGraphError>
+    where
+        T: SyncTransport,
+    {
+        let transport_error = |e: T::Error| SyncTransportError(Box::new(e));
+
+        let mut report = SyncReport::default();
+        let remote = transport.summary().map_err(transport_error)?;
+        let buckets = diff_buckets(&self.sync_summary()?, &remote);
+        if buckets.is_empty() {
+            return Ok(report);
+        }
+        report.buckets = buckets.len() as u32;
+
+        let known: HashSet<RelationDigest> = self.sync_digests(&buckets)?.into_iter().collect();
+        let missing: HashSet<RelationDigest> = transport
+            .digests(&buckets)
+            .map_err(transport_error)?
+            .into_iter()
+            .filter(|d| !known.contains(d))
+            .collect();
+        if missing.is_empty() {
+            return Ok(report);
+        }
+
+        let requested: Vec<RelationDigest> = missing.iter().copied().collect();
+        let relations = transport.relations(&requested).map_err(transport_error)?;
+        report.fetched = relations.len() as u32;
+
+        let mut auths = vec![];
+        for relation in relations {
+            if !missing.contains(&relation.digest()) {
+                report.rejected += 1;
+                continue;
+            }
+
+            let result = match relation {
+                TrustRelation::Auth(auth) => {
+                    auths.push(auth);
+                    continue;
+                }
+                TrustRelation::Revocation(revocation) => self.revoke(revocation),
+                TrustRelation::Distrust(distrust) => self.distrust(distrust),
+            };
+
+            match result {
+                Ok(()) => report.applied += 1,
+                Err(e @ InternalStorageError(_)) => return Err(e),
+                Err(_) => report.rejected += 1,
+            }
+        }
+
+        // a trust gives weight only after trusts on the path to its issuer are added
+        loop {
+            let mut remaining = vec![];
+            for auth in auths.iter() {
+                match self.add_trust(&auth.trust, &auth.issued_by, cur_time) {
+                    Ok(0) => remaining.push(auth.clone()),
+                    Ok(_) => report.applied += 1,
+                    Err(e @ InternalStorageError(_)) => return Err(e),
+                    Err(_) => report.rejected += 1,
+                }
+            }
+
+            let progress = remaining.len() < auths.len();
+            auths = remaining;
+            if !progress || auths.is_empty() {
+                break;
+            }
+        }
+        report.unanchored = auths.len() as u32;
+
+        Ok(report)
+    }
+
+    /// Keys trusted by `issued_by`, only valid trusts are taken into account
+    fn get_valid_issued_keys(
+        &self,
+        issued_by: &PK,
+        cur_time: Duration,
+    ) -> Result<Vec<PK>, TrustGraphError> {
+        let mut keys = vec![];
+        for relation in self.storage.get_issued_by(issued_by)? {
+            if let TrustRelation::Auth(auth) = relation {
+                if auth.issued_by == auth.trust.issued_for
+                    || is_expired(auth.trust.expires_at, cur_time)
+                    || self.is_void(issued_by, auth.trust.issued_at)?
+                {
+                    continue;
+                }
+
+                keys.push(auth.trust.issued_for.into());
+            }
+        }
+
+        Ok(keys)
+    }
+
     /// Mark public key as revoked.
     /// Every chain that contains path from `revoked_by` to revoked `pk`
     /// will be excluded from valid certificates until revocation canceled by giving trust
     pub fn revoke(&mut self, revocation: Revocation) -> Result<(), TrustGraphError> {
         Revocation::verify(&revocation)?;
 
-        Ok(self.storage.revoke(revocation)?)
+        let relation = TrustRelation::Revocation(revocation.clone());
+        let needs_logging = self.needs_logging(&relation)?;
+        self.storage.revoke(revocation)?;
+        self.relation_written(relation, needs_logging)
     }
 
     pub fn get_revocations<P>(&self, issued_for: P) -> Result<Vec<Revocation>, TrustGraphError>
@@ -396,4 +1757,176 @@ where
     {
         Ok(self.storage.get_revocations(issued_for.borrow().as_ref())?)
     }
+
+    /// Get all trusts signed by `issued_by`, including its self-signed trust
+    pub fn get_issued_trusts<P>(&self, issued_by: P) -> Result<Vec<Trust>, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        Ok(self
+            .storage
+            .get_issued_by(issued_by.borrow().as_ref())?
+            .into_iter()
+            .filter_map(|r| match r {
+                TrustRelation::Auth(auth) => Some(auth.trust),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Get all revocations signed by `issued_by`
+    pub fn get_issued_revocations<P>(
+        &self,
+        issued_by: P,
+    ) -> Result<Vec<Revocation>, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        Ok(self
+            .storage
+            .get_issued_by(issued_by.borrow().as_ref())?
+            .into_iter()
+            .filter_map(|r| match r {
+                TrustRelation::Revocation(revocation) => Some(revocation),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Mark public key as distrusted by `distrusted_by`.
+    /// How it affects weights depends on the distrust policy.
+    /// Canceled by a later trust or revocation from `distrusted_by`.
+    pub fn distrust(&mut self, distrust: Distrust) -> Result<(), TrustGraphError> {
+        Distrust::verify(&distrust)?;
+
+        let relation = TrustRelation::Distrust(distrust.clone());
+        let needs_logging = self.needs_logging(&relation)?;
+        self.storage.distrust(distrust)?;
+        self.relation_written(relation, needs_logging)
+    }
+
+    pub fn get_distrusts<P>(&self, issued_for: P) -> Result<Vec<Distrust>, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        Ok(self.storage.get_distrusts(issued_for.borrow().as_ref())?)
+    }
+
+    /// Import all revocations from the list and keep the list itself for export.
+    /// The list is rejected if the issuer has already published a list
+    /// with the same or a greater sequence number.
+    /// It is written in one storage transaction, so it is either imported entirely or not at all.
+    /// Lists are additive: a revocation left out of a newer list stays in the graph
+    /// like any other revocation, until a newer trust between the same keys replaces it.
+    pub fn import_revocation_list(
+        &mut self,
+        list: RevocationList,
+        cur_time: Duration,
+    ) -> Result<(), TrustGraphError> {
+        RevocationList::verify(&list, cur_time)?;
+
+        if let Some(current) = self.storage.get_revocation_list(list.issued_by.as_ref())? {
+            if current.sequence >= list.sequence {
+                return Err(OutdatedRevocationList(list.sequence, current.sequence));
+            }
+        }
+
+        self.storage.begin_transaction()?;
+        match self.write_revocation_list(list) {
+            Ok(()) => Ok(self.storage.commit_transaction()?),
+            Err(e) => {
+                self.storage.rollback_transaction()?;
+                // leaves of the rolled back log entries are reloaded on the next request
+                self.log_leaves = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn write_revocation_list(&mut self, list: RevocationList) -> Result<(), TrustGraphError> {
+        for revocation in list.revocations.iter() {
+            let relation = TrustRelation::Revocation(revocation.clone());
+            let needs_logging = self.needs_logging(&relation)?;
+            self.storage.revoke(revocation.clone())?;
+            self.relation_written(relation, needs_logging)?;
+        }
+
+        Ok(self.storage.set_revocation_list(list)?)
+    }
+
+    /// Get the last imported revocation list of `issued_by`
+    pub fn export_revocation_list<P>(
+        &self,
+        issued_by: P,
+    ) -> Result<Option<RevocationList>, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        Ok(self
+            .storage
+            .get_revocation_list(issued_by.borrow().as_ref())?)
+    }
+
+    /// Declare the key compromised: every trust and revocation it signed
+    /// after `compromised_at` is ignored from now on.
+    /// The declaration can't be canceled, only moved to an earlier moment.
+    pub fn declare_compromise(&mut self, compromise: KeyCompromise) -> Result<(), TrustGraphError> {
+        KeyCompromise::verify(&compromise)?;
+
+        if let Some(current) = self.storage.get_key_compromise(compromise.pk.as_ref())? {
+            if current.compromised_at <= compromise.compromised_at {
+                return Ok(());
+            }
+        }
+
+        Ok(self.storage.set_key_compromise(compromise)?)
+    }
+
+    /// Compute global reputation of keys from all stored trusts, seeded by the roots.
+    /// Every root contributes in proportion to its weight, every trust in proportion to its confidence.
+    /// Pass the previous result to recompute faster after the graph has changed.
+    pub fn reputation(
+        &mut self,
+        config: &ReputationConfig,
+        previous: Option<&Reputation>,
+        cur_time: Duration,
+    ) -> Result<Reputation, TrustGraphError> {
+        self.scheduled_gc(cur_time)?;
+
+        let mut seeds = vec![];
+        for pk in self.storage.root_keys()? {
+            if let Some(weight_factor) = self.storage.get_root_weight_factor(&pk)? {
+                seeds.push((pk, f64::from(get_weight_from_factor(weight_factor))));
+            }
+        }
+        for trust in self.valid_threshold_trusts(cur_time)? {
+            if let Some(weight_factor) = self.anchored_root_weight_factor(&trust, cur_time)? {
+                let weight = f64::from(get_weight_from_factor(weight_factor));
+                seeds.push((trust.issued_for.into(), weight));
+            }
+        }
+
+        let mut edges = vec![];
+        for relation in self.storage.get_all_relations()? {
+            if let TrustRelation::Auth(auth) = relation {
+                if is_expired(auth.trust.expires_at, cur_time)
+                    || self.is_void(auth.issued_by.as_ref(), auth.trust.issued_at)?
+                {
+                    continue;
+                }
+
+                let weight = f64::from(auth.trust.confidence) / f64::from(MAX_CONFIDENCE);
+                edges.push((auth.issued_by.into(), auth.trust.issued_for.into(), weight));
+            }
+        }
+
+        Ok(compute(&edges, &seeds, config, previous))
+    }
+
+    pub fn get_key_compromise<P>(&self, pk: P) -> Result<Option<KeyCompromise>, TrustGraphError>
+    where
+        P: Borrow<PublicKey>,
+    {
+        Ok(self.storage.get_key_compromise(pk.borrow().as_ref())?)
+    }
 }
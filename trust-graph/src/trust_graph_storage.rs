@@ -1,5 +1,12 @@
+use crate::distrust::Distrust;
+use crate::key_compromise::KeyCompromise;
+use crate::merkle::Hash;
+use crate::pending::PendingTrust;
 use crate::public_key_hashable::PublicKeyHashable as PK;
+use crate::revocation_list::RevocationList;
 use crate::revoke::Revocation;
+use crate::threshold::{ThresholdAnchor, ThresholdTrust};
+use crate::transparency_log::LogEntry;
 use crate::trust_graph::WeightFactor;
 use crate::trust_relation::{Auth, TrustRelation};
 use std::fmt::Display;
@@ -18,8 +25,22 @@ pub trait Storage {
 
     fn get_authorizations(&self, issued_for: &PK) -> Result<Vec<Auth>, Self::Error>;
     fn get_revocations(&self, issued_for: &PK) -> Result<Vec<Revocation>, Self::Error>;
+    fn get_distrusts(&self, issued_for: &PK) -> Result<Vec<Distrust>, Self::Error>;
+    /// all relations signed by `issued_by`
+    fn get_issued_by(&self, issued_by: &PK) -> Result<Vec<TrustRelation>, Self::Error>;
+    /// all stored relations, used for computations over the whole graph
+    fn get_all_relations(&self) -> Result<Vec<TrustRelation>, Self::Error>;
 
     fn insert(&mut self, node: TrustRelation) -> Result<(), Self::Error>;
+    fn remove_relation(&mut self, issued_for: &PK, issued_by: &PK) -> Result<(), Self::Error>;
+    /// Keeps the time of a revocation removed by gc,
+    /// relations between the same keys issued at or before it must not be stored again
+    fn set_tombstone(
+        &mut self,
+        issued_for: &PK,
+        issued_by: &PK,
+        revoked_at: Duration,
+    ) -> Result<(), Self::Error>;
 
     fn get_root_weight_factor(&self, pk: &PK) -> Result<Option<WeightFactor>, Self::Error>;
     fn set_root_weight_factor(
@@ -29,6 +50,66 @@ pub trait Storage {
     ) -> Result<(), Self::Error>;
     fn root_keys(&self) -> Result<Vec<PK>, Self::Error>;
     fn revoke(&mut self, revocation: Revocation) -> Result<(), Self::Error>;
+    fn distrust(&mut self, distrust: Distrust) -> Result<(), Self::Error>;
     fn update_auth(&mut self, auth: Auth, cur_time: Duration) -> Result<(), Self::Error>;
     fn remove_expired(&mut self, current_time: Duration) -> Result<(), Self::Error>;
+
+    fn get_revocation_list(&self, issued_by: &PK) -> Result<Option<RevocationList>, Self::Error>;
+    fn set_revocation_list(&mut self, list: RevocationList) -> Result<(), Self::Error>;
+
+    fn get_key_compromise(&self, pk: &PK) -> Result<Option<KeyCompromise>, Self::Error>;
+    /// must not be removed by `remove_expired`
+    fn set_key_compromise(&mut self, compromise: KeyCompromise) -> Result<(), Self::Error>;
+
+    fn get_threshold_anchor_weight_factor(
+        &self,
+        anchor: &ThresholdAnchor,
+    ) -> Result<Option<WeightFactor>, Self::Error>;
+    fn set_threshold_anchor_weight_factor(
+        &mut self,
+        anchor: ThresholdAnchor,
+        weight_factor: WeightFactor,
+    ) -> Result<(), Self::Error>;
+    fn get_threshold_trust(&self, issued_for: &PK) -> Result<Option<ThresholdTrust>, Self::Error>;
+    fn set_threshold_trust(&mut self, trust: ThresholdTrust) -> Result<(), Self::Error>;
+    fn threshold_trusts(&self) -> Result<Vec<ThresholdTrust>, Self::Error>;
+
+    /// pending trusts issued by `issued_by`
+    fn get_pending(&self, issued_by: &PK) -> Result<Vec<PendingTrust>, Self::Error>;
+    fn get_all_pending(&self) -> Result<Vec<PendingTrust>, Self::Error>;
+    /// replaces pending trust between the same keys
+    fn insert_pending(&mut self, pending: PendingTrust) -> Result<(), Self::Error>;
+    fn remove_pending(&mut self, issued_for: &PK, issued_by: &PK) -> Result<(), Self::Error>;
+
+    fn log_size(&self) -> Result<u64, Self::Error>;
+    /// entry sequence is always the current log size, entries are never removed
+    fn append_log_entry(&mut self, entry: LogEntry) -> Result<(), Self::Error>;
+    /// entries with sequence numbers in `from..to`
+    fn get_log_entries(&self, from: u64, to: u64) -> Result<Vec<LogEntry>, Self::Error>;
+
+    /// leaves of a state tree bucket ordered by their keys
+    fn get_state_leaves(&self, bucket: u8) -> Result<Vec<(Vec<u8>, Hash)>, Self::Error>;
+    /// replaces the leaf with the same key, leaves with `expires_at` are removed when it comes
+    fn insert_state_leaf(
+        &mut self,
+        bucket: u8,
+        key: Vec<u8>,
+        leaf: Hash,
+        expires_at: Option<Duration>,
+    ) -> Result<(), Self::Error>;
+    fn remove_state_leaf(&mut self, key: &[u8]) -> Result<(), Self::Error>;
+    /// removes leaves expiring at or before `current_time`, returns buckets they were in
+    fn remove_expired_state_leaves(
+        &mut self,
+        current_time: Duration,
+    ) -> Result<Vec<u8>, Self::Error>;
+    /// roots of all state tree buckets ordered by bucket, empty until the tree is stored
+    fn get_state_buckets(&self) -> Result<Vec<Hash>, Self::Error>;
+    fn set_state_bucket(&mut self, bucket: u8, root: Hash) -> Result<(), Self::Error>;
+
+    /// writes after it are applied together by `commit_transaction`
+    /// or discarded by `rollback_transaction`, transactions are not nested
+    fn begin_transaction(&mut self) -> Result<(), Self::Error>;
+    fn commit_transaction(&mut self) -> Result<(), Self::Error>;
+    fn rollback_transaction(&mut self) -> Result<(), Self::Error>;
 }
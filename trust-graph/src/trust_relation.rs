@@ -14,12 +14,15 @@
  * limitations under the License.
  */
 
+use crate::distrust::Distrust;
 use crate::revoke::Revocation;
-use crate::trust::Trust;
+use crate::signing::{length_prefix, SigningVersion};
+use crate::trust::{Trust, MAX_CONFIDENCE};
 use failure::_core::time::Duration;
 use fluence_keypair::public_key::PublicKey;
 use fluence_keypair::Signature;
 use serde::{Deserialize, Serialize};
+use sha2::Digest;
 
 /// Represents who give a trust
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +37,7 @@ pub struct Auth {
 pub enum TrustRelation {
     Auth(Auth),
     Revocation(Revocation),
+    Distrust(Distrust),
 }
 
 impl TrustRelation {
@@ -42,6 +46,7 @@ impl TrustRelation {
         match self {
             TrustRelation::Auth(auth) => auth.trust.issued_at,
             TrustRelation::Revocation(r) => r.revoked_at,
+            TrustRelation::Distrust(d) => d.distrusted_at,
         }
     }
 
@@ -50,6 +55,7 @@ impl TrustRelation {
         match self {
             TrustRelation::Auth(auth) => &auth.issued_by,
             TrustRelation::Revocation(r) => &r.revoked_by,
+            TrustRelation::Distrust(d) => &d.distrusted_by,
         }
     }
 
@@ -57,14 +63,15 @@ impl TrustRelation {
         match self {
             TrustRelation::Auth(auth) => &auth.trust.issued_for,
             TrustRelation::Revocation(r) => &r.pk,
+            TrustRelation::Distrust(d) => &d.pk,
         }
     }
 
     pub fn expires_at(&self) -> Duration {
         match self {
             TrustRelation::Auth(auth) => auth.trust.expires_at,
-            // revocations never expire
-            TrustRelation::Revocation(_) => Duration::from_secs(0),
+            // revocations and distrusts never expire
+            TrustRelation::Revocation(_) | TrustRelation::Distrust(_) => Duration::from_secs(0),
         }
     }
 
@@ -72,6 +79,48 @@ impl TrustRelation {
         match self {
             TrustRelation::Auth(auth) => &auth.trust.signature,
             TrustRelation::Revocation(r) => &r.signature,
+            TrustRelation::Distrust(d) => &d.signature,
         }
     }
+
+    /// Returns format of the payload the signature is made over
+    pub fn signing_version(&self) -> SigningVersion {
+        match self {
+            TrustRelation::Auth(auth) => auth.trust.version,
+            TrustRelation::Revocation(r) => r.version,
+            // distrust appeared after v2, so it has no legacy payload
+            TrustRelation::Distrust(_) => SigningVersion::V2,
+        }
+    }
+
+    /// Returns confidence of a trust, revocations and distrusts have none
+    pub fn confidence(&self) -> u8 {
+        match self {
+            TrustRelation::Auth(auth) => auth.trust.confidence,
+            TrustRelation::Revocation(_) | TrustRelation::Distrust(_) => MAX_CONFIDENCE,
+        }
+    }
+
+    /// Returns sha256 over the kind of the relation, its keys, timestamps and signature,
+    /// so equal relations have equal digests on every replica
+    pub fn digest(&self) -> [u8; 32] {
+        let kind: u8 = match self {
+            TrustRelation::Auth(_) => 0,
+            TrustRelation::Revocation(_) => 1,
+            TrustRelation::Distrust(_) => 2,
+        };
+
+        let mut hasher = sha2::Sha256::new();
+        hasher.update([kind, self.signing_version().to_u8(), self.confidence()]);
+        for pk in [self.issued_by(), self.issued_for()] {
+            let encoded = pk.encode();
+            hasher.update(length_prefix(&encoded));
+            hasher.update(encoded);
+        }
+        hasher.update(self.issued_at().as_secs().to_le_bytes());
+        hasher.update(self.expires_at().as_secs().to_le_bytes());
+        hasher.update(self.signature().encode());
+
+        hasher.finalize().into()
+    }
 }
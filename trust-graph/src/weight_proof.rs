@@ -0,0 +1,435 @@
+/*
+ * Copyright 2024 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::certificate::{Certificate, CertificateError};
+use crate::merkle::Hash;
+use crate::state::{NonRevocationProof, StateProof};
+use crate::threshold::{ThresholdAnchor, ThresholdError, ThresholdTrust};
+use crate::trust_graph::{apply_confidence, get_weight_from_factor, WeightFactor};
+use crate::trust_relation::{Auth, TrustRelation};
+use crate::weight_proof::WeightProofError::{
+    HopNotIncluded, HopProofsCount, KeyMismatch, NoRootWeightFactor, NonRevocationProofsCount,
+    RevocationNotExcluded, WeightMismatch,
+};
+use fluence_keypair::public_key::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum WeightProofError {
+    #[error("Certificate check error: {0}")]
+    CertificateCheckError(
+        #[from]
+        #[source]
+        CertificateError,
+    ),
+    #[error("Proof is made for another key than the certificate")]
+    KeyMismatch,
+    #[error("Weight factor of the root {0} is not in the proof")]
+    NoRootWeightFactor(String),
+    #[error("Proof has {1} non-revocation proofs for {0} trusts of the certificate")]
+    HopProofsCount(usize, usize),
+    #[error("Trust {0} of the certificate is not included into the state root")]
+    HopNotIncluded(usize),
+    #[error("Proof has {1} non-revocation proofs, {0} are needed for the certificate")]
+    NonRevocationProofsCount(usize, usize),
+    #[error("Revocation of key {1} of the certificate by key {0} is not excluded by the proof")]
+    RevocationNotExcluded(usize, usize),
+    #[error("Anchor trust check error: {0}")]
+    AnchorTrustCheckError(
+        #[from]
+        #[source]
+        ThresholdError,
+    ),
+    #[error("Claimed weight {0} is greater than weight {1} given by the certificate")]
+    WeightMismatch(u32, u32),
+}
+
+/// Self-contained proof of the weight of a key, see `verify_weight_proof`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WeightProof {
+    pub pk: PublicKey,
+    /// weight of the key in the graph, distrusts are taken into account
+    pub weight: u32,
+    /// certificate that gives the weight, None if the key is a root itself
+    #[serde(with = "crate::certificate_serde::option")]
+    pub certificate: Option<Certificate>,
+    /// threshold trust of the key if it's a root certified by a threshold anchor
+    pub anchor_trust: Option<ThresholdTrust>,
+    /// all roots of the graph
+    pub roots: Vec<(PublicKey, WeightFactor)>,
+    /// threshold anchor of the certificate or of the anchor trust if there is one
+    pub anchors: Vec<(ThresholdAnchor, WeightFactor)>,
+    /// state root of the graph the hop proofs are made against
+    pub state_root: Hash,
+    /// For every trust of the certificate, proof that it's the current relation between its keys,
+    /// so it isn't replaced by a revocation from its issuer
+    pub hops: Vec<StateProof>,
+    /// For every pair of keys of the certificate with other keys between them,
+    /// proof that the earlier key hasn't revoked the later one.
+    /// Pairs are ordered by the later key, then by the earlier one
+    pub non_revocations: Vec<NonRevocationProof>,
+}
+
+/// Pairs of indexes of the certificate keys that are checked by non-revocation proofs
+pub(crate) fn non_adjacent_pairs(len: usize) -> impl Iterator<Item = (usize, usize)> {
+    (2..len).flat_map(|later| (0..later - 1).map(move |earlier| (earlier, later)))
+}
+
+/// Recompute the weight from the proof and check every signature in it, no storage is needed.
+/// The verifier is expected to compare roots and the state root of the proof with its own view.
+/// Distrusts are not part of the proof and can only lower the weight,
+/// so the claimed weight is returned if it doesn't exceed the weight given by the certificate.
+pub fn verify_weight_proof(
+    proof: &WeightProof,
+    cur_time: Duration,
+) -> Result<u32, WeightProofError> {
+    let root_weight_factor = |pk: &PublicKey| {
+        proof
+            .roots
+            .iter()
+            .find(|(root, _)| root == pk)
+            .map(|(_, wf)| *wf)
+            .ok_or_else(|| NoRootWeightFactor(pk.to_peer_id().to_base58()))
+    };
+
+    let anchor_weight_factor = |anchor: &ThresholdAnchor| {
+        proof
+            .anchors
+            .iter()
+            .find(|(a, _)| a == anchor)
+            // anchor trust is one more element in the chain
+            .map(|(_, wf)| wf + 1)
+            .ok_or_else(|| NoRootWeightFactor(anchor.id()))
+    };
+
+    let certificate = match &proof.certificate {
+        Some(certificate) => certificate,
+        None => {
+            let weight = root_weight_factor(&proof.pk).map(get_weight_from_factor);
+            let weight = match &proof.anchor_trust {
+                Some(anchor_trust) => {
+                    if anchor_trust.issued_for != proof.pk {
+                        return Err(KeyMismatch);
+                    }
+                    ThresholdTrust::verify(anchor_trust, cur_time)?;
+                    let anchored =
+                        get_weight_from_factor(anchor_weight_factor(&anchor_trust.anchor)?);
+                    weight.map_or(anchored, |w| std::cmp::max(w, anchored))
+                }
+                None => weight?,
+            };
+            return check_weight(proof.weight, weight);
+        }
+    };
+
+    let roots: Vec<PublicKey> = proof.roots.iter().map(|(pk, _)| pk.clone()).collect();
+    let anchors: Vec<ThresholdAnchor> = proof.anchors.iter().map(|(a, _)| a.clone()).collect();
+    Certificate::verify_with_anchors(certificate, &roots, &anchors, cur_time)?;
+
+    let chain = &certificate.chain;
+    if chain.last().map(|t| &t.issued_for) != Some(&proof.pk) {
+        return Err(KeyMismatch);
+    }
+
+    let root_weight_factor = match &certificate.anchor_trust {
+        Some(anchor_trust) => anchor_weight_factor(&anchor_trust.anchor)?,
+        None => root_weight_factor(&chain[0].issued_for)?,
+    };
+
+    if proof.hops.len() != chain.len() {
+        return Err(HopProofsCount(chain.len(), proof.hops.len()));
+    }
+    for (i, hop) in proof.hops.iter().enumerate() {
+        let relation = TrustRelation::Auth(Auth {
+            trust: chain[i].clone(),
+            issued_by: chain[i.saturating_sub(1)].issued_for.clone(),
+        });
+        if !hop.verify(&relation, &proof.state_root) {
+            return Err(HopNotIncluded(i));
+        }
+    }
+
+    // adjacent keys are connected by the trusts, so only other pairs may hold revocations
+    let pairs: Vec<(usize, usize)> = non_adjacent_pairs(chain.len()).collect();
+    if proof.non_revocations.len() != pairs.len() {
+        return Err(NonRevocationProofsCount(
+            pairs.len(),
+            proof.non_revocations.len(),
+        ));
+    }
+    for ((earlier, later), non_revocation) in pairs.into_iter().zip(&proof.non_revocations) {
+        if !non_revocation.verify(
+            &chain[later].issued_for,
+            &chain[earlier].issued_for,
+            &proof.state_root,
+        ) {
+            return Err(RevocationNotExcluded(earlier, later));
+        }
+    }
+
+    let weight_factor = root_weight_factor + chain.len() as u32 - 1;
+    check_weight(
+        proof.weight,
+        apply_confidence(get_weight_from_factor(weight_factor), chain),
+    )
+}
+
+fn check_weight(claimed: u32, proven: u32) -> Result<u32, WeightProofError> {
+    if claimed > proven {
+        return Err(WeightMismatch(claimed, proven));
+    }
+
+    Ok(claimed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_memory_storage::InMemoryStorage;
+    use crate::revoke::Revocation;
+    use crate::trust::Trust;
+    use crate::trust_graph::TrustGraph;
+    use fluence_keypair::key_pair::KeyPair;
+
+    fn add_trust(
+        graph: &mut TrustGraph<InMemoryStorage>,
+        issuer: &KeyPair,
+        issued_for: &KeyPair,
+        confidence: u8,
+    ) {
+        let trust = Trust::create_with_confidence(
+            issuer,
+            issued_for.public(),
+            Duration::from_secs(100),
+            Duration::from_secs(1),
+            confidence,
+        );
+        graph
+            .add_trust(trust, issuer.public(), Duration::from_secs(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_weight_proof() {
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::from_secs(10);
+
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_root(root_kp.public(), 4).unwrap();
+        add_trust(&mut graph, &root_kp, &root_kp, 255);
+        add_trust(&mut graph, &root_kp, &a_kp, 255);
+        add_trust(&mut graph, &a_kp, &b_kp, 128);
+
+        let weight = graph.weight(b_kp.public(), cur_time).unwrap();
+        let proof = graph
+            .prove_weight(b_kp.public(), cur_time)
+            .unwrap()
+            .unwrap();
+        assert_eq!(proof.weight, weight);
+        assert_eq!(proof.hops.len(), 3);
+        assert_eq!(verify_weight_proof(&proof, cur_time).unwrap(), weight);
+
+        let root_proof = graph
+            .prove_weight(root_kp.public(), cur_time)
+            .unwrap()
+            .unwrap();
+        assert!(root_proof.certificate.is_none());
+        assert_eq!(
+            verify_weight_proof(&root_proof, cur_time).unwrap(),
+            graph.weight(root_kp.public(), cur_time).unwrap()
+        );
+
+        let mut claimed = proof.clone();
+        claimed.weight += 1;
+        assert!(matches!(
+            verify_weight_proof(&claimed, cur_time),
+            Err(WeightMismatch(_, _))
+        ));
+
+        let mut other_key = proof.clone();
+        other_key.pk = a_kp.public();
+        assert!(matches!(
+            verify_weight_proof(&other_key, cur_time),
+            Err(KeyMismatch)
+        ));
+
+        assert!(matches!(
+            verify_weight_proof(&proof, Duration::from_secs(200)),
+            Err(WeightProofError::CertificateCheckError(_))
+        ));
+
+        // the trust to b is replaced by a revocation, the old proof doesn't match the new state
+        graph
+            .revoke(Revocation::create(
+                &a_kp,
+                b_kp.public(),
+                Duration::from_secs(2),
+            ))
+            .unwrap();
+        assert!(graph
+            .prove_weight(b_kp.public(), cur_time)
+            .unwrap()
+            .is_none());
+
+        let mut revoked = proof;
+        revoked.state_root = graph.state_root().unwrap();
+        assert!(matches!(
+            verify_weight_proof(&revoked, cur_time),
+            Err(HopNotIncluded(_))
+        ));
+    }
+
+    #[test]
+    fn test_weight_proof_revocation_by_earlier_key() {
+        let root_kp = KeyPair::generate_ed25519();
+        let a_kp = KeyPair::generate_ed25519();
+        let b_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::from_secs(10);
+
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_root(root_kp.public(), 4).unwrap();
+        add_trust(&mut graph, &root_kp, &root_kp, 255);
+        add_trust(&mut graph, &root_kp, &a_kp, 255);
+        add_trust(&mut graph, &a_kp, &b_kp, 255);
+
+        let proof = graph
+            .prove_weight(b_kp.public(), cur_time)
+            .unwrap()
+            .unwrap();
+        // (root, a) are adjacent, (root, b) isn't
+        assert_eq!(proof.non_revocations.len(), 1);
+        assert!(verify_weight_proof(&proof, cur_time).is_ok());
+
+        let mut missing = proof.clone();
+        missing.non_revocations.clear();
+        assert!(matches!(
+            verify_weight_proof(&missing, cur_time),
+            Err(NonRevocationProofsCount(1, 0))
+        ));
+
+        // the root revokes b, the trust from a to b stays in the state
+        graph
+            .revoke(Revocation::create(
+                &root_kp,
+                b_kp.public(),
+                Duration::from_secs(2),
+            ))
+            .unwrap();
+        assert_eq!(graph.weight(b_kp.public(), cur_time).unwrap(), 0);
+        assert!(graph
+            .prove_weight(b_kp.public(), cur_time)
+            .unwrap()
+            .is_none());
+        assert!(graph
+            .prove_non_revocation(&b_kp.public(), &root_kp.public())
+            .unwrap()
+            .is_none());
+
+        // hops are still provable in the new state, but the revocation is not excluded
+        let mut revoked = proof;
+        revoked.state_root = graph.state_root().unwrap();
+        revoked.hops = [(&root_kp, &root_kp), (&a_kp, &root_kp), (&b_kp, &a_kp)]
+            .iter()
+            .map(|(issued_for, issued_by)| {
+                graph
+                    .prove_relation(issued_for.public(), issued_by.public())
+                    .unwrap()
+                    .unwrap()
+                    .1
+            })
+            .collect();
+        assert!(matches!(
+            verify_weight_proof(&revoked, cur_time),
+            Err(RevocationNotExcluded(0, 2))
+        ));
+    }
+
+    #[test]
+    fn test_weight_proof_anchored_root() {
+        let holders: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate_ed25519()).collect();
+        let root_kp = KeyPair::generate_ed25519();
+        let cur_time = Duration::from_secs(10);
+        let anchor =
+            ThresholdAnchor::new(holders.iter().map(|kp| kp.public()).collect(), 2).unwrap();
+
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_threshold_root(anchor.clone(), 4).unwrap();
+        let trust = ThresholdTrust::create(
+            anchor,
+            &[&holders[0], &holders[1]],
+            root_kp.public(),
+            Duration::from_secs(100),
+            Duration::from_secs(1),
+        );
+        graph.add_threshold_trust(trust, cur_time).unwrap();
+
+        let weight = graph.weight(root_kp.public(), cur_time).unwrap();
+        assert_ne!(weight, 0);
+        let proof = graph
+            .prove_weight(root_kp.public(), cur_time)
+            .unwrap()
+            .unwrap();
+        assert!(proof.certificate.is_none());
+        assert!(proof.anchor_trust.is_some());
+        assert_eq!(proof.weight, weight);
+        assert_eq!(verify_weight_proof(&proof, cur_time).unwrap(), weight);
+
+        let mut expired = proof.clone();
+        expired.weight = 0;
+        assert!(matches!(
+            verify_weight_proof(&expired, Duration::from_secs(200)),
+            Err(WeightProofError::AnchorTrustCheckError(_))
+        ));
+
+        let mut other_key = proof;
+        other_key.pk = holders[0].public();
+        assert!(matches!(
+            verify_weight_proof(&other_key, cur_time),
+            Err(KeyMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_weight_proof_serde() {
+        let root_kp = KeyPair::generate_ed25519();
+        let kps: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate_ed25519()).collect();
+        let cur_time = Duration::from_secs(10);
+
+        let mut graph = TrustGraph::new(InMemoryStorage::default());
+        graph.set_root(root_kp.public(), 6).unwrap();
+        add_trust(&mut graph, &root_kp, &root_kp, 255);
+        add_trust(&mut graph, &root_kp, &kps[0], 255);
+        add_trust(&mut graph, &kps[0], &kps[1], 255);
+        add_trust(&mut graph, &kps[1], &kps[2], 255);
+
+        let proof = graph
+            .prove_weight(kps[2].public(), cur_time)
+            .unwrap()
+            .unwrap();
+        assert_eq!(proof.non_revocations.len(), 3);
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: WeightProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            verify_weight_proof(&decoded, cur_time).unwrap(),
+            graph.weight(kps[2].public(), cur_time).unwrap()
+        );
+    }
+}